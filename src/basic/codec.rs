@@ -0,0 +1,257 @@
+//! Registry of the codecs used to detect, read and write compressed streams.
+//!
+//! Built-in formats are registered by default. Other formats can be added at runtime
+//! with [register], their codecs are then used by [sniff](crate::sniff),
+//! [get_reader](crate::get_reader), [get_reader_with_format](crate::get_reader_with_format)
+//! and [get_writer](crate::get_writer). The registry is shared with the functions of
+//! [send](crate::send), they use [Codec::send_decoder] and [Codec::send_encoder].
+
+/* standard use */
+use std::io;
+use std::sync::{Arc, LazyLock, PoisonError, RwLock};
+
+/* project use */
+use crate::basic::compression::{self, Format};
+use crate::builder::{DecoderOptions, EncoderOptions};
+use crate::error::Error;
+use crate::generic::{Decoder, Encoder};
+use crate::level::Level;
+
+/// A compression format: how to recognize it, read it and write it.
+///
+/// # Example
+/// ```
+/// use std::io;
+/// use niffler::{Error, Format, Level};
+///
+/// /// An in-house container, a magic number in front of uncompressed data.
+/// struct Tagged;
+///
+/// impl niffler::codec::Codec for Tagged {
+///     fn format(&self) -> Format {
+///         Format::Custom("tagged")
+///     }
+///
+///     fn matches(&self, first_bytes: &[u8]) -> bool {
+///         first_bytes.starts_with(b"TAG1")
+///     }
+///
+///     fn decoder<'a>(&self, mut in_stream: Box<dyn io::Read + 'a>) -> Result<Box<dyn io::Read + 'a>, Error> {
+///         in_stream.read_exact(&mut [0; 4])?;
+///         Ok(in_stream)
+///     }
+///
+///     fn encoder<'a>(&self, mut out_stream: Box<dyn io::Write + 'a>, _: Level) -> Result<Box<dyn io::Write + 'a>, Error> {
+///         out_stream.write_all(b"TAG1")?;
+///         Ok(out_stream)
+///     }
+/// }
+///
+/// # fn main() -> Result<(), Error> {
+/// niffler::codec::register(Tagged);
+///
+/// let mut buffer = vec![];
+/// {
+///   let mut writer = niffler::get_writer(Box::new(&mut buffer), Format::Custom("tagged"), Level::One)?;
+///   writer.write_all(b"I'm in a tagged container\n")?;
+/// }
+///
+/// let (mut reader, format) = niffler::get_reader(Box::new(&buffer[..]))?;
+/// let mut contents = String::new();
+/// reader.read_to_string(&mut contents)?;
+///
+/// assert_eq!(format, Format::Custom("tagged"));
+/// assert_eq!(contents, "I'm in a tagged container\n");
+/// # Ok(())
+/// # }
+/// ```
+pub trait Codec: Send + Sync {
+    /// Format detected by this codec, it selects the codec in
+    /// [get_reader_with_format](crate::get_reader_with_format) and [get_writer](crate::get_writer).
+    fn format(&self) -> Format;
+
+    /// Check if a stream is in this format, from its first bytes.
+    ///
+    /// Up to 16 bytes are given, fewer for shorter streams, for streams read by
+    /// [get_reader_recursive](crate::get_reader_recursive) after the first layer or by [Format::detect](Format::detect).
+    /// It's called while the registry is locked, it must not register codecs.
+    fn matches(&self, first_bytes: &[u8]) -> bool;
+
+    /// Create a readable stream that decompress `in_stream`.
+    fn decoder<'a>(
+        &self,
+        in_stream: Box<dyn io::Read + 'a>,
+    ) -> Result<Box<dyn io::Read + 'a>, Error>;
+
+    /// Create a writable stream that compress data to `out_stream`.
+    fn encoder<'a>(
+        &self,
+        out_stream: Box<dyn io::Write + 'a>,
+        level: Level,
+    ) -> Result<Box<dyn io::Write + 'a>, Error>;
+
+    /// Similar to [Codec::decoder] but streams are now sendable, used by the functions of
+    /// [send](crate::send). Return [Error::UnsupportedFormat] by default.
+    fn send_decoder<'a>(
+        &self,
+        in_stream: Box<dyn io::Read + Send + 'a>,
+    ) -> Result<Box<dyn io::Read + Send + 'a>, Error> {
+        let _ = in_stream;
+        Err(Error::UnsupportedFormat {
+            format: self.format(),
+            feature: None,
+        })
+    }
+
+    /// Similar to [Codec::encoder] but streams are now sendable, used by the functions of
+    /// [send](crate::send). Return [Error::UnsupportedFormat] by default.
+    fn send_encoder<'a>(
+        &self,
+        out_stream: Box<dyn io::Write + Send + 'a>,
+        level: Level,
+    ) -> Result<Box<dyn io::Write + Send + 'a>, Error> {
+        let _ = (out_stream, level);
+        Err(Error::UnsupportedFormat {
+            format: self.format(),
+            feature: None,
+        })
+    }
+}
+
+/// Codecs of the formats supported by niffler, they match the format given by [compression::bytes2type].
+struct BuiltIn(Format);
+
+impl Codec for BuiltIn {
+    fn format(&self) -> Format {
+        self.0
+    }
+
+    fn matches(&self, first_bytes: &[u8]) -> bool {
+        compression::bytes2type(first_bytes) == self.0
+    }
+
+    fn decoder<'a>(
+        &self,
+        in_stream: Box<dyn io::Read + 'a>,
+    ) -> Result<Box<dyn io::Read + 'a>, Error> {
+        let options = DecoderOptions::default();
+
+        Ok(Box::new(Decoder::with_options(
+            vec![],
+            in_stream,
+            self.0,
+            &options,
+        )?))
+    }
+
+    fn encoder<'a>(
+        &self,
+        out_stream: Box<dyn io::Write + 'a>,
+        level: Level,
+    ) -> Result<Box<dyn io::Write + 'a>, Error> {
+        let options = EncoderOptions {
+            level,
+            ..Default::default()
+        };
+
+        Ok(Box::new(Encoder::with_options(
+            out_stream, self.0, &options,
+        )?))
+    }
+
+    fn send_decoder<'a>(
+        &self,
+        in_stream: Box<dyn io::Read + Send + 'a>,
+    ) -> Result<Box<dyn io::Read + Send + 'a>, Error> {
+        let options = DecoderOptions::default();
+
+        Ok(Box::new(Decoder::with_options(
+            vec![],
+            in_stream,
+            self.0,
+            &options,
+        )?))
+    }
+
+    fn send_encoder<'a>(
+        &self,
+        out_stream: Box<dyn io::Write + Send + 'a>,
+        level: Level,
+    ) -> Result<Box<dyn io::Write + Send + 'a>, Error> {
+        let options = EncoderOptions {
+            level,
+            ..Default::default()
+        };
+
+        Ok(Box::new(Encoder::with_options(
+            out_stream, self.0, &options,
+        )?))
+    }
+}
+
+/// Formats of the built-in codecs.
+const BUILT_IN: [Format; 14] = [
+    Format::Gzip,
+    Format::Bzip,
+    Format::Lzma,
+    Format::LzmaAlone,
+    Format::Zstd,
+    Format::Lz4,
+    Format::Brotli,
+    Format::Snappy,
+    Format::Lzip,
+    Format::Compress,
+    Format::Lzo,
+    Format::Zlib,
+    Format::Deflate,
+    Format::Zip,
+];
+
+/// Built-in codecs, then the codecs added with [register].
+static REGISTRY: LazyLock<RwLock<Vec<Arc<dyn Codec>>>> = LazyLock::new(|| {
+    let built_in = BUILT_IN
+        .into_iter()
+        .map(|format| Arc::new(BuiltIn(format)) as Arc<dyn Codec>);
+
+    RwLock::new(built_in.collect())
+});
+
+/// Add a codec to the registry.
+///
+/// Formats are detected by the registered codecs first, from the last registered one, and
+/// then by the built-in codecs. A codec can replace the codec of a built-in format, streams
+/// in this format are then read and written by it.
+pub fn register<C: Codec + 'static>(codec: C) {
+    REGISTRY
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .push(Arc::new(codec));
+}
+
+/// Built-in and registered codecs, from the first registered one.
+pub fn codecs() -> Vec<Arc<dyn Codec>> {
+    REGISTRY
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
+
+/// Format of the first bytes of a stream, from the last codec of the registry that matches them.
+pub(crate) fn detect(first_bytes: &[u8]) -> Format {
+    REGISTRY
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .iter()
+        .rev()
+        .find(|codec| codec.matches(first_bytes))
+        .map_or(Format::No, |codec| codec.format())
+}
+
+/// Last codec of a format added with [register], built-in codecs aren't returned.
+pub(crate) fn find_registered(format: Format) -> Option<Arc<dyn Codec>> {
+    REGISTRY.read().unwrap_or_else(PoisonError::into_inner)[BUILT_IN.len()..]
+        .iter()
+        .rev()
+        .find(|codec| codec.format() == format)
+        .cloned()
+}
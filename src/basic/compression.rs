@@ -1,147 +1,252 @@
-#![allow(clippy::unnecessary_wraps)]
-
 /* standard use */
 use std::io;
-
-/* crates use */
-use cfg_if::cfg_if;
-
-/* project use */
-use crate::error::Error;
+use std::io::Read;
 
 /* backward compatibility, can remove on 3.x */
 pub use crate::level::Level;
 
 /* Format detection enum */
-/// `Format` represent a compression format of a file. Currently Gzip, Bzip, Lzma or No are supported.
+/// `Format` represent a compression format of a file, [No](Format::No) for uncompressed files.
+///
+/// Formats are read and written when the Cargo feature they need is enabled, more formats
+/// may be added in minor versions.
 #[derive(Debug, Eq, PartialEq, Clone, Copy)]
+#[non_exhaustive]
 pub enum Format {
     Gzip,
     Bzip,
     Lzma,
+    /// Legacy `.lzma` files, as written by LZMA Utils or the LZMA SDK. They have no magic
+    /// number, [sniff](crate::sniff) never returns this format. It's only used when requested
+    /// explicitly or for paths ending with `.lzma`.
+    LzmaAlone,
     Zstd,
+    Lz4,
+    /// Brotli streams have no magic number, [sniff](crate::sniff) never returns this format.
+    /// It's only used when requested explicitly or for paths ending with `.br`.
+    Brotli,
+    /// Snappy framing format, each chunk checksum is verified on read
+    Snappy,
+    /// lzip files, made of one or more LZMA members
+    Lzip,
+    /// Unix `compress` files (`.Z`), using LZW
+    Compress,
+    /// lzop files (`.lzo`), made of blocks compressed with LZO1X
+    Lzo,
+    /// zlib streams, a deflate stream with a 2 bytes header and an Adler-32 checksum. The
+    /// header matches some text, [sniff](crate::sniff) never returns this format. It's only used
+    /// when requested explicitly or for paths ending with `.zz` or `.zlib`.
+    Zlib,
+    /// Raw deflate streams have no header, [sniff](crate::sniff) never returns this format.
+    /// It's only used when requested explicitly.
+    Deflate,
+    /// Zip archives, only the entry of an archive with a single file is read,
+    /// [get_entry_reader](crate::get_entry_reader) reads an entry chosen by name.
+    /// Written archives hold one deflated entry, named `-`, or like the file
+    /// without its extension with [to_path](crate::to_path).
+    Zip,
     No,
+    /// Format of a [Codec](crate::codec::Codec) registered at runtime, identified by its name.
+    Custom(&'static str),
 }
 
 impl Format {
     #[allow(non_upper_case_globals)]
     pub const Xz: Format = Format::Lzma;
-}
 
-pub(crate) fn bytes2type(bytes: [u8; 5]) -> Format {
-    match bytes {
-        [0x1f, 0x8b, ..] => Format::Gzip,
-        [0x42, 0x5a, ..] => Format::Bzip,
-        [0x28, 0xb5, 0x2f, 0xfd, ..] => Format::Zstd,
-        [0xfd, 0x37, 0x7a, 0x58, 0x5a] => Format::Lzma,
-        _ => Format::No,
+    /// Find out the compression format from the first bytes of a stream, without allocation.
+    ///
+    /// Return `None` if more bytes are needed to decide, 16 bytes are always enough. Formats
+    /// of [registered codecs](crate::codec::register) are also detected.
+    ///
+    /// # Example
+    /// ```
+    /// use niffler::Format;
+    ///
+    /// assert_eq!(Format::detect(&[0x1f, 0x8b, 0x08, 0x00]), Some(Format::Gzip));
+    /// assert_eq!(Format::detect(&[0x1f]), None);
+    /// assert_eq!(Format::detect(b"Not compressed at all"), Some(Format::No));
+    /// ```
+    pub fn detect(bytes: &[u8]) -> Option<Format> {
+        let format = crate::basic::codec::detect(bytes);
+
+        let decided = bytes.len() >= crate::utils::MAGIC_LEN
+            || (format != Format::No && !is_before_next_frame(bytes));
+        decided.then_some(format)
     }
-}
 
-cfg_if! {
-    if #[cfg(feature = "gz")] {
-        pub(crate) fn new_gz_encoder<'a>(out: Box<dyn io::Write  + 'a>, level: Level) -> Result<Box<dyn io::Write + 'a>, Error> {
-            Ok(Box::new(flate2::write::GzEncoder::new(
-        out,
-        level.into(),
-            )))
+    /// Compression format usually given by a file extension, with or without the leading dot.
+    ///
+    /// Compound extensions like `tar.gz` or `fq.gz` give the format of their last part, and
+    /// `tgz` like extensions are supported. BGZF files are gzip files, `bgz` gives
+    /// [Gzip](Format::Gzip). Return `None` for extensions of uncompressed files.
+    ///
+    /// # Example
+    /// ```
+    /// use niffler::Format;
+    ///
+    /// assert_eq!(Format::from_extension("zst"), Some(Format::Zstd));
+    /// assert_eq!(Format::from_extension(".tar.gz"), Some(Format::Gzip));
+    /// assert_eq!(Format::from_extension("fastq"), None);
+    /// ```
+    pub fn from_extension(extension: &str) -> Option<Format> {
+        let extension = extension.rsplit('.').next().unwrap_or_default();
+
+        // `.Z` is compress, `.z` was used by pack and early gzip versions
+        if extension == "Z" {
+            return Some(Format::Compress);
         }
 
-        pub(crate) fn new_gz_decoder<'a>(
-            inp: Box<dyn io::Read  + 'a>,
-        ) -> Result<(Box<dyn io::Read  + 'a>, Format), Error> {
-            Ok((
-        Box::new(flate2::read::MultiGzDecoder::new(inp)),
-        Format::Gzip,
-            ))
-        }
-    } else {
-        pub(crate) fn new_gz_encoder<'a>(_: Box<dyn io::Write  + 'a>, _: Level) -> Result<Box<dyn io::Write  + 'a>, Error> {
-            Err(Error::FeatureDisabled)
-        }
-
-        pub(crate) fn new_gz_decoder<'a>(_: Box<dyn io::Read  + 'a>) -> Result<(Box<dyn io::Read  + 'a>, Format), Error> {
-            Err(Error::FeatureDisabled)
+        match extension.to_ascii_lowercase().as_str() {
+            "gz" | "gzip" | "bgz" | "tgz" => Some(Format::Gzip),
+            "bz2" | "bz" | "bzip2" | "tbz" | "tbz2" => Some(Format::Bzip),
+            "xz" | "txz" => Some(Format::Lzma),
+            "lzma" | "tlz" => Some(Format::LzmaAlone),
+            "zst" | "zstd" | "tzst" => Some(Format::Zstd),
+            "lz4" => Some(Format::Lz4),
+            "br" => Some(Format::Brotli),
+            "sz" => Some(Format::Snappy),
+            "lz" => Some(Format::Lzip),
+            "lzo" => Some(Format::Lzo),
+            "zz" | "zlib" => Some(Format::Zlib),
+            "zip" => Some(Format::Zip),
+            _ => None,
         }
     }
-}
 
-cfg_if! {
-    if #[cfg(feature = "bz2")] {
-        pub(crate) fn new_bz2_encoder<'a>(out: Box<dyn io::Write  + 'a>, level: Level) -> Result<Box<dyn io::Write  + 'a>, Error> {
-            Ok(Box::new(bzip2::write::BzEncoder::new(
-                out,
-                level.into(),
-            )))
-        }
+    /// Compression format given by the extension of a path, see [from_extension](Format::from_extension).
+    ///
+    /// It's used by [to_path_auto](crate::to_path_auto) to choose the format of a new file.
+    pub fn from_path<P: AsRef<std::path::Path>>(path: P) -> Option<Format> {
+        path.as_ref()
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(Format::from_extension)
+    }
 
-        pub(crate) fn new_bz2_decoder<'a>(
-            inp: Box<dyn io::Read  + 'a>,
-        ) -> Result<(Box<dyn io::Read  + 'a>, Format), Error> {
-            Ok((
-                Box::new(bzip2::read::MultiBzDecoder::new(inp)),
-                Format::Bzip,
-            ))
-        }
-    } else {
-        pub(crate) fn new_bz2_encoder<'a>(_: Box<dyn io::Write  + 'a>, _: Level) -> Result<Box<dyn io::Write  + 'a>, Error> {
-            Err(Error::FeatureDisabled)
-        }
+    /// The format has no magic number, [from_path](crate::from_path) reads files in this format
+    /// when their extension gives it.
+    pub(crate) fn is_undetectable(self) -> bool {
+        matches!(
+            self,
+            Format::Brotli | Format::LzmaAlone | Format::Zlib | Format::Deflate
+        )
+    }
 
-        pub(crate) fn new_bz2_decoder<'a>(_: Box<dyn io::Read  + 'a>) -> Result<(Box<dyn io::Read  + 'a>, Format), Error> {
-            Err(Error::FeatureDisabled)
+    /// Cargo feature needed to read and write this format, `None` if it's always available.
+    pub(crate) fn feature(self) -> Option<&'static str> {
+        match self {
+            Format::Gzip | Format::Zlib | Format::Deflate => Some("gz"),
+            Format::Bzip => Some("bz2"),
+            Format::Lzma | Format::LzmaAlone => Some("lzma"),
+            Format::Zstd => Some("zstd"),
+            Format::Lz4 => Some("lz4"),
+            Format::Brotli => Some("brotli"),
+            Format::Snappy => Some("snappy"),
+            Format::Lzip => Some("lzip"),
+            Format::Compress => Some("compress"),
+            Format::Lzo => Some("lzo"),
+            Format::Zip => Some("zip"),
+            Format::No | Format::Custom(_) => None,
         }
     }
 }
 
-cfg_if! {
-    if #[cfg(feature = "lzma")] {
-    pub(crate) fn new_lzma_encoder<'a>(out: Box<dyn io::Write  + 'a>, level: Level) -> Result<Box<dyn io::Write  + 'a>, Error> {
-            Ok(Box::new(liblzma::write::XzEncoder::new(out, level.into())))
-    }
+/// The extension of a file doesn't match its compression format, returned by
+/// [from_path_checked](crate::from_path_checked).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExtensionMismatch {
+    /// Format given by the extension, [No](Format::No) for extensions of uncompressed files
+    pub expected: Format,
+    /// Format detected from the content of the file
+    pub detected: Format,
+}
 
-    pub(crate) fn new_lzma_decoder<'a>(
-            inp: Box<dyn io::Read  + 'a>,
-    ) -> Result<(Box<dyn io::Read  + 'a>, Format), Error> {
-            Ok((
-        Box::new(liblzma::read::XzDecoder::new(inp)),
-        Format::Lzma,
-            ))
-    }
-    } else {
-    pub(crate) fn new_lzma_encoder<'a>(_: Box<dyn io::Write  + 'a>, _: Level) -> Result<Box<dyn io::Write  + 'a>, Error> {
-            Err(Error::FeatureDisabled)
-    }
+/// Payload of a skippable frame, zstd and LZ4 decoders ignore these frames.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippableFrame {
+    /// Low 4 bits of the frame magic number, applications can use it to tag frames.
+    pub variant: u8,
+    pub data: Vec<u8>,
+}
 
-    pub(crate) fn new_lzma_decoder<'a>(_: Box<dyn io::Read  + 'a>) -> Result<(Box<dyn io::Read  + 'a>, Format), Error> {
-            Err(Error::FeatureDisabled)
-    }
+pub(crate) fn bytes2type(bytes: &[u8]) -> Format {
+    match bytes {
+        [0x1f, 0x8b, ..] => Format::Gzip,
+        [0x42, 0x5a, ..] => Format::Bzip,
+        [0x1f, 0x9d, ..] => Format::Compress,
+        [0x28, 0xb5, 0x2f, 0xfd, ..] => Format::Zstd,
+        [0x25..=0x27, 0xb5, 0x2f, 0xfd, ..] => Format::Zstd,
+        [0x50..=0x5f, 0x2a, 0x4d, 0x18, ..] if is_lz4_after_skippable_frame(bytes) => Format::Lz4,
+        [0x50..=0x5f, 0x2a, 0x4d, 0x18, ..] => Format::Zstd,
+        [0xfd, 0x37, 0x7a, 0x58, 0x5a, ..] => Format::Lzma,
+        [0x04, 0x22, 0x4d, 0x18, ..] | [0x02, 0x21, 0x4c, 0x18, ..] => Format::Lz4,
+        [0xff, 0x06, 0x00, 0x00, 0x73, 0x4e, 0x61, 0x50, 0x70, 0x59, ..] => Format::Snappy,
+        [0x4c, 0x5a, 0x49, 0x50, ..] => Format::Lzip,
+        [0x89, 0x4c, 0x5a, 0x4f, 0x00, 0x0d, 0x0a, 0x1a, 0x0a, ..] => Format::Lzo,
+        [0x50, 0x4b, 0x03, 0x04, ..] => Format::Zip,
+        _ => Format::No,
     }
 }
 
-cfg_if! {
-    if #[cfg(feature = "zstd")] {
-        pub(crate) fn new_zstd_encoder<'a>(out: Box<dyn io::Write  + 'a>, level: Level) -> Result<Box<dyn io::Write  + 'a>, Error> {
-            Ok(Box::new(zstd::stream::write::Encoder::new(
-                        out,
-                        level.into(),
-            )?.auto_finish()))
+/// zstd and LZ4 share skippable frames, the format is given by the frame that follows
+/// them when it's in `bytes`, otherwise zstd is assumed as its tools use them the most,
+/// e.g. `pzstd` writes one before each frame.
+pub(crate) fn is_lz4_after_skippable_frame(bytes: &[u8]) -> bool {
+    let next = match bytes {
+        [_, _, _, _, s0, s1, s2, s3, ..] => {
+            let size = u32::from_le_bytes([*s0, *s1, *s2, *s3]) as usize;
+            bytes.get(size.saturating_add(8)..).unwrap_or_default()
         }
+        _ => &[],
+    };
 
-        pub(crate) fn new_zstd_decoder<'a>(
-            inp: Box<dyn io::Read  + 'a>,
-        ) -> Result<(Box<dyn io::Read  + 'a>, Format), Error> {
-            Ok((Box::new(zstd::stream::read::Decoder::new(inp)?),
-                         Format::Zstd,
-            ))
-        }
-    } else {
-        pub(crate) fn new_zstd_encoder<'a>(_: Box<dyn io::Write  + 'a>, _: Level) -> Result<Box<dyn io::Write  + 'a>, Error> {
-            Err(Error::FeatureDisabled)
+    bytes2type(next) == Format::Lz4
+}
+
+/// Check if bytes end in the skippable frames at the start of a stream, the format is
+/// given by the next frame.
+pub(crate) fn is_before_next_frame(bytes: &[u8]) -> bool {
+    match bytes {
+        [0x50..=0x5f, 0x2a, 0x4d, 0x18, s0, s1, s2, s3, ..] => {
+            let size = u32::from_le_bytes([*s0, *s1, *s2, *s3]) as usize;
+            bytes.len() < size.saturating_add(12)
         }
+        [0x50..=0x5f, 0x2a, 0x4d, 0x18, ..] => true,
+        _ => false,
+    }
+}
 
-        pub(crate) fn new_zstd_decoder<'a>(_: Box<dyn io::Read  + 'a>) -> Result<(Box<dyn io::Read  + 'a>, Format), Error> {
-            Err(Error::FeatureDisabled)
+/// Read the skippable frames at the start of a stream.
+///
+/// Also returns the bytes read after them, up to 8, that must be put back in front of the stream.
+pub(crate) fn read_skippable_frames<R: io::Read>(
+    mut inner: R,
+) -> io::Result<(Vec<SkippableFrame>, Vec<u8>, R)> {
+    let mut frames = vec![];
+
+    loop {
+        let mut header = Vec::with_capacity(8);
+        (&mut inner).take(8).read_to_end(&mut header)?;
+
+        match header[..] {
+            [magic @ 0x50..=0x5f, 0x2a, 0x4d, 0x18, s0, s1, s2, s3] => {
+                let size = u64::from(u32::from_le_bytes([s0, s1, s2, s3]));
+                let mut data = vec![];
+                (&mut inner).take(size).read_to_end(&mut data)?;
+                if (data.len() as u64) < size {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "skippable frame is truncated",
+                    ));
+                }
+
+                frames.push(SkippableFrame {
+                    variant: magic & 0x0f,
+                    data,
+                });
+            }
+            _ => return Ok((frames, header, inner)),
         }
     }
 }
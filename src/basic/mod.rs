@@ -1,3 +1,4 @@
+pub mod codec;
 pub mod compression;
 
 /* standard use */
@@ -6,6 +7,8 @@ use std::io::Read;
 use std::path::Path;
 
 /* project use */
+use crate::builder::{ReaderBuilder, WriterBuilder};
+use crate::content::Content;
 use crate::error::Error;
 use crate::level::Level;
 
@@ -37,21 +40,157 @@ use crate::level::Level;
 pub fn sniff<'a>(
     in_stream: Box<dyn io::Read + 'a>,
 ) -> Result<(Box<dyn io::Read + 'a>, compression::Format), Error> {
-    let (first_bytes, in_stream) = crate::utils::get_first_five(in_stream)?;
-
-    let cursor = io::Cursor::new(first_bytes);
-    match compression::bytes2type(first_bytes) {
-        e @ compression::Format::Gzip
-        | e @ compression::Format::Bzip
-        | e @ compression::Format::Lzma
-        | e @ compression::Format::Zstd => Ok((Box::new(cursor.chain(in_stream)), e)),
-        _ => Ok((Box::new(cursor.chain(in_stream)), compression::Format::No)),
+    let (first_bytes, in_stream) = crate::utils::get_first_bytes_read(in_stream)?;
+
+    let format = codec::detect(&first_bytes);
+
+    Ok((
+        Box::new(io::Cursor::new(first_bytes).chain(in_stream)),
+        format,
+    ))
+}
+
+/// Similar to [sniff] but the buffer of a [BufRead](io::BufRead) is peeked without consuming it.
+///
+/// The format is found out with [Format::detect](compression::Format::detect) from the bytes
+/// already buffered. If they are too few to decide, up to 16 bytes are read until the format
+/// is known or the stream ends, they are kept by the returned [Sniffed] stream and read again
+/// before the rest of the stream.
+///
+/// # Example
+/// ```
+/// # fn main() -> Result<(), niffler::Error> {
+/// use std::io::BufRead;
+///
+/// let data = [
+///         0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0xf3, 0x54, 0xcf, 0x55,
+///         0x48, 0xce, 0xcf, 0x2d, 0x28, 0x4a, 0x2d, 0x2e, 0x56, 0xc8, 0xcc, 0x53, 0x48, 0xaf,
+///         0xca, 0x2c, 0xe0, 0x02, 0x00, 0x45, 0x7c, 0xf4, 0x10, 0x15, 0x00, 0x00, 0x00
+///         ];
+///
+/// let reader = std::io::BufReader::new(&data[..]);
+/// let (mut reader, compression) = niffler::sniff_bufread(reader)?;
+///
+/// assert_eq!(compression, niffler::compression::Format::Gzip);
+/// assert_eq!(reader.fill_buf()?, data);
+/// # Ok(())
+/// # }
+/// ```
+pub fn sniff_bufread<R: io::BufRead>(
+    in_stream: R,
+) -> Result<(Sniffed<R>, compression::Format), Error> {
+    let mut in_stream = Sniffed::new(in_stream);
+    in_stream.peek(|bytes| compression::Format::detect(bytes).is_some())?;
+
+    let first_bytes = io::BufRead::fill_buf(&mut in_stream)?;
+    if first_bytes.len() < 5 {
+        return Err(Error::FileTooShort);
+    }
+
+    // short streams are decided with all their bytes
+    let format =
+        compression::Format::detect(first_bytes).unwrap_or_else(|| codec::detect(first_bytes));
+
+    Ok((in_stream, format))
+}
+
+/// A [BufRead](io::BufRead) returned by [sniff_bufread], the bytes read to find out the format
+/// come first, then the wrapped stream.
+///
+/// When the buffer of the wrapped stream was enough to find out the format, no byte was read
+/// and reads go straight to the wrapped stream.
+pub struct Sniffed<R> {
+    peeked: Vec<u8>,
+    pos: usize,
+    inner: R,
+}
+
+impl<R: io::BufRead> Sniffed<R> {
+    /// Wrap `inner` without reading it.
+    pub(crate) fn new(inner: R) -> Self {
+        Sniffed {
+            peeked: Vec::new(),
+            pos: 0,
+            inner,
+        }
+    }
+
+    /// Read the start of the wrapped stream until [MAGIC_LEN](crate::utils::MAGIC_LEN) bytes
+    /// are there, `decided` tells if fewer bytes are enough.
+    pub(crate) fn peek(&mut self, decided: impl Fn(&[u8]) -> bool) -> io::Result<()> {
+        let buffered = self.inner.fill_buf()?;
+        if buffered.len() >= crate::utils::MAGIC_LEN || decided(buffered) {
+            return Ok(());
+        }
+
+        while self.peeked.len() < crate::utils::MAGIC_LEN && !decided(&self.peeked) {
+            let buffered = match self.inner.fill_buf() {
+                Ok(buffered) => buffered,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            if buffered.is_empty() {
+                break;
+            }
+
+            let len = buffered
+                .len()
+                .min(crate::utils::MAGIC_LEN - self.peeked.len());
+            self.peeked.extend_from_slice(&buffered[..len]);
+            self.inner.consume(len);
+        }
+
+        Ok(())
+    }
+}
+
+impl<R> Sniffed<R> {
+    /// The wrapped stream, the bytes read to find out the format aren't in it.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+}
+
+impl<R: io::BufRead> io::Read for Sniffed<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos == self.peeked.len() {
+            return self.inner.read(buf);
+        }
+
+        let len = buf.len().min(self.peeked.len() - self.pos);
+        buf[..len].copy_from_slice(&self.peeked[self.pos..self.pos + len]);
+        self.pos += len;
+
+        Ok(len)
+    }
+}
+
+impl<R: io::BufRead> io::BufRead for Sniffed<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.pos == self.peeked.len() {
+            return self.inner.fill_buf();
+        }
+
+        Ok(&self.peeked[self.pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        if self.pos == self.peeked.len() {
+            self.inner.consume(amt);
+        } else {
+            self.pos = (self.pos + amt).min(self.peeked.len());
+        }
     }
 }
 
 /// Create a readable stream that can be read transparently even if the original stream is compress.
 /// Also returns the compression type of the original stream.
 ///
+/// Zip archives are read as a stream, without their central directory: the content of their
+/// first file is returned, then reading fails with [Error::MultipleEntries] if another file
+/// follows. [from_path] checks that an archive has a single file before reading it, and
+/// [get_entry_reader] reads a file of an archive with several files.
+///
 /// # Example
 /// ```
 /// use niffler::{Error, get_reader};
@@ -78,17 +217,198 @@ pub fn sniff<'a>(
 pub fn get_reader<'a>(
     in_stream: Box<dyn io::Read + 'a>,
 ) -> Result<(Box<dyn io::Read + 'a>, compression::Format), Error> {
-    // check compression
-    let (in_stream, compression) = sniff(in_stream)?;
-
-    // return readable and compression status
-    match compression {
-        compression::Format::Gzip => compression::new_gz_decoder(in_stream),
-        compression::Format::Bzip => compression::new_bz2_decoder(in_stream),
-        compression::Format::Lzma => compression::new_lzma_decoder(in_stream),
-        compression::Format::Zstd => compression::new_zstd_decoder(in_stream),
-        compression::Format::No => Ok((in_stream, compression::Format::No)),
+    ReaderBuilder::new().get_reader(in_stream)
+}
+
+/// Create a readable stream with all compression layers removed, for streams compressed
+/// several times like `file.gz.gz` or zstd inside gzip.
+///
+/// The decompressed stream is sniffed again until it isn't compressed, or `max_depth` layers
+/// were removed: the stream can then still be compressed. Also returns the compression format
+/// of each layer, from the outermost one, it's empty if the stream isn't compressed.
+///
+/// # Example
+/// ```
+/// use niffler::{Error, compression};
+/// # fn main() -> Result<(), Error> {
+///
+/// # #[cfg(all(feature = "gz", feature = "zstd"))] {
+/// let mut buffer = vec![];
+/// {
+///   // data is compressed with gzip, then with zstd
+///   let zstd = niffler::get_writer(Box::new(&mut buffer), compression::Format::Zstd, niffler::Level::One)?;
+///   let mut writer = niffler::get_writer(zstd, compression::Format::Gzip, niffler::Level::One)?;
+///   writer.write_all(b"I'm compress in gzip then zstd\n")?;
+/// }
+///
+/// let (mut reader, formats) = niffler::get_reader_recursive(Box::new(&buffer[..]), 8)?;
+///
+/// let mut contents = String::new();
+/// reader.read_to_string(&mut contents)?;
+///
+/// assert_eq!(formats, [compression::Format::Zstd, compression::Format::Gzip]);
+/// assert_eq!(contents, "I'm compress in gzip then zstd\n");
+/// # }
+/// # Ok(())
+/// # }
+/// ```
+pub fn get_reader_recursive<'a>(
+    in_stream: Box<dyn io::Read + 'a>,
+    max_depth: usize,
+) -> Result<(Box<dyn io::Read + 'a>, Vec<compression::Format>), Error> {
+    let (mut reader, mut format) = sniff(in_stream)?;
+    let mut formats = vec![];
+
+    while format != compression::Format::No && formats.len() < max_depth {
+        (reader, format) = get_reader_with_format(reader, format)?;
+        formats.push(format);
+
+        // decompressed data can be shorter than a magic number
+        let (first_bytes, inner) = crate::utils::read_first_bytes(reader)?;
+        format = codec::detect(&first_bytes);
+        reader = Box::new(io::Cursor::new(first_bytes).chain(inner));
+    }
+
+    Ok((reader, formats))
+}
+
+/// Create a readable stream that decompress the original stream with the given compression format,
+/// without trying to detect it.
+///
+/// This is the only way to read formats without magic number, like
+/// [Brotli](compression::Format::Brotli).
+///
+/// # Example
+/// ```
+/// use niffler::{Error, compression};
+/// # fn main() -> Result<(), Error> {
+///
+/// # #[cfg(feature = "brotli")] {
+/// let mut buffer = vec![];
+/// {
+///   let mut writer = niffler::get_writer(Box::new(&mut buffer), compression::Format::Brotli, niffler::Level::One)?;
+///   writer.write_all(b"I'm compress in brotli\n")?;
+/// }
+///
+/// let (mut reader, _) = niffler::get_reader_with_format(Box::new(&buffer[..]), compression::Format::Brotli)?;
+///
+/// let mut contents = String::new();
+/// reader.read_to_string(&mut contents)?;
+///
+/// assert_eq!(contents, "I'm compress in brotli\n");
+/// # }
+/// # Ok(())
+/// # }
+/// ```
+pub fn get_reader_with_format<'a>(
+    in_stream: Box<dyn io::Read + 'a>,
+    format: compression::Format,
+) -> Result<(Box<dyn io::Read + 'a>, compression::Format), Error> {
+    ReaderBuilder::new().format(format).get_reader(in_stream)
+}
+
+/// Create a readable stream of the entry called `entry` in an archive.
+///
+/// [get_reader] only reads zip archives containing a single file, this function reads
+/// the chosen entry of any zip archive. Tar archives are also supported with the `tar`
+/// feature, compressed in any format: entries before the chosen one are skipped and the
+/// returned format is the compression of the archive.
+///
+/// # Example
+/// ```
+/// use niffler::{Error, compression};
+/// # fn main() -> Result<(), Error> {
+///
+/// # #[cfg(feature = "zip")] {
+/// let mut buffer = vec![];
+/// {
+///   let mut writer = niffler::get_writer(Box::new(&mut buffer), compression::Format::Zip, niffler::Level::One)?;
+///   writer.write_all(b"I'm compress in zip\n")?;
+/// }
+///
+/// // entries written by get_writer are named "-", like the ones created by `zip` from its standard input
+/// let (mut reader, _) = niffler::get_entry_reader(Box::new(&buffer[..]), "-")?;
+///
+/// let mut contents = String::new();
+/// reader.read_to_string(&mut contents)?;
+///
+/// assert_eq!(contents, "I'm compress in zip\n");
+/// # }
+/// # Ok(())
+/// # }
+/// ```
+#[cfg_attr(not(any(feature = "zip", feature = "tar")), allow(unused_variables))]
+pub fn get_entry_reader<'a>(
+    in_stream: Box<dyn io::Read + 'a>,
+    entry: &str,
+) -> Result<(Box<dyn io::Read + 'a>, compression::Format), Error> {
+    let (in_stream, format) = sniff(in_stream)?;
+
+    #[cfg(feature = "zip")]
+    if format == compression::Format::Zip {
+        let reader = crate::formats::zip::ZipDecoder::new(in_stream, Some(entry))?;
+        return Ok((Box::new(reader), format));
+    }
+    #[cfg(not(feature = "zip"))]
+    if format == compression::Format::Zip {
+        return Err(Error::feature_disabled(format));
     }
+
+    // other archives are tar archives, possibly compressed
+    #[cfg(feature = "tar")]
+    {
+        let (reader, format) = get_reader_with_format(in_stream, format)?;
+        let tar = crate::tar::check_tar(reader)?;
+
+        Ok((Box::new(crate::tar::find_entry(tar, entry)?), format))
+    }
+
+    #[cfg(not(feature = "tar"))]
+    Err(Error::NotAnArchive)
+}
+
+/// Read the skippable frames at the start of a zstd or LZ4 stream, some tools use them
+/// to store metadata before the compressed data.
+///
+/// Return their payloads and the rest of the stream, that can be given to [get_reader].
+/// Decoders skip the skippable frames found later in the stream.
+///
+/// # Example
+/// ```
+/// use niffler::{Error, compression};
+/// # fn main() -> Result<(), Error> {
+///
+/// # #[cfg(feature = "zstd")] {
+/// let mut buffer = vec![0x5e, 0x2a, 0x4d, 0x18, 0x05, 0x00, 0x00, 0x00];
+/// buffer.extend(b"hello");
+/// {
+///   let mut writer = niffler::get_writer(Box::new(&mut buffer), compression::Format::Zstd, niffler::Level::One)?;
+///   writer.write_all(b"I'm compress in zstd\n")?;
+/// }
+///
+/// let (frames, stream) = niffler::read_skippable_frames(Box::new(&buffer[..]))?;
+/// assert_eq!(frames[0].variant, 0xe);
+/// assert_eq!(frames[0].data, b"hello");
+///
+/// let (mut reader, compression) = niffler::get_reader(stream)?;
+/// let mut contents = String::new();
+/// reader.read_to_string(&mut contents)?;
+///
+/// assert_eq!(compression, compression::Format::Zstd);
+/// assert_eq!(contents, "I'm compress in zstd\n");
+/// # }
+/// # Ok(())
+/// # }
+/// ```
+pub fn read_skippable_frames<'a>(
+    in_stream: Box<dyn io::Read + 'a>,
+) -> Result<(Vec<compression::SkippableFrame>, Box<dyn io::Read + 'a>), Error> {
+    let (frames, first_bytes, in_stream) = compression::read_skippable_frames(in_stream)?;
+
+    Ok((
+        frames,
+        Box::new(io::Cursor::new(first_bytes).chain(in_stream)),
+    ))
 }
 
 /// Create a new writable stream with the given compression format and level.
@@ -123,16 +443,15 @@ pub fn get_writer<'a>(
     format: compression::Format,
     level: Level,
 ) -> Result<Box<dyn io::Write + 'a>, Error> {
-    match format {
-        compression::Format::Gzip => compression::new_gz_encoder(out_stream, level),
-        compression::Format::Bzip => compression::new_bz2_encoder(out_stream, level),
-        compression::Format::Lzma => compression::new_lzma_encoder(out_stream, level),
-        compression::Format::Zstd => compression::new_zstd_encoder(out_stream, level),
-        compression::Format::No => Ok(Box::new(out_stream)),
-    }
+    WriterBuilder::new(format)
+        .level(level)
+        .get_writer(out_stream)
 }
 
 /// Open a possibly compressed file and decompress it transparently.
+///
+/// Files with a `.br` or `.lzma` extension are read as [Brotli](compression::Format::Brotli) or
+/// [LzmaAlone](compression::Format::LzmaAlone), since these formats can't be detected.
 /// ```
 /// use niffler::{Error, compression};
 /// # fn main() -> Result<(), Error> {
@@ -158,8 +477,203 @@ pub fn get_writer<'a>(
 pub fn from_path<'a, P: AsRef<Path>>(
     path: P,
 ) -> Result<(Box<dyn io::Read + 'a>, compression::Format), Error> {
-    let readable = io::BufReader::new(std::fs::File::open(path)?);
-    get_reader(Box::new(readable))
+    ReaderBuilder::new().from_path(path)
+}
+
+/// Similar to [get_reader] but the decompressed stream is buffered, with a buffer of `capacity` bytes,
+/// ready for line oriented parsing.
+///
+/// # Example
+/// ```
+/// use std::io::BufRead;
+/// use niffler::{Error, compression};
+/// # fn main() -> Result<(), Error> {
+///
+/// # #[cfg(feature = "gz")] {
+/// let mut buffer = vec![];
+/// {
+///   let mut writer = niffler::get_writer(Box::new(&mut buffer), compression::Format::Gzip, niffler::Level::One)?;
+///   writer.write_all(b"@read1\nACGT\n+\nIIII\n")?;
+/// }
+///
+/// let (reader, compression) = niffler::get_bufread(Box::new(&buffer[..]), 64 * 1024)?;
+/// let lines = reader.lines().collect::<Result<Vec<_>, _>>()?;
+///
+/// assert_eq!(compression, compression::Format::Gzip);
+/// assert_eq!(lines, ["@read1", "ACGT", "+", "IIII"]);
+/// # }
+/// # Ok(())
+/// # }
+/// ```
+pub fn get_bufread<'a>(
+    in_stream: Box<dyn io::Read + 'a>,
+    capacity: usize,
+) -> Result<(Box<dyn io::BufRead + 'a>, compression::Format), Error> {
+    let (reader, format) = get_reader(in_stream)?;
+
+    Ok((
+        Box::new(io::BufReader::with_capacity(capacity, reader)),
+        format,
+    ))
+}
+
+/// Similar to [from_path] but the decompressed stream is buffered, with a buffer of `capacity` bytes.
+///
+/// The file is read through this buffer too: uncompressed files are buffered once, and returned
+/// without copy by [BufRead::fill_buf](io::BufRead::fill_buf).
+pub fn from_path_bufread<'a, P: AsRef<Path>>(
+    path: P,
+    capacity: usize,
+) -> Result<(Box<dyn io::BufRead + 'a>, compression::Format), Error> {
+    ReaderBuilder::new()
+        .output_buffer_size(capacity)
+        .from_path_bufread(path)
+}
+
+/// What [from_path_checked] does when the extension of a file doesn't match its compression format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExtensionCheck {
+    /// Read the file and return the mismatch
+    Report,
+    /// Fail with [Error::ExtensionMismatch]
+    Reject,
+}
+
+/// Stream, compression format and extension mismatch returned by [from_path_checked].
+type CheckedReader<'a> = (
+    Box<dyn io::Read + 'a>,
+    compression::Format,
+    Option<compression::ExtensionMismatch>,
+);
+
+/// Open a possibly compressed file like [from_path], and check that its extension matches
+/// its compression format.
+///
+/// The extension gives a format with [Format::from_path](compression::Format::from_path),
+/// extensions of uncompressed files are expected to be uncompressed. With
+/// [ExtensionCheck::Report] a mismatch is returned with the stream, with
+/// [ExtensionCheck::Reject] it's an error.
+///
+/// # Example
+/// ```
+/// use niffler::{Error, ExtensionCheck, compression};
+/// # fn main() -> Result<(), Error> {
+///
+/// # #[cfg(feature = "zstd")] {
+/// # let dir = tempfile::tempdir()?;
+/// let path = dir.path().join("reads.fq.gz");
+/// {
+///   let mut writer = niffler::to_path(&path, compression::Format::Zstd, niffler::Level::One)?;
+///   writer.write_all(b"@read1\nACGT\n+\nIIII\n")?;
+/// }
+///
+/// let (_, format, mismatch) = niffler::from_path_checked(&path, ExtensionCheck::Report)?;
+/// assert_eq!(format, compression::Format::Zstd);
+/// assert_eq!(mismatch.map(|m| m.expected), Some(compression::Format::Gzip));
+///
+/// assert!(matches!(
+///     niffler::from_path_checked(&path, ExtensionCheck::Reject),
+///     Err(Error::ExtensionMismatch { .. })
+/// ));
+/// # }
+/// # Ok(())
+/// # }
+/// ```
+pub fn from_path_checked<'a, P: AsRef<Path>>(
+    path: P,
+    check: ExtensionCheck,
+) -> Result<CheckedReader<'a>, Error> {
+    let path = path.as_ref();
+    let (reader, detected) = from_path(path)?;
+
+    let expected = compression::Format::from_path(path).unwrap_or(compression::Format::No);
+    if expected == detected {
+        return Ok((reader, detected, None));
+    }
+
+    match check {
+        ExtensionCheck::Report => Ok((
+            reader,
+            detected,
+            Some(compression::ExtensionMismatch { expected, detected }),
+        )),
+        ExtensionCheck::Reject => Err(Error::ExtensionMismatch { expected, detected }),
+    }
+}
+
+/// Find out the content of a decompressed stream, like FASTA or FASTQ, without consuming it.
+///
+/// The first [CONTENT_PEEK_LEN](crate::content::CONTENT_PEEK_LEN) bytes are read and put back
+/// in front of the returned stream.
+///
+/// # Example
+/// ```
+/// use niffler::{Error, compression, Content};
+/// # fn main() -> Result<(), Error> {
+///
+/// # #[cfg(feature = "gz")] {
+/// let mut buffer = vec![];
+/// {
+///   let mut writer = niffler::get_writer(Box::new(&mut buffer), compression::Format::Gzip, niffler::Level::One)?;
+///   writer.write_all(b">seq1\nACGT\n")?;
+/// }
+///
+/// let (reader, format) = niffler::get_reader(Box::new(&buffer[..]))?;
+/// let (mut reader, content) = niffler::sniff_content(reader)?;
+///
+/// let mut contents = String::new();
+/// reader.read_to_string(&mut contents)?;
+///
+/// assert_eq!(format, compression::Format::Gzip);
+/// assert_eq!(content, Content::Fasta);
+/// assert_eq!(contents, ">seq1\nACGT\n");
+/// # }
+/// # Ok(())
+/// # }
+/// ```
+pub fn sniff_content<'a>(
+    in_stream: Box<dyn io::Read + 'a>,
+) -> Result<(Box<dyn io::Read + 'a>, Content), Error> {
+    let (first_bytes, in_stream) =
+        crate::utils::read_up_to(in_stream, crate::content::CONTENT_PEEK_LEN)?;
+    let content = Content::detect(&first_bytes);
+
+    Ok((
+        Box::new(io::Cursor::new(first_bytes).chain(in_stream)),
+        content,
+    ))
+}
+
+/// Open a possibly compressed file, decompress it transparently and find out its content.
+///
+/// This is [from_path] followed by [sniff_content], the content can select the parser
+/// to use for the returned stream.
+/// ```
+/// use niffler::{Error, compression, Content};
+/// # fn main() -> Result<(), Error> {
+///
+/// # #[cfg(feature = "gz")] {
+/// # let file = tempfile::NamedTempFile::new()?;
+///
+/// # {
+/// #   let mut writer = niffler::to_path(file.path(), compression::Format::Gzip, niffler::Level::Nine)?;
+/// #   writer.write_all(b"@read1\nACGT\n+\nIIII\n")?;
+/// # }
+/// let (reader, format, content) = niffler::open_any(file.path())?;
+///
+/// assert_eq!(format, compression::Format::Gzip);
+/// assert_eq!(content, Content::Fastq);
+/// # }
+/// # Ok(())
+/// # }
+/// ```
+pub fn open_any<'a, P: AsRef<Path>>(
+    path: P,
+) -> Result<(Box<dyn io::Read + 'a>, compression::Format, Content), Error> {
+    let (reader, format) = from_path(path)?;
+    let (reader, content) = sniff_content(reader)?;
+
+    Ok((reader, format, content))
 }
 
 /// Create a file with specific compression format.
@@ -188,21 +702,254 @@ pub fn to_path<'a, P: AsRef<Path>>(
     format: compression::Format,
     level: Level,
 ) -> Result<Box<dyn io::Write + 'a>, Error> {
-    let writable = io::BufWriter::new(std::fs::File::create(path)?);
-    get_writer(Box::new(writable), format, level)
+    WriterBuilder::new(format).level(level).to_path(path)
 }
 
-#[cfg(test)]
-mod test {
-
-    use super::*;
-    use tempfile::NamedTempFile;
-
-    pub(crate) const SHORT_FILE: &'static [u8] = &[0o037, 0o213, 0o0, 0o0];
-    pub(crate) const GZIP_FILE: &'static [u8] = &[0o037, 0o213, 0o0, 0o0, 0o0];
-    pub(crate) const BZIP_FILE: &'static [u8] = &[0o102, 0o132, 0o0, 0o0, 0o0];
-    pub(crate) const LZMA_FILE: &'static [u8] = &[0o375, 0o067, 0o172, 0o130, 0o132];
+/// Create a file compressed with the format given by its extension, see
+/// [Format::from_path](compression::Format::from_path).
+///
+/// Files with extensions of uncompressed files aren't compressed.
+///
+/// # Example
+/// ```
+/// use niffler::{Error, compression};
+/// # fn main() -> Result<(), Error> {
+///
+/// # #[cfg(feature = "gz")] {
+/// # let dir = tempfile::tempdir()?;
+/// let path = dir.path().join("reads.fq.gz");
+/// {
+///   let mut writer = niffler::to_path_auto(&path, niffler::Level::Nine)?;
+///   writer.write_all(b"@read1\nACGT\n+\nIIII\n")?;
+/// }
+///
+/// let (_, format) = niffler::from_path(&path)?;
+/// assert_eq!(format, compression::Format::Gzip);
+/// # }
+/// # Ok(())
+/// # }
+/// ```
+pub fn to_path_auto<'a, P: AsRef<Path>>(
+    path: P,
+    level: Level,
+) -> Result<Box<dyn io::Write + 'a>, Error> {
+    let format = compression::Format::from_path(&path).unwrap_or(compression::Format::No);
+
+    to_path(path, format, level)
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use tempfile::NamedTempFile;
+
+    pub(crate) const SHORT_FILE: &'static [u8] = &[0o037, 0o213, 0o0, 0o0];
+    pub(crate) const GZIP_FILE: &'static [u8] = &[0o037, 0o213, 0o0, 0o0, 0o0];
+    pub(crate) const BZIP_FILE: &'static [u8] = &[0o102, 0o132, 0o0, 0o0, 0o0];
+    pub(crate) const LZMA_FILE: &'static [u8] = &[0o375, 0o067, 0o172, 0o130, 0o132];
+    pub(crate) const LZMA_ALONE_FILE: &[u8] = &[
+        0x5d, 0x00, 0x00, 0x80, 0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00,
+    ];
     pub(crate) const ZSTD_FILE: &'static [u8] = &[0x28, 0xb5, 0x2f, 0xfd, 0];
+    pub(crate) const LZ4_FILE: &[u8] = &[0x04, 0x22, 0x4d, 0x18, 0];
+    pub(crate) const COMPRESS_FILE: &[u8] = &[0x1f, 0x9d, 0x90, 0x61, 0x00];
+    pub(crate) const ZLIB_FILE: &[u8] = &[0x78, 0x9c, 0xf3, 0x54, 0xcf, 0x55, 0x48];
+    pub(crate) const ZIP_FILE: &[u8] = &[0x50, 0x4b, 0x03, 0x04, 0x14, 0x00];
+    #[cfg(feature = "zip")]
+    pub(crate) const ZIP_TWO_FILES: &[u8] = &[
+        0x50, 0x4b, 0x03, 0x04, 0x14, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x21, 0x00, 0x82,
+        0x1b, 0xb5, 0x20, 0x15, 0x00, 0x00, 0x00, 0x15, 0x00, 0x00, 0x00, 0x07, 0x00, 0x00, 0x00,
+        0x61, 0x2e, 0x66, 0x61, 0x73, 0x74, 0x71, 0x73, 0x48, 0xd4, 0x4b, 0x4b, 0x2c, 0x2e, 0x29,
+        0xe4, 0x72, 0x74, 0x76, 0x0f, 0xe1, 0xd2, 0xe6, 0xf2, 0x04, 0x02, 0x2e, 0x00, 0x50, 0x4b,
+        0x03, 0x04, 0x14, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x21, 0x00, 0x48, 0x56, 0x1c,
+        0x8f, 0x15, 0x00, 0x00, 0x00, 0x15, 0x00, 0x00, 0x00, 0x07, 0x00, 0x00, 0x00, 0x62, 0x2e,
+        0x66, 0x61, 0x73, 0x74, 0x71, 0x73, 0x48, 0xd2, 0x4b, 0x4b, 0x2c, 0x2e, 0x29, 0xe4, 0x72,
+        0x74, 0x76, 0x0f, 0xe1, 0xd2, 0xe6, 0xf2, 0x04, 0x02, 0x2e, 0x00, 0x50, 0x4b, 0x01, 0x02,
+        0x14, 0x03, 0x14, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x21, 0x00, 0x82, 0x1b, 0xb5,
+        0x20, 0x15, 0x00, 0x00, 0x00, 0x15, 0x00, 0x00, 0x00, 0x07, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80, 0x01, 0x00, 0x00, 0x00, 0x00, 0x61, 0x2e, 0x66,
+        0x61, 0x73, 0x74, 0x71, 0x50, 0x4b, 0x01, 0x02, 0x14, 0x03, 0x14, 0x00, 0x00, 0x00, 0x08,
+        0x00, 0x00, 0x00, 0x21, 0x00, 0x48, 0x56, 0x1c, 0x8f, 0x15, 0x00, 0x00, 0x00, 0x15, 0x00,
+        0x00, 0x00, 0x07, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80,
+        0x01, 0x3a, 0x00, 0x00, 0x00, 0x62, 0x2e, 0x66, 0x61, 0x73, 0x74, 0x71, 0x50, 0x4b, 0x05,
+        0x06, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x02, 0x00, 0x6a, 0x00, 0x00, 0x00, 0x74, 0x00,
+        0x00, 0x00, 0x00, 0x00,
+    ];
+    pub(crate) const ZSTD_SKIPPABLE_FILE: &[u8] = &[
+        0x50, 0x2a, 0x4d, 0x18, 0x04, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x28, 0xb5, 0x2f,
+        0xfd,
+    ];
+    pub(crate) const LZ4_SKIPPABLE_FILE: &[u8] = &[
+        0x5f, 0x2a, 0x4d, 0x18, 0x00, 0x00, 0x00, 0x00, 0x04, 0x22, 0x4d, 0x18, 0x00,
+    ];
+    pub(crate) const ZSTD_LEGACY_FILE: &[u8] = &[
+        0x27, 0xb5, 0x2f, 0xfd, 0x00, 0x00, 0x40, 0x00, 0x1a, 0x49, 0x27, 0x6d, 0x20, 0x63, 0x6f,
+        0x6d, 0x70, 0x72, 0x65, 0x73, 0x73, 0x20, 0x69, 0x6e, 0x20, 0x7a, 0x73, 0x74, 0x64, 0x20,
+        0x76, 0x30, 0x2e, 0x37, 0x0a, 0xc0, 0x00, 0x00,
+    ];
+    pub(crate) const LZO_FILE: &[u8] = &[
+        0x89, 0x4c, 0x5a, 0x4f, 0x00, 0x0d, 0x0a, 0x1a, 0x0a, 0x10, 0x30, 0x20, 0x80, 0x09, 0x40,
+        0x01, 0x03, 0x03, 0x00, 0x00, 0x03, 0x00, 0x00, 0x81, 0xa4, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x26, 0x5f, 0x02, 0x59, 0x00, 0x00, 0x00, 0x25, 0x00, 0x00, 0x00,
+        0x1c, 0xf7, 0x2b, 0x0d, 0x04, 0x80, 0x7f, 0x07, 0xb1, 0x25, 0x49, 0x27, 0x6d, 0x20, 0x63,
+        0x6f, 0x6d, 0x70, 0x72, 0x65, 0x73, 0x73, 0x20, 0x69, 0x6e, 0x20, 0x6c, 0x7a, 0x6f, 0x2c,
+        0x2e, 0x41, 0x00, 0x0a, 0x11, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+    pub(crate) const LZIP_FILE: &[u8] = &[0x4c, 0x5a, 0x49, 0x50, 0x01, 0x0c];
+    pub(crate) const SNAPPY_FILE: &[u8] =
+        &[0xff, 0x06, 0x00, 0x00, 0x73, 0x4e, 0x61, 0x50, 0x70, 0x59];
+    pub(crate) const LZ4_LEGACY_FILE: &[u8] = &[
+        0x02, 0x21, 0x4c, 0x18, 0x1d, 0x00, 0x00, 0x00, 0xf0, 0x0c, 0x49, 0x27, 0x6d, 0x20, 0x63,
+        0x6f, 0x6d, 0x70, 0x72, 0x65, 0x73, 0x73, 0x20, 0x69, 0x6e, 0x20, 0x6c, 0x7a, 0x34, 0x20,
+        0x6c, 0x65, 0x67, 0x61, 0x63, 0x79, 0x0a,
+    ];
+    // LOREM_IPSUM 30 times, written like ncompress with 10 bits codes at most (`-b10`) and
+    // checked with the .Z decoder of gzip: the dictionary is cleared once it's full
+    #[cfg(feature = "compress")]
+    pub(crate) const COMPRESS_BLOCK_FILE: &[u8] = &[
+        0x1f, 0x9d, 0x8a, 0x4c, 0xde, 0xc8, 0x29, 0xd3, 0x06, 0x44, 0x1a, 0x38, 0x73, 0xea, 0x14,
+        0x24, 0xf3, 0x86, 0x8d, 0x40, 0x10, 0x73, 0xd2, 0xd0, 0x01, 0x11, 0xa6, 0x4d, 0x19, 0x3a,
+        0x2c, 0x40, 0x8c, 0x79, 0xe3, 0x66, 0x4e, 0x99, 0x31, 0x74, 0x2e, 0xd6, 0x91, 0x43, 0x91,
+        0xcc, 0xc1, 0x34, 0x73, 0xc6, 0xa4, 0x71, 0x73, 0x06, 0x44, 0x19, 0x36, 0x12, 0x5d, 0x80,
+        0xa8, 0x32, 0xb1, 0x0e, 0x1b, 0x3a, 0x72, 0xd2, 0xa8, 0x2c, 0x33, 0x07, 0xe2, 0x98, 0x97,
+        0x65, 0x72, 0xce, 0x89, 0x53, 0xa7, 0x0c, 0x08, 0x93, 0x15, 0x33, 0x86, 0xf1, 0x09, 0x54,
+        0x28, 0x51, 0xa3, 0x65, 0xdc, 0xa4, 0x29, 0x38, 0x27, 0xcc, 0x19, 0x89, 0x74, 0x50, 0x52,
+        0xa4, 0xe3, 0x22, 0xe0, 0xc0, 0x82, 0x07, 0x13, 0x2e, 0x6c, 0xf8, 0x30, 0xe2, 0xc4, 0x8a,
+        0x17, 0x33, 0x6e, 0xec, 0xf8, 0x31, 0x24, 0x9d, 0x91, 0x25, 0x4f, 0xa6, 0x5c, 0xd9, 0xf2,
+        0x65, 0xcc, 0x99, 0x35, 0x6f, 0xe6, 0xdc, 0xd9, 0x33, 0x65, 0x53, 0x94, 0x4f, 0x8f, 0xa6,
+        0x49, 0x4a, 0x91, 0x29, 0x9b, 0xa0, 0x80, 0x8b, 0xba, 0x94, 0x4a, 0xd5, 0x2a, 0x56, 0xad,
+        0x61, 0xb8, 0x7a, 0x25, 0x68, 0x10, 0xa1, 0xc2, 0xa3, 0x64, 0x49, 0x9a, 0xa5, 0x68, 0x11,
+        0xa3, 0x46, 0x8e, 0x1e, 0x41, 0x8a, 0x24, 0x19, 0xc6, 0x24, 0x1c, 0x94, 0x2a, 0x59, 0xba,
+        0x84, 0xc9, 0x15, 0x2f, 0x08, 0x9b, 0x38, 0x75, 0xa6, 0xe1, 0x69, 0x18, 0xf1, 0x50, 0xc5,
+        0x48, 0xdb, 0x28, 0xad, 0xed, 0x54, 0x71, 0xd4, 0xa9, 0x10, 0x1d, 0xd3, 0xc9, 0xda, 0x33,
+        0x72, 0x57, 0x81, 0x94, 0xc3, 0x5e, 0x66, 0xe8, 0x50, 0xb3, 0x44, 0xce, 0x69, 0x3f, 0xb3,
+        0x15, 0xfd, 0x96, 0xb4, 0x69, 0xd4, 0x74, 0x57, 0xdf, 0xa5, 0xf9, 0x5a, 0xaf, 0x6c, 0xda,
+        0x7e, 0x0f, 0xf7, 0x36, 0x9a, 0x7b, 0x77, 0x78, 0xdb, 0x81, 0x7f, 0x37, 0xbe, 0x3a, 0x1c,
+        0xb2, 0x64, 0xe4, 0x60, 0x2d, 0x8f, 0x6d, 0x0e, 0xf1, 0x39, 0x5a, 0xcf, 0x6b, 0x43, 0xbb,
+        0x85, 0x5b, 0x5a, 0x6e, 0xea, 0xba, 0xac, 0xc9, 0xc4, 0x1d, 0x6c, 0x7b, 0xcd, 0xd6, 0xd7,
+        0x4f, 0xe2, 0x25, 0x46, 0xde, 0x60, 0xba, 0x15, 0x76, 0xde, 0x78, 0x8b, 0x01, 0x57, 0x15,
+        0x7b, 0xc4, 0x6d, 0x75, 0xdc, 0x57, 0x95, 0x89, 0x85, 0x19, 0x7d, 0x9b, 0xdd, 0xa7, 0x16,
+        0x68, 0x6d, 0x8d, 0x16, 0xd7, 0x69, 0x73, 0xa9, 0x66, 0x57, 0x6b, 0x03, 0x7a, 0xc7, 0x17,
+        0x6f, 0x0a, 0x0a, 0x46, 0xd8, 0x52, 0x0f, 0xb6, 0xa8, 0x5e, 0x70, 0x14, 0xba, 0x77, 0x61,
+        0x72, 0xf2, 0x6d, 0x58, 0x96, 0x7d, 0x9d, 0x7d, 0x38, 0xdd, 0x7e, 0xd6, 0xf9, 0x97, 0xdd,
+        0x89, 0x02, 0xe6, 0x15, 0xdb, 0x8a, 0x31, 0xde, 0xb6, 0xe0, 0x8b, 0x2c, 0x2a, 0x19, 0xe1,
+        0x7a, 0x8f, 0x15, 0xf7, 0x1e, 0x86, 0xca, 0xcd, 0xb7, 0xe3, 0x59, 0x3d, 0x4a, 0xa7, 0x9f,
+        0x88, 0xfd, 0x91, 0xf8, 0x9f, 0x76, 0x28, 0x1a, 0x59, 0x20, 0x78, 0x08, 0xa2, 0x87, 0x1b,
+        0x83, 0xe6, 0x95, 0x09, 0xe1, 0x8c, 0x13, 0x46, 0x69, 0xe1, 0x64, 0xf1, 0x69, 0xc8, 0xdc,
+        0x95, 0xd0, 0xe1, 0x07, 0x22, 0x75, 0xfc, 0x5d, 0x57, 0x22, 0x80, 0xdb, 0x89, 0xf9, 0xdd,
+        0x81, 0x7f, 0x39, 0x59, 0x9e, 0x83, 0x6a, 0xca, 0xc8, 0x18, 0x8d, 0x6e, 0x1a, 0x07, 0x67,
+        0x86, 0xcb, 0x65, 0x56, 0x1f, 0x96, 0xd1, 0xe5, 0x17, 0x62, 0x75, 0x23, 0x62, 0x67, 0x62,
+        0x80, 0xae, 0x11, 0xf8, 0x67, 0x93, 0x81, 0x0d, 0x0a, 0x63, 0xa1, 0x4e, 0xb2, 0x29, 0x5c,
+        0x85, 0x8a, 0xc2, 0xc7, 0xa8, 0x95, 0xce, 0x41, 0x6a, 0xe7, 0x8f, 0x5c, 0xea, 0xf9, 0x25,
+        0x91, 0x99, 0xaa, 0x68, 0x20, 0xa7, 0x67, 0x32, 0x99, 0x64, 0x7a, 0x87, 0xb6, 0xd9, 0x9e,
+        0x94, 0x37, 0xc6, 0xd9, 0x28, 0x87, 0x3c, 0x46, 0x7a, 0x27, 0x90, 0x95, 0xee, 0x09, 0x66,
+        0x91, 0xdd, 0x1d, 0x39, 0xeb, 0xad, 0xb5, 0x36, 0xf8, 0x69, 0xa0, 0xb8, 0x4a, 0x38, 0xaa,
+        0x8d, 0x8b, 0x56, 0xa9, 0x63, 0xaa, 0x75, 0xfa, 0xb8, 0x25, 0xa5, 0x5d, 0x5a, 0xca, 0x67,
+        0x98, 0xc9, 0x8e, 0x09, 0x68, 0x82, 0x82, 0xa2, 0x49, 0x28, 0xb4, 0xbe, 0xe5, 0x3a, 0x2d,
+        0xaf, 0xd5, 0xe6, 0x38, 0x27, 0xb6, 0x1e, 0x6a, 0x39, 0x69, 0x9e, 0x42, 0x5e, 0xda, 0x67,
+        0xb8, 0x9b, 0x32, 0xbb, 0xa4, 0xb3, 0xb4, 0x42, 0xa5, 0x6e, 0x8d, 0xec, 0x9a, 0x6a, 0xed,
+        0xbb, 0x8f, 0x66, 0x2b, 0x2f, 0x9e, 0x41, 0x7a, 0x39, 0x24, 0xa6, 0x29, 0x2a, 0x4b, 0x26,
+        0xba, 0xfb, 0xa6, 0x09, 0xf1, 0x93, 0x88, 0xee, 0xfa, 0xa6, 0xc0, 0xee, 0x3a, 0xda, 0x61,
+        0x96, 0x92, 0x22, 0x5c, 0xec, 0xab, 0x0c, 0xfb, 0x89, 0x24, 0xa8, 0x9d, 0x9a, 0xfb, 0x2c,
+        0xb9, 0xd1, 0x42, 0x69, 0x71, 0xa9, 0x54, 0x66, 0x0c, 0xac, 0xaa, 0xda, 0xce, 0x9b, 0xb0,
+        0xb7, 0xc7, 0xc6, 0xea, 0xf0, 0xb8, 0x66, 0x46, 0x7c, 0x2e, 0xca, 0xe9, 0x4a, 0x0b, 0xf0,
+        0xc5, 0x2d, 0xcb, 0xa9, 0x71, 0xb0, 0xab, 0x6e, 0x4b, 0xaf, 0xc2, 0xf6, 0x82, 0xab, 0xe9,
+        0xc8, 0x13, 0x7b, 0xda, 0x2f, 0xc5, 0xba, 0x92, 0x3a, 0x25, 0x8e, 0x42, 0xbf, 0x6c, 0x70,
+        0xc7, 0xc4, 0x76, 0x6b, 0x2c, 0xac, 0x0d, 0x8b, 0xfb, 0xb4, 0xd3, 0xfa, 0x42, 0xbd, 0x2e,
+        0xd0, 0x54, 0xff, 0x4a, 0x67, 0xbc, 0x58, 0xb7, 0x5a, 0xef, 0xb7, 0xc8, 0x2e, 0xbd, 0x2c,
+        0xc9, 0xcd, 0x4a, 0xcc, 0xb3, 0xbf, 0x3e, 0x27, 0x3a, 0xb5, 0xaf, 0xa8, 0x16, 0x8c, 0xf6,
+        0xb0, 0x6a, 0x23, 0xcd, 0xb6, 0xcd, 0x5e, 0x87, 0x0d, 0x36, 0xdc, 0x74, 0xab, 0x2c, 0x75,
+        0xaf, 0xa7, 0x5e, 0xab, 0x37, 0xc7, 0x7c, 0x73, 0xeb, 0xea, 0xc2, 0xf7, 0xba, 0xfd, 0xf0,
+        0xdc, 0x2e, 0xf2, 0x1b, 0xb6, 0xa8, 0x3f, 0xb3, 0x5c, 0x76, 0xde, 0x1b, 0x0b, 0xcb, 0xaa,
+        0xe3, 0x6b, 0xd7, 0xdc, 0x75, 0xbe, 0x84, 0x57, 0x2e, 0x77, 0xce, 0x62, 0x67, 0x7e, 0x77,
+        0xe2, 0x04, 0x77, 0x5e, 0xb4, 0xcc, 0x1f, 0x43, 0xae, 0xb4, 0xac, 0x93, 0xa3, 0x3e, 0xf8,
+        0xc4, 0x98, 0xdb, 0x8d, 0xf8, 0xc0, 0x43, 0xc3, 0x7c, 0x70, 0xd6, 0x8f, 0x27, 0xdd, 0x36,
+        0xed, 0x38, 0x43, 0x78, 0x3b, 0xe5, 0xb9, 0xaf, 0xbc, 0x3a, 0xef, 0x56, 0xef, 0xfd, 0xf9,
+        0xd1, 0x34, 0x73, 0x2d, 0xf2, 0xdb, 0x4d, 0x9b, 0xfc, 0x74, 0xf2, 0x87, 0xb7, 0x5b, 0xf5,
+        0xd9, 0x8c, 0x3f, 0x3f, 0xf3, 0xd6, 0x21, 0xe3, 0xcb, 0x34, 0xe5, 0xc7, 0xa3, 0x8e, 0x3d,
+        0xb5, 0x18, 0x6f, 0x0f, 0x6f, 0xf7, 0x46, 0x7f, 0x0f, 0x72, 0xe4, 0xc4, 0x7f, 0x6d, 0xfd,
+        0xe5, 0xff, 0xea, 0xae, 0xbd, 0xd9, 0xeb, 0x7b, 0xde, 0x7e, 0xec, 0xc2, 0x03, 0x4e, 0x7a,
+        0xf5, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x61, 0xda, 0xb0, 0x00,
+        0x11, 0x06, 0xc4, 0x9c, 0x31, 0x65, 0xd8, 0x94, 0x91, 0x93, 0x66, 0x4e, 0x9c, 0x3a, 0x65,
+        0x40, 0x94, 0x71, 0x93, 0xa6, 0x8d, 0xc1, 0x30, 0x67, 0xd2, 0xd0, 0xa1, 0xd3, 0x90, 0x20,
+        0x1d, 0x17, 0x4c, 0xde, 0xc8, 0x29, 0x63, 0x31, 0x0d, 0x9c, 0x39, 0x75, 0x2c, 0x92, 0x79,
+        0xc3, 0x46, 0xa4, 0x41, 0x8d, 0x04, 0xdb, 0x94, 0xa1, 0x33, 0x70, 0xcc, 0x1b, 0x37, 0x73,
+        0xca, 0x8c, 0xa1, 0x33, 0xb3, 0x8e, 0x1c, 0x82, 0x64, 0x4c, 0x36, 0x1c, 0x93, 0xc6, 0xcd,
+        0x19, 0x89, 0x6c, 0x34, 0xba, 0x00, 0x51, 0x85, 0x0e, 0x88, 0x3a, 0x6c, 0xe8, 0x30, 0x24,
+        0x5a, 0x66, 0x8e, 0x41, 0x84, 0x0a, 0x19, 0x3a, 0x84, 0x08, 0x22, 0x68, 0xc0, 0x81, 0x05,
+        0x0f, 0x26, 0x5c, 0xd8, 0xf0, 0x61, 0xc4, 0x89, 0x15, 0x2f, 0x66, 0xdc, 0xd8, 0x31, 0xcc,
+        0x47,
+    ];
+    // same with the dictionary left full, without clear code (`-b10 -C`)
+    #[cfg(feature = "compress")]
+    pub(crate) const COMPRESS_NO_BLOCK_FILE: &[u8] = &[
+        0x1f, 0x9d, 0x0a, 0x4c, 0xde, 0xc8, 0x29, 0xd3, 0x06, 0x44, 0x1a, 0x38, 0x73, 0xea, 0x10,
+        0x24, 0xf3, 0x86, 0x4d, 0x40, 0x10, 0x73, 0xd2, 0xd0, 0x01, 0x11, 0xa6, 0x4d, 0x19, 0x3a,
+        0x2c, 0x40, 0x8c, 0x79, 0xe3, 0x66, 0x4e, 0x99, 0x31, 0x74, 0x2c, 0xd6, 0x91, 0x33, 0x91,
+        0x8c, 0xc1, 0x34, 0x73, 0xc6, 0xa4, 0x71, 0x73, 0x06, 0x44, 0x19, 0x36, 0x11, 0x5d, 0x80,
+        0xa8, 0x22, 0xb1, 0x0e, 0x1b, 0x3a, 0x72, 0xd2, 0xa4, 0x2c, 0x33, 0xe7, 0xe1, 0x18, 0x97,
+        0x65, 0x70, 0xce, 0x89, 0x53, 0xa7, 0x0c, 0x88, 0x92, 0x14, 0x31, 0x86, 0xe9, 0xf9, 0x33,
+        0xe8, 0xd0, 0xa2, 0x65, 0xdc, 0xa4, 0x21, 0x38, 0x27, 0xcc, 0x99, 0x88, 0x74, 0x4e, 0x4e,
+        0xa4, 0xe3, 0x02, 0xa0, 0x40, 0x82, 0x06, 0x11, 0x2a, 0x64, 0xe8, 0x10, 0xa2, 0x44, 0x8a,
+        0x16, 0x31, 0x6a, 0xe4, 0xe8, 0x11, 0x24, 0x1d, 0x91, 0x24, 0x4d, 0xa2, 0x54, 0xc9, 0xd2,
+        0x25, 0x4c, 0x99, 0x34, 0x6d, 0xe2, 0xd4, 0xc9, 0x13, 0x25, 0xd3, 0x93, 0x4e, 0x8d, 0xa6,
+        0x41, 0x3a, 0x71, 0x29, 0x1b, 0xa0, 0x7f, 0x89, 0xb6, 0x8c, 0x3a, 0xb5, 0xea, 0xd5, 0xac,
+        0x61, 0xb6, 0x76, 0x1d, 0x58, 0xf0, 0x60, 0x42, 0xa3, 0x63, 0x47, 0x96, 0x9d, 0x58, 0xf1,
+        0x62, 0xc6, 0x8d, 0x1d, 0x3f, 0x86, 0x1c, 0x19, 0xa6, 0x24, 0x9c, 0x93, 0x29, 0x57, 0xb6,
+        0x7c, 0xb9, 0xf5, 0x2e, 0x88, 0x9a, 0x37, 0x73, 0xa6, 0xd9, 0x59, 0xf8, 0xb0, 0xd0, 0xc4,
+        0x47, 0xdb, 0x24, 0xa5, 0xdd, 0x34, 0x31, 0x54, 0xa9, 0x0f, 0x1b, 0xd3, 0xc1, 0xca, 0x13,
+        0x32, 0xd7, 0x80, 0x93, 0xc1, 0x5a, 0x5e, 0xd8, 0x30, 0x73, 0xc4, 0xcd, 0x68, 0x3d, 0xaf,
+        0x0d, 0xed, 0x76, 0x74, 0xe9, 0xd3, 0x73, 0x55, 0xdb, 0x9d, 0xe9, 0x3a, 0x6f, 0xec, 0xd9,
+        0x7d, 0x0d, 0xf3, 0x2e, 0x8a, 0x5b, 0x37, 0xf8, 0xda, 0x80, 0x7d, 0x33, 0xb6, 0x2a, 0xfc,
+        0x71, 0xe4, 0xe3, 0x5f, 0x2b, 0x8b, 0x65, 0xfe, 0xd0, 0xf9, 0xd9, 0xce, 0x6a, 0x41, 0xb7,
+        0x7d, 0x4b, 0x3a, 0x2e, 0x6a, 0xba, 0xab, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x31, 0x6d, 0xf7, 0x9a, 0x5e, 0xb2, 0xf1, 0xe5, 0x53, 0x78, 0x88, 0x8d, 0x27, 0x58, 0x6e,
+        0x84, 0x99, 0x27, 0x9e, 0x62, 0xbf, 0x51, 0xb5, 0xde, 0x70, 0x5a, 0x19, 0xe7, 0x15, 0x65,
+        0x61, 0x5d, 0x36, 0x9f, 0x66, 0xf6, 0xa5, 0xf5, 0x19, 0x5b, 0xa2, 0xc1, 0x65, 0x9a, 0x5c,
+        0xa9, 0xd5, 0xc5, 0x9a, 0x80, 0xdd, 0xed, 0xb5, 0x5b, 0x82, 0x81, 0x0d, 0xa6, 0x94, 0x83,
+        0x2c, 0xa6, 0x07, 0xdc, 0x84, 0xed, 0x59, 0x88, 0x5c, 0x7c, 0x1a, 0x92, 0x55, 0x1f, 0x67,
+        0x1e, 0x4a, 0xa7, 0x5f, 0x75, 0xfd, 0x61, 0x67, 0x62, 0x80, 0x78, 0xc1, 0xa6, 0x22, 0x8c,
+        0xb6, 0x29, 0xe8, 0xe2, 0x8a, 0x49, 0x42, 0xa8, 0x9e, 0x63, 0xc4, 0xb9, 0x77, 0x61, 0x72,
+        0xf2, 0xe9, 0x68, 0x16, 0x8f, 0xd1, 0xe5, 0x17, 0x22, 0x7f, 0x23, 0xfa, 0x97, 0xdd, 0x89,
+        0x45, 0x12, 0xf8, 0xdd, 0x81, 0xe7, 0xdd, 0xb6, 0x60, 0x79, 0x64, 0x3e, 0x28, 0xa3, 0x84,
+        0x50, 0x56, 0x28, 0x19, 0x7c, 0x19, 0x2e, 0x67, 0xe5, 0x73, 0xf7, 0x7d, 0x38, 0xdd, 0x7e,
+        0xd6, 0x91, 0xf8, 0x9f, 0x76, 0x61, 0x7a, 0x67, 0xa0, 0x5f, 0x4d, 0x92, 0xd7, 0x60, 0x9a,
+        0x31, 0x2e, 0x36, 0x63, 0x9b, 0xc5, 0xbd, 0x89, 0xa1, 0x72, 0x98, 0xd1, 0x77, 0x25, 0x74,
+        0xf8, 0x81, 0x48, 0x9d, 0x88, 0xd7, 0x95, 0x08, 0x60, 0x6b, 0x03, 0xfa, 0xc9, 0x24, 0x60,
+        0x82, 0xbe, 0x48, 0x68, 0x93, 0x6b, 0x06, 0x47, 0x61, 0xa2, 0xef, 0x2d, 0x5a, 0x65, 0x73,
+        0x8f, 0xd6, 0xe9, 0xe3, 0x96, 0x79, 0x7a, 0x39, 0x24, 0xa6, 0x29, 0x16, 0xb8, 0xa9, 0x99,
+        0x4b, 0x22, 0x89, 0x9e, 0xa1, 0x6c, 0xb2, 0x17, 0xa5, 0x8d, 0x70, 0x32, 0xba, 0xe1, 0x8e,
+        0x90, 0xda, 0xf9, 0x23, 0xa5, 0x7a, 0x7e, 0x49, 0x24, 0x77, 0x46, 0xca, 0x6a, 0x2b, 0xad,
+        0x0c, 0x7a, 0x0a, 0xe8, 0xad, 0x11, 0x8a, 0x5a, 0xa3, 0xa2, 0x54, 0xe6, 0x88, 0x2a, 0x9d,
+        0x3d, 0x6a, 0x39, 0x29, 0x97, 0x95, 0xee, 0x09, 0x26, 0xb2, 0x62, 0xfe, 0x89, 0x60, 0xa0,
+        0x67, 0x0e, 0xfa, 0x6c, 0x6f, 0xb8, 0x4a, 0xbb, 0x2b, 0xb5, 0x38, 0xca, 0x79, 0x6d, 0x87,
+        0x59, 0x4a, 0x8a, 0x67, 0x90, 0x96, 0xf2, 0x09, 0xae, 0xa6, 0xcb, 0x2a, 0xd9, 0xec, 0xac,
+        0x4f, 0xa5, 0x4b, 0xe3, 0xba, 0xa5, 0x56, 0xeb, 0xae, 0xa3, 0xd8, 0xc6, 0x7b, 0x27, 0x90,
+        0x5d, 0x0a, 0x79, 0x29, 0x8a, 0xc9, 0x8e, 0x79, 0xae, 0xbe, 0x68, 0x3e, 0xec, 0xe4, 0xa1,
+        0xba, 0xba, 0x19, 0x70, 0xbb, 0x8d, 0x72, 0x88, 0x65, 0xa4, 0x07, 0x13, 0xeb, 0xea, 0xc2,
+        0x7d, 0x1e, 0xf9, 0x29, 0xa7, 0xe5, 0x3a, 0x3b, 0x2e, 0xb4, 0x4f, 0x56, 0x4c, 0xea, 0x94,
+        0x18, 0xff, 0x9a, 0x6a, 0xb6, 0xf2, 0x22, 0xdc, 0xad, 0xb1, 0xb0, 0x36, 0x2c, 0x6e, 0x99,
+        0x10, 0x9b, 0x7b, 0x32, 0xba, 0xd1, 0xfe, 0x6b, 0x31, 0xcb, 0x71, 0x66, 0x0c, 0xac, 0xaa,
+        0xda, 0xce, 0x9b, 0x70, 0xbd, 0xdf, 0x66, 0x2a, 0xb2, 0xc4, 0x9d, 0xf2, 0x3b, 0x71, 0xae,
+        0xa3, 0x4a, 0x79, 0x63, 0xd0, 0x2e, 0x17, 0xcc, 0xf1, 0xb0, 0xdc, 0x16, 0xfb, 0x2a, 0xc3,
+        0xe1, 0x3a, 0xdd, 0x74, 0xbe, 0x4f, 0xab, 0xfb, 0xf3, 0xd4, 0xbe, 0xce, 0x09, 0xef, 0xd5,
+        0xac, 0xd2, 0xeb, 0xed, 0xb1, 0x4a, 0x2b, 0x3b, 0x32, 0xb3, 0x11, 0xef, 0xdc, 0x6f, 0xcf,
+        0x88, 0x4a, 0xdd, 0xeb, 0xa9, 0x04, 0x9f, 0x2d, 0x6c, 0xda, 0x47, 0xaf, 0x5d, 0x73, 0xd7,
+        0x60, 0x7f, 0xfd, 0xf6, 0xdc, 0x29, 0x47, 0xcd, 0xab, 0xa9, 0xd6, 0xe6, 0xbd, 0xf1, 0xde,
+        0xdb, 0xb6, 0xaa, 0xb0, 0xbd, 0x6d, 0x3b, 0x2c, 0x77, 0x8b, 0xfb, 0x82, 0x1d, 0xaa, 0xcf,
+        0x2b, 0x93, 0x8d, 0xb7, 0xc6, 0xc1, 0xae, 0xda, 0xb8, 0xda, 0x34, 0x73, 0x8d, 0xef, 0xe0,
+        0x94, 0xc7, 0x8d, 0x73, 0xd8, 0x98, 0xdb, 0x8d, 0xf8, 0xc0, 0x9c, 0x13, 0x1d, 0xb3, 0xc7,
+        0x8f, 0x27, 0x1d, 0xab, 0xe4, 0xa7, 0x0b, 0x2e, 0xf1, 0xe5, 0x75, 0x1f, 0x2e, 0xb0, 0xd0,
+        0x2f, 0x1b, 0x8c, 0xb5, 0xe3, 0x48, 0xb3, 0x3d, 0xfb, 0xcd, 0x0f, 0xda, 0x3e, 0x39, 0xee,
+        0x2a, 0xab, 0xbe, 0x7b, 0xd5, 0x7a, 0x7b, 0x6e, 0xf4, 0xcc, 0x5b, 0x87, 0xec, 0x36, 0xd3,
+        0x25, 0x3b, 0x8d, 0xbc, 0xe1, 0xec, 0x52, 0x6d, 0xf6, 0xe2, 0xce, 0xcb, 0xac, 0x35, 0xc8,
+        0xf7, 0x2e, 0x3d, 0xb9, 0xf1, 0xa7, 0x5f, 0x3f, 0xed, 0xc5, 0xda, 0xbf, 0xcb, 0x7d, 0xd1,
+        0xde, 0x7f, 0x0c, 0xf9, 0xf0, 0x5e, 0x57, 0x6f, 0xb9, 0xbf, 0xb9, 0x67, 0x5f, 0xb6, 0xfa,
+        0x9d, 0xb3, 0x0f, 0x7b, 0xf0, 0x7f, 0x8f, 0x4e, 0x7d, 0xad, 0xa4, 0x9b, 0x0f, 0x30, 0xd0,
+        0xf7, 0x2b, 0x9e, 0xff, 0xeb, 0x59, 0xbb, 0x2f, 0xbb, 0xcd, 0xf1, 0xd7, 0x4a, 0xba, 0xf9,
+        0xbb, 0x02,
+    ];
     pub(crate) const LOREM_IPSUM: &'static [u8] = b"Lorem ipsum dolor sit amet, consectetur adipiscing elit. Ut ultricies scelerisque diam, a scelerisque enim sagittis at.";
 
     mod compress_uncompress {
@@ -354,6 +1101,12 @@ mod test {
                 get_reader(Box::new(&LZMA_FILE[..])).is_err(),
                 "lzma disabled, this assertion should fail"
             );
+
+            assert!(
+                get_reader_with_format(Box::new(LZMA_ALONE_FILE), compression::Format::LzmaAlone)
+                    .is_err(),
+                "lzma disabled, this assertion should fail"
+            );
         }
 
         #[cfg(feature = "lzma")]
@@ -383,6 +1136,67 @@ mod test {
             assert_eq!(LOREM_IPSUM, buffer.as_slice());
         }
 
+        #[cfg(feature = "lzma")]
+        #[test]
+        fn lzma_alone() {
+            let ofile = NamedTempFile::new().expect("Can't create tmpfile");
+
+            {
+                let wfile = ofile.reopen().expect("Can't create tmpfile");
+                let mut writer =
+                    get_writer(Box::new(wfile), compression::Format::LzmaAlone, Level::Six)
+                        .unwrap();
+                writer
+                    .write_all(LOREM_IPSUM)
+                    .expect("Error during write of data");
+            }
+
+            let rfile = ofile.reopen().expect("Can't create tmpfile");
+            let (_, compression) = sniff(Box::new(rfile)).expect("Error reading from tmpfile");
+            assert_eq!(compression, compression::Format::No);
+
+            let rfile = ofile.reopen().expect("Can't create tmpfile");
+            let (mut reader, compression) =
+                get_reader_with_format(Box::new(rfile), compression::Format::LzmaAlone)
+                    .expect("Error reading from tmpfile");
+
+            assert_eq!(compression, compression::Format::LzmaAlone);
+
+            let mut buffer = Vec::new();
+            reader
+                .read_to_end(&mut buffer)
+                .expect("Error during reading");
+            assert_eq!(LOREM_IPSUM, buffer.as_slice());
+        }
+
+        #[cfg(feature = "lzma")]
+        #[test]
+        fn lzma_alone_extension() {
+            let ofile = tempfile::Builder::new()
+                .suffix(".txt.lzma")
+                .tempfile()
+                .expect("Can't create tmpfile");
+
+            {
+                let mut writer =
+                    to_path(ofile.path(), compression::Format::LzmaAlone, Level::Six).unwrap();
+                writer
+                    .write_all(LOREM_IPSUM)
+                    .expect("Error during write of data");
+            }
+
+            let (mut reader, compression) =
+                from_path(ofile.path()).expect("Error reading from tmpfile");
+
+            assert_eq!(compression, compression::Format::LzmaAlone);
+
+            let mut buffer = Vec::new();
+            reader
+                .read_to_end(&mut buffer)
+                .expect("Error during reading");
+            assert_eq!(LOREM_IPSUM, buffer.as_slice());
+        }
+
         #[test]
         #[cfg(not(feature = "zstd"))]
         fn no_zstd_feature() {
@@ -423,45 +1237,1741 @@ mod test {
                 .expect("Error during reading");
             assert_eq!(LOREM_IPSUM, buffer.as_slice());
         }
-    }
 
-    mod compression_format_detection {
-        use super::*;
+        #[test]
+        #[cfg(not(feature = "lz4"))]
+        fn no_lz4_feature() {
+            assert!(
+                get_writer(Box::new(vec![]), compression::Format::Lz4, Level::Six).is_err(),
+                "lz4 disabled, this assertion should fail"
+            );
+
+            assert!(
+                get_reader(Box::new(LZ4_FILE)).is_err(),
+                "lz4 disabled, this assertion should fail"
+            );
+        }
 
+        #[cfg(feature = "lz4")]
         #[test]
-        fn gzip() {
-            let (_, compression) = sniff(Box::new(GZIP_FILE)).expect("Error in read file");
-            assert_eq!(compression, compression::Format::Gzip);
+        fn lz4() {
+            let ofile = NamedTempFile::new().expect("Can't create tmpfile");
+
+            {
+                let wfile = ofile.reopen().expect("Can't create tmpfile");
+                let mut writer =
+                    get_writer(Box::new(wfile), compression::Format::Lz4, Level::Six).unwrap();
+                writer
+                    .write_all(LOREM_IPSUM)
+                    .expect("Error during write of data");
+            }
+
+            let rfile = ofile.reopen().expect("Can't create tmpfile");
+            let (mut reader, compression) =
+                get_reader(Box::new(rfile)).expect("Error reading from tmpfile");
+
+            assert_eq!(compression, compression::Format::Lz4);
+
+            let mut buffer = Vec::new();
+            reader
+                .read_to_end(&mut buffer)
+                .expect("Error during reading");
+            assert_eq!(LOREM_IPSUM, buffer.as_slice());
         }
 
+        #[cfg(feature = "lz4")]
         #[test]
-        fn bzip() {
-            let (_, compression) = sniff(Box::new(BZIP_FILE)).expect("Error in read file");
-            assert_eq!(compression, compression::Format::Bzip);
+        fn lz4_multiframe() {
+            let mut buf: Vec<u8> = vec![];
+
+            {
+                let mut writer =
+                    get_writer(Box::new(&mut buf), compression::Format::Lz4, Level::Six).unwrap();
+                writer
+                    .write_all(LOREM_IPSUM)
+                    .expect("Error during write of data");
+            }
+            buf.extend(LZ4_LEGACY_FILE);
+
+            let (mut reader, compression) =
+                get_reader(Box::new(buf.as_slice())).expect("Error reading from buffer");
+
+            assert_eq!(compression, compression::Format::Lz4);
+
+            let mut buffer = Vec::new();
+            reader
+                .read_to_end(&mut buffer)
+                .expect("Error during reading");
+            let mut result: Vec<u8> = LOREM_IPSUM.into();
+            result.extend(b"I'm compress in lz4 legacy\n");
+            assert_eq!(result, buffer.as_slice());
         }
 
         #[test]
-        fn lzma() {
-            let (_, compression) = sniff(Box::new(LZMA_FILE)).expect("Error in read file");
-            assert_eq!(compression, compression::Format::Lzma);
+        #[cfg(not(feature = "brotli"))]
+        fn no_brotli_feature() {
+            assert!(
+                get_writer(Box::new(vec![]), compression::Format::Brotli, Level::Six).is_err(),
+                "brotli disabled, this assertion should fail"
+            );
+
+            assert!(
+                get_reader_with_format(Box::new(LOREM_IPSUM), compression::Format::Brotli).is_err(),
+                "brotli disabled, this assertion should fail"
+            );
         }
 
+        #[cfg(feature = "brotli")]
         #[test]
-        fn zstd() {
-            let (_, compression) = sniff(Box::new(ZSTD_FILE)).expect("Error in read file");
-            assert_eq!(compression, compression::Format::Zstd);
+        fn brotli() {
+            let ofile = NamedTempFile::new().expect("Can't create tmpfile");
+
+            {
+                let wfile = ofile.reopen().expect("Can't create tmpfile");
+                let mut writer =
+                    get_writer(Box::new(wfile), compression::Format::Brotli, Level::Six).unwrap();
+                writer
+                    .write_all(LOREM_IPSUM)
+                    .expect("Error during write of data");
+            }
+
+            let rfile = ofile.reopen().expect("Can't create tmpfile");
+            let (mut reader, compression) =
+                get_reader_with_format(Box::new(rfile), compression::Format::Brotli)
+                    .expect("Error reading from tmpfile");
+
+            assert_eq!(compression, compression::Format::Brotli);
+
+            let mut buffer = Vec::new();
+            reader
+                .read_to_end(&mut buffer)
+                .expect("Error during reading");
+            assert_eq!(LOREM_IPSUM, buffer.as_slice());
         }
 
+        #[cfg(feature = "brotli")]
         #[test]
-        fn too_short() {
-            let result = sniff(Box::new(SHORT_FILE));
-            assert!(result.is_err());
+        fn brotli_extension() {
+            let ofile = tempfile::Builder::new()
+                .suffix(".txt.br")
+                .tempfile()
+                .expect("Can't create tmpfile");
+
+            {
+                let mut writer =
+                    to_path(ofile.path(), compression::Format::Brotli, Level::Six).unwrap();
+                writer
+                    .write_all(LOREM_IPSUM)
+                    .expect("Error during write of data");
+            }
+
+            let (mut reader, compression) =
+                from_path(ofile.path()).expect("Error reading from tmpfile");
+
+            assert_eq!(compression, compression::Format::Brotli);
+
+            let mut buffer = Vec::new();
+            reader
+                .read_to_end(&mut buffer)
+                .expect("Error during reading");
+            assert_eq!(LOREM_IPSUM, buffer.as_slice());
         }
 
         #[test]
-        fn no_compression() {
-            let (_, compression) = sniff(Box::new(LOREM_IPSUM)).expect("Error in read file");
-            assert_eq!(compression, compression::Format::No);
+        #[cfg(not(feature = "snappy"))]
+        fn no_snappy_feature() {
+            assert!(
+                get_writer(Box::new(vec![]), compression::Format::Snappy, Level::Six).is_err(),
+                "snappy disabled, this assertion should fail"
+            );
+
+            assert!(
+                get_reader(Box::new(SNAPPY_FILE)).is_err(),
+                "snappy disabled, this assertion should fail"
+            );
+        }
+
+        #[cfg(feature = "snappy")]
+        #[test]
+        fn snappy() {
+            let ofile = NamedTempFile::new().expect("Can't create tmpfile");
+
+            {
+                let wfile = ofile.reopen().expect("Can't create tmpfile");
+                let mut writer =
+                    get_writer(Box::new(wfile), compression::Format::Snappy, Level::Six).unwrap();
+                writer
+                    .write_all(LOREM_IPSUM)
+                    .expect("Error during write of data");
+            }
+
+            let rfile = ofile.reopen().expect("Can't create tmpfile");
+            let (mut reader, compression) =
+                get_reader(Box::new(rfile)).expect("Error reading from tmpfile");
+
+            assert_eq!(compression, compression::Format::Snappy);
+
+            let mut buffer = Vec::new();
+            reader
+                .read_to_end(&mut buffer)
+                .expect("Error during reading");
+            assert_eq!(LOREM_IPSUM, buffer.as_slice());
+        }
+
+        #[cfg(feature = "snappy")]
+        #[test]
+        fn snappy_checksum() {
+            let mut buf: Vec<u8> = vec![];
+
+            {
+                let mut writer =
+                    get_writer(Box::new(&mut buf), compression::Format::Snappy, Level::Six)
+                        .unwrap();
+                writer
+                    .write_all(LOREM_IPSUM)
+                    .expect("Error during write of data");
+            }
+
+            // stream identifier is 10 bytes, then chunk header is 4 bytes, then the checksum
+            buf[14] ^= 0xff;
+
+            let (mut reader, compression) =
+                get_reader(Box::new(buf.as_slice())).expect("Error reading from buffer");
+
+            assert_eq!(compression, compression::Format::Snappy);
+
+            let mut buffer = Vec::new();
+            assert!(reader.read_to_end(&mut buffer).is_err());
+        }
+
+        #[test]
+        #[cfg(not(feature = "lzip"))]
+        fn no_lzip_feature() {
+            assert!(
+                get_writer(Box::new(vec![]), compression::Format::Lzip, Level::Six).is_err(),
+                "lzip disabled, this assertion should fail"
+            );
+
+            assert!(
+                get_reader(Box::new(LZIP_FILE)).is_err(),
+                "lzip disabled, this assertion should fail"
+            );
+        }
+
+        #[cfg(feature = "lzip")]
+        #[test]
+        fn lzip() {
+            let ofile = NamedTempFile::new().expect("Can't create tmpfile");
+
+            {
+                let wfile = ofile.reopen().expect("Can't create tmpfile");
+                let mut writer =
+                    get_writer(Box::new(wfile), compression::Format::Lzip, Level::Six).unwrap();
+                writer
+                    .write_all(LOREM_IPSUM)
+                    .expect("Error during write of data");
+            }
+
+            let rfile = ofile.reopen().expect("Can't create tmpfile");
+            let (mut reader, compression) =
+                get_reader(Box::new(rfile)).expect("Error reading from tmpfile");
+
+            assert_eq!(compression, compression::Format::Lzip);
+
+            let mut buffer = Vec::new();
+            reader
+                .read_to_end(&mut buffer)
+                .expect("Error during reading");
+            assert_eq!(LOREM_IPSUM, buffer.as_slice());
+        }
+
+        #[cfg(feature = "lzip")]
+        #[test]
+        fn lzip_multimember() {
+            let mut buf: Vec<u8> = vec![];
+
+            {
+                let mut writer =
+                    get_writer(Box::new(&mut buf), compression::Format::Lzip, Level::One).unwrap();
+                writer
+                    .write_all(LOREM_IPSUM)
+                    .expect("Error during write of data");
+            }
+            let member = buf.clone();
+            buf.extend(member);
+
+            let (mut reader, compression) =
+                get_reader(Box::new(buf.as_slice())).expect("Error reading from buffer");
+
+            assert_eq!(compression, compression::Format::Lzip);
+
+            let mut buffer = Vec::new();
+            reader
+                .read_to_end(&mut buffer)
+                .expect("Error during reading");
+            let mut result: Vec<u8> = LOREM_IPSUM.into();
+            result.extend(LOREM_IPSUM);
+            assert_eq!(result, buffer.as_slice());
+        }
+
+        #[test]
+        #[cfg(not(feature = "compress"))]
+        fn no_compress_feature() {
+            assert!(
+                get_writer(Box::new(vec![]), compression::Format::Compress, Level::Six).is_err(),
+                "compress disabled, this assertion should fail"
+            );
+
+            assert!(
+                get_reader(Box::new(COMPRESS_FILE)).is_err(),
+                "compress disabled, this assertion should fail"
+            );
+        }
+
+        #[cfg(feature = "compress")]
+        #[test]
+        fn compress() {
+            let ofile = NamedTempFile::new().expect("Can't create tmpfile");
+
+            {
+                let wfile = ofile.reopen().expect("Can't create tmpfile");
+                let mut writer =
+                    get_writer(Box::new(wfile), compression::Format::Compress, Level::Six).unwrap();
+                writer
+                    .write_all(LOREM_IPSUM)
+                    .expect("Error during write of data");
+            }
+
+            let rfile = ofile.reopen().expect("Can't create tmpfile");
+            let (mut reader, compression) =
+                get_reader(Box::new(rfile)).expect("Error reading from tmpfile");
+
+            assert_eq!(compression, compression::Format::Compress);
+
+            let mut buffer = Vec::new();
+            reader
+                .read_to_end(&mut buffer)
+                .expect("Error during reading");
+            assert_eq!(LOREM_IPSUM, buffer.as_slice());
+        }
+
+        #[cfg(feature = "compress")]
+        #[test]
+        fn compress_code_sizes() {
+            // enough data to fill the dictionary, with all code sizes and a clear code
+            let data: Vec<u8> = (0..400_000u32)
+                .map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8)
+                .collect();
+
+            let mut buf: Vec<u8> = vec![];
+            {
+                let mut writer = get_writer(
+                    Box::new(&mut buf),
+                    compression::Format::Compress,
+                    Level::Six,
+                )
+                .unwrap();
+                writer.write_all(&data).expect("Error during write of data");
+            }
+
+            let (mut reader, compression) =
+                get_reader(Box::new(buf.as_slice())).expect("Error reading from buffer");
+
+            assert_eq!(compression, compression::Format::Compress);
+
+            let mut buffer = Vec::new();
+            reader
+                .read_to_end(&mut buffer)
+                .expect("Error during reading");
+            assert_eq!(data, buffer);
+        }
+
+        #[cfg(feature = "compress")]
+        #[test]
+        fn compress_fixtures() {
+            // block mode and max bits in the third byte of the header
+            for (file, header) in [(COMPRESS_BLOCK_FILE, 0x8a), (COMPRESS_NO_BLOCK_FILE, 0x0a)] {
+                assert_eq!(file[2], header);
+
+                let (mut reader, compression) =
+                    get_reader(Box::new(file)).expect("Error reading from buffer");
+
+                assert_eq!(compression, compression::Format::Compress);
+
+                let mut buffer = Vec::new();
+                reader
+                    .read_to_end(&mut buffer)
+                    .expect("Error during reading");
+                assert_eq!(LOREM_IPSUM.repeat(30), buffer);
+            }
+        }
+
+        #[test]
+        #[cfg(not(feature = "lzo"))]
+        fn no_lzo_feature() {
+            assert!(
+                get_writer(Box::new(vec![]), compression::Format::Lzo, Level::Six).is_err(),
+                "lzo disabled, this assertion should fail"
+            );
+
+            assert!(
+                get_reader(Box::new(LZO_FILE)).is_err(),
+                "lzo disabled, this assertion should fail"
+            );
+        }
+
+        #[cfg(feature = "lzo")]
+        #[test]
+        fn lzo() {
+            let ofile = NamedTempFile::new().expect("Can't create tmpfile");
+
+            {
+                let wfile = ofile.reopen().expect("Can't create tmpfile");
+                let mut writer =
+                    get_writer(Box::new(wfile), compression::Format::Lzo, Level::Six).unwrap();
+                writer
+                    .write_all(LOREM_IPSUM)
+                    .expect("Error during write of data");
+            }
+
+            let rfile = ofile.reopen().expect("Can't create tmpfile");
+            let (mut reader, compression) =
+                get_reader(Box::new(rfile)).expect("Error reading from tmpfile");
+
+            assert_eq!(compression, compression::Format::Lzo);
+
+            let mut buffer = Vec::new();
+            reader
+                .read_to_end(&mut buffer)
+                .expect("Error during reading");
+            assert_eq!(LOREM_IPSUM, buffer.as_slice());
+        }
+
+        #[cfg(feature = "lzo")]
+        #[test]
+        fn lzo_blocks() {
+            // several blocks, with literal runs and matches of all lengths and distances
+            let data: Vec<u8> = (0..1_000_000u32)
+                .map(|i| match (i / 50_000) % 3 {
+                    0 => (i.wrapping_mul(2_654_435_761) >> 13) as u8,
+                    1 => (i % 40_000 % 251) as u8,
+                    _ => b'A',
+                })
+                .collect();
+
+            let mut buf: Vec<u8> = vec![];
+            {
+                let mut writer =
+                    get_writer(Box::new(&mut buf), compression::Format::Lzo, Level::Six).unwrap();
+                writer.write_all(&data).expect("Error during write of data");
+            }
+            assert!(buf.len() < data.len());
+
+            let (mut reader, compression) =
+                get_reader(Box::new(buf.as_slice())).expect("Error reading from buffer");
+
+            assert_eq!(compression, compression::Format::Lzo);
+
+            let mut buffer = Vec::new();
+            reader
+                .read_to_end(&mut buffer)
+                .expect("Error during reading");
+            assert_eq!(data, buffer);
+        }
+
+        #[cfg(feature = "lzo")]
+        #[test]
+        fn lzo_file() {
+            let (mut reader, compression) =
+                get_reader(Box::new(LZO_FILE)).expect("Error reading lzop file");
+
+            assert_eq!(compression, compression::Format::Lzo);
+
+            let mut contents = String::new();
+            reader
+                .read_to_string(&mut contents)
+                .expect("Error during reading");
+            assert_eq!(contents, "I'm compress in lzo, compress in lzo\n");
+        }
+
+        #[cfg(feature = "lzo")]
+        #[test]
+        fn lzo_checksum() {
+            // change a literal of the compressed block
+            let mut data = LZO_FILE.to_vec();
+            data[60] ^= 1;
+
+            let (mut reader, _) = get_reader(Box::new(&data[..])).expect("Error reading header");
+            let mut buffer = Vec::new();
+            let error = reader.read_to_end(&mut buffer).unwrap_err();
+            assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        }
+
+        #[test]
+        #[cfg(not(feature = "gz"))]
+        fn no_zlib_feature() {
+            assert!(
+                get_writer(Box::new(vec![]), compression::Format::Zlib, Level::Six).is_err(),
+                "gz disabled, this assertion should fail"
+            );
+
+            assert!(
+                get_reader_with_format(Box::new(ZLIB_FILE), compression::Format::Zlib).is_err(),
+                "gz disabled, this assertion should fail"
+            );
+
+            assert!(
+                get_writer(Box::new(vec![]), compression::Format::Deflate, Level::Six).is_err(),
+                "gz disabled, this assertion should fail"
+            );
+        }
+
+        #[cfg(feature = "gz")]
+        #[test]
+        fn zlib() {
+            let ofile = NamedTempFile::new().expect("Can't create tmpfile");
+
+            {
+                let wfile = ofile.reopen().expect("Can't create tmpfile");
+                let mut writer =
+                    get_writer(Box::new(wfile), compression::Format::Zlib, Level::Six).unwrap();
+                writer
+                    .write_all(LOREM_IPSUM)
+                    .expect("Error during write of data");
+            }
+
+            let rfile = ofile.reopen().expect("Can't create tmpfile");
+            let (_, compression) = sniff(Box::new(rfile)).expect("Error reading from tmpfile");
+            assert_eq!(compression, compression::Format::No);
+
+            let rfile = ofile.reopen().expect("Can't create tmpfile");
+            let (mut reader, compression) =
+                get_reader_with_format(Box::new(rfile), compression::Format::Zlib)
+                    .expect("Error reading from tmpfile");
+
+            assert_eq!(compression, compression::Format::Zlib);
+
+            let mut buffer = Vec::new();
+            reader
+                .read_to_end(&mut buffer)
+                .expect("Error during reading");
+            assert_eq!(LOREM_IPSUM, buffer.as_slice());
+        }
+
+        #[cfg(feature = "gz")]
+        #[test]
+        fn deflate() {
+            let ofile = NamedTempFile::new().expect("Can't create tmpfile");
+
+            {
+                let wfile = ofile.reopen().expect("Can't create tmpfile");
+                let mut writer =
+                    get_writer(Box::new(wfile), compression::Format::Deflate, Level::Six).unwrap();
+                writer
+                    .write_all(LOREM_IPSUM)
+                    .expect("Error during write of data");
+            }
+
+            let rfile = ofile.reopen().expect("Can't create tmpfile");
+            let (mut reader, compression) =
+                get_reader_with_format(Box::new(rfile), compression::Format::Deflate)
+                    .expect("Error reading from tmpfile");
+
+            assert_eq!(compression, compression::Format::Deflate);
+
+            let mut buffer = Vec::new();
+            reader
+                .read_to_end(&mut buffer)
+                .expect("Error during reading");
+            assert_eq!(LOREM_IPSUM, buffer.as_slice());
+        }
+
+        #[test]
+        #[cfg(not(feature = "zip"))]
+        fn no_zip_feature() {
+            assert!(
+                get_writer(Box::new(vec![]), compression::Format::Zip, Level::Six).is_err(),
+                "zip disabled, this assertion should fail"
+            );
+
+            assert!(
+                get_reader(Box::new(ZIP_FILE)).is_err(),
+                "zip disabled, this assertion should fail"
+            );
+        }
+
+        #[cfg(feature = "zip")]
+        #[test]
+        fn zip() {
+            let ofile = NamedTempFile::new().expect("Can't create tmpfile");
+
+            {
+                let wfile = ofile.reopen().expect("Can't create tmpfile");
+                let mut writer =
+                    get_writer(Box::new(wfile), compression::Format::Zip, Level::Six).unwrap();
+                writer
+                    .write_all(LOREM_IPSUM)
+                    .expect("Error during write of data");
+            }
+
+            let rfile = ofile.reopen().expect("Can't create tmpfile");
+            let (mut reader, compression) =
+                get_reader(Box::new(rfile)).expect("Error reading from tmpfile");
+
+            assert_eq!(compression, compression::Format::Zip);
+
+            let mut buffer = Vec::new();
+            reader
+                .read_to_end(&mut buffer)
+                .expect("Error during reading");
+            assert_eq!(LOREM_IPSUM, buffer.as_slice());
+        }
+
+        #[cfg(feature = "zip")]
+        #[test]
+        fn zip_entry() {
+            let (mut reader, compression) =
+                get_entry_reader(Box::new(ZIP_TWO_FILES), "b.fastq").expect("Error reading entry");
+
+            assert_eq!(compression, compression::Format::Zip);
+
+            let mut buffer = Vec::new();
+            reader
+                .read_to_end(&mut buffer)
+                .expect("Error during reading");
+            assert_eq!(b"@b.fastq\nACGT\n+\nIIII\n", buffer.as_slice());
+
+            assert!(matches!(
+                get_entry_reader(Box::new(ZIP_TWO_FILES), "c.fastq"),
+                Err(Error::EntryNotFound(name)) if name == "c.fastq"
+            ));
+            assert!(matches!(
+                get_entry_reader(Box::new(LOREM_IPSUM), "c.fastq"),
+                Err(Error::NotAnArchive)
+            ));
+        }
+
+        #[cfg(feature = "zip")]
+        #[test]
+        fn zip_several_files() {
+            let files = vec!["a.fastq".to_string(), "b.fastq".to_string()];
+
+            // streams are only known to hold several files once the first one is read
+            let (mut reader, _) = get_reader(Box::new(ZIP_TWO_FILES)).expect("Error reading zip");
+            let error = reader.read_to_end(&mut Vec::new()).unwrap_err();
+            assert!(matches!(
+                error.get_ref().and_then(|e| e.downcast_ref::<Error>()),
+                Some(Error::MultipleEntries(entries)) if *entries == files
+            ));
+
+            let file = NamedTempFile::new().expect("Can't create tmpfile");
+            std::fs::write(file.path(), ZIP_TWO_FILES).expect("Error during write of data");
+            assert!(matches!(
+                from_path(file.path()),
+                Err(Error::MultipleEntries(entries)) if entries == files
+            ));
+        }
+
+        #[cfg(all(unix, feature = "gz"))]
+        #[test]
+        fn from_path_pipe() {
+            let dir = tempfile::tempdir().expect("Can't create tmpdir");
+            let path = dir.path().join("reads.fastq.gz");
+            let status = std::process::Command::new("mkfifo")
+                .arg(&path)
+                .status()
+                .expect("Can't run mkfifo");
+            assert!(status.success());
+
+            let mut buffer = vec![];
+            {
+                let mut writer =
+                    get_writer(Box::new(&mut buffer), compression::Format::Gzip, Level::One)
+                        .unwrap();
+                writer
+                    .write_all(LOREM_IPSUM)
+                    .expect("Error during write of data");
+            }
+            // opening a pipe blocks until its other end is opened
+            let writer_path = path.clone();
+            let writer = std::thread::spawn(move || std::fs::write(writer_path, buffer));
+
+            // pipes can't seek, the data is only read once
+            let (mut reader, format) = from_path(&path).expect("Error reading pipe");
+            let mut contents = Vec::new();
+            reader
+                .read_to_end(&mut contents)
+                .expect("Error during reading");
+            writer.join().unwrap().expect("Error during write of pipe");
+
+            assert_eq!(format, compression::Format::Gzip);
+            assert_eq!(LOREM_IPSUM, contents.as_slice());
+        }
+
+        #[cfg(feature = "zip")]
+        #[test]
+        fn zip_to_path() {
+            let dir = tempfile::tempdir().expect("Can't create tmpdir");
+            let path = dir.path().join("reads.fastq.zip");
+
+            {
+                let mut writer = to_path(&path, compression::Format::Zip, Level::One).unwrap();
+                writer
+                    .write_all(LOREM_IPSUM)
+                    .expect("Error during write of data");
+            }
+
+            let file = std::fs::File::open(&path).expect("Can't open tmpfile");
+            let (mut reader, _) =
+                get_entry_reader(Box::new(file), "reads.fastq").expect("Error reading entry");
+
+            let mut buffer = Vec::new();
+            reader
+                .read_to_end(&mut buffer)
+                .expect("Error during reading");
+            assert_eq!(LOREM_IPSUM, buffer.as_slice());
+        }
+
+        #[cfg(feature = "gz")]
+        #[test]
+        fn recursive() {
+            let mut buffer = vec![];
+            {
+                let inner =
+                    get_writer(Box::new(&mut buffer), compression::Format::Gzip, Level::One)
+                        .unwrap();
+                let mut writer = get_writer(inner, compression::Format::Gzip, Level::One).unwrap();
+                writer
+                    .write_all(LOREM_IPSUM)
+                    .expect("Error during write of data");
+            }
+
+            let (mut reader, formats) =
+                get_reader_recursive(Box::new(&buffer[..]), 8).expect("Error reading from buffer");
+            assert_eq!(
+                formats,
+                [compression::Format::Gzip, compression::Format::Gzip]
+            );
+
+            let mut contents = Vec::new();
+            reader
+                .read_to_end(&mut contents)
+                .expect("Error during reading");
+            assert_eq!(LOREM_IPSUM, contents.as_slice());
+
+            // the last layer is left when the depth limit is reached
+            let (reader, formats) =
+                get_reader_recursive(Box::new(&buffer[..]), 1).expect("Error reading from buffer");
+            assert_eq!(formats, [compression::Format::Gzip]);
+            let (_, compression) = sniff(reader).expect("Error reading from buffer");
+            assert_eq!(compression, compression::Format::Gzip);
+
+            let (_, formats) =
+                get_reader_recursive(Box::new(LOREM_IPSUM), 8).expect("Error reading from buffer");
+            assert!(formats.is_empty());
+        }
+
+        #[cfg(feature = "gz")]
+        #[test]
+        fn recursive_empty() {
+            let mut buffer = vec![];
+            {
+                let inner =
+                    get_writer(Box::new(&mut buffer), compression::Format::Gzip, Level::One)
+                        .unwrap();
+                get_writer(inner, compression::Format::Gzip, Level::One).unwrap();
+            }
+
+            let (mut reader, formats) =
+                get_reader_recursive(Box::new(&buffer[..]), 8).expect("Error reading from buffer");
+            assert_eq!(
+                formats,
+                [compression::Format::Gzip, compression::Format::Gzip]
+            );
+
+            let mut contents = Vec::new();
+            reader
+                .read_to_end(&mut contents)
+                .expect("Error during reading");
+            assert!(contents.is_empty());
+        }
+
+        #[cfg(feature = "zstd")]
+        #[test]
+        fn zstd_skippable_frames() {
+            // like pzstd, each frame is preceded by a skippable frame with its size
+            let mut buffer = vec![];
+            for _ in 0..2 {
+                let mut frame = vec![];
+                {
+                    let mut writer =
+                        get_writer(Box::new(&mut frame), compression::Format::Zstd, Level::One)
+                            .unwrap();
+                    writer
+                        .write_all(LOREM_IPSUM)
+                        .expect("Error during write of data");
+                }
+
+                buffer.extend([0x50, 0x2a, 0x4d, 0x18, 0x04, 0x00, 0x00, 0x00]);
+                buffer.extend((frame.len() as u32).to_le_bytes());
+                buffer.extend(frame);
+            }
+
+            let (frames, stream) =
+                read_skippable_frames(Box::new(&buffer[..])).expect("Error reading from buffer");
+            assert_eq!(frames.len(), 1);
+            assert_eq!(frames[0].variant, 0);
+            assert_eq!(frames[0].data.len(), 4);
+
+            let (mut reader, compression) = get_reader(stream).expect("Error reading from buffer");
+            assert_eq!(compression, compression::Format::Zstd);
+
+            let mut contents = Vec::new();
+            reader
+                .read_to_end(&mut contents)
+                .expect("Error during reading");
+            let mut result: Vec<u8> = LOREM_IPSUM.into();
+            result.extend(LOREM_IPSUM);
+            assert_eq!(result, contents);
+
+            let (mut reader, compression) =
+                get_reader(Box::new(&buffer[..])).expect("Error reading from buffer");
+            assert_eq!(compression, compression::Format::Zstd);
+
+            let mut contents = Vec::new();
+            reader
+                .read_to_end(&mut contents)
+                .expect("Error during reading");
+            assert_eq!(result, contents);
+        }
+
+        #[test]
+        fn truncated_skippable_frame() {
+            assert!(read_skippable_frames(Box::new(&ZSTD_SKIPPABLE_FILE[..10])).is_err());
+        }
+
+        #[cfg(feature = "zstd-legacy")]
+        #[test]
+        fn zstd_legacy() {
+            let (mut reader, compression) =
+                get_reader(Box::new(ZSTD_LEGACY_FILE)).expect("Error reading from buffer");
+
+            assert_eq!(compression, compression::Format::Zstd);
+
+            let mut buffer = Vec::new();
+            reader
+                .read_to_end(&mut buffer)
+                .expect("Error during reading");
+            assert_eq!(b"I'm compress in zstd v0.7\n", buffer.as_slice());
+        }
+
+        #[test]
+        #[cfg(all(feature = "zstd", not(feature = "zstd-legacy")))]
+        fn no_zstd_legacy_feature() {
+            assert!(matches!(
+                get_reader(Box::new(ZSTD_LEGACY_FILE)),
+                Err(Error::UnsupportedFormat {
+                    feature: Some("zstd-legacy"),
+                    ..
+                })
+            ));
+        }
+    }
+
+    mod compression_format_detection {
+        use super::*;
+
+        #[test]
+        fn gzip() {
+            let (_, compression) = sniff(Box::new(GZIP_FILE)).expect("Error in read file");
+            assert_eq!(compression, compression::Format::Gzip);
+        }
+
+        #[test]
+        fn bzip() {
+            let (_, compression) = sniff(Box::new(BZIP_FILE)).expect("Error in read file");
+            assert_eq!(compression, compression::Format::Bzip);
+        }
+
+        #[test]
+        fn lzma() {
+            let (_, compression) = sniff(Box::new(LZMA_FILE)).expect("Error in read file");
+            assert_eq!(compression, compression::Format::Lzma);
+        }
+
+        #[test]
+        fn lzma_alone() {
+            // no magic number, only read on request or for `.lzma` files
+            let (_, compression) = sniff(Box::new(LZMA_ALONE_FILE)).expect("Error in read file");
+            assert_eq!(compression, compression::Format::No);
+        }
+
+        #[test]
+        fn zstd() {
+            let (_, compression) = sniff(Box::new(ZSTD_FILE)).expect("Error in read file");
+            assert_eq!(compression, compression::Format::Zstd);
+        }
+
+        #[test]
+        fn lz4() {
+            let (_, compression) = sniff(Box::new(LZ4_FILE)).expect("Error in read file");
+            assert_eq!(compression, compression::Format::Lz4);
+
+            let (_, compression) = sniff(Box::new(LZ4_LEGACY_FILE)).expect("Error in read file");
+            assert_eq!(compression, compression::Format::Lz4);
+        }
+
+        #[test]
+        fn snappy() {
+            let (_, compression) = sniff(Box::new(SNAPPY_FILE)).expect("Error in read file");
+            assert_eq!(compression, compression::Format::Snappy);
+        }
+
+        #[test]
+        fn lzip() {
+            let (_, compression) = sniff(Box::new(LZIP_FILE)).expect("Error in read file");
+            assert_eq!(compression, compression::Format::Lzip);
+        }
+
+        #[test]
+        fn compress() {
+            let (_, compression) = sniff(Box::new(COMPRESS_FILE)).expect("Error in read file");
+            assert_eq!(compression, compression::Format::Compress);
+        }
+
+        #[test]
+        fn lzo() {
+            let (_, compression) = sniff(Box::new(LZO_FILE)).expect("Error in read file");
+            assert_eq!(compression, compression::Format::Lzo);
+        }
+
+        #[test]
+        fn zlib() {
+            // the header matches some text, only read on request or for `.zz` files
+            let (_, compression) = sniff(Box::new(ZLIB_FILE)).expect("Error in read file");
+            assert_eq!(compression, compression::Format::No);
+        }
+
+        #[test]
+        fn zlib_like_text() {
+            // valid zlib headers, some followed by a valid deflate block header
+            for text in [
+                &b"x rays and more"[..],
+                b"80 reads mapped",
+                b"HKG airport",
+                b"x^2 + y^2 = z^2 is the equation\n",
+                b"x^y is x to the power of y\n",
+            ] {
+                let (mut reader, compression) =
+                    get_reader(Box::new(text)).expect("Error in read file");
+                assert_eq!(compression, compression::Format::No);
+
+                let mut contents = Vec::new();
+                reader.read_to_end(&mut contents).unwrap();
+                assert_eq!(contents, text);
+            }
+        }
+
+        #[test]
+        fn zip() {
+            let (_, compression) = sniff(Box::new(ZIP_FILE)).expect("Error in read file");
+            assert_eq!(compression, compression::Format::Zip);
+        }
+
+        #[test]
+        fn zstd_skippable() {
+            let (_, compression) =
+                sniff(Box::new(ZSTD_SKIPPABLE_FILE)).expect("Error in read file");
+            assert_eq!(compression, compression::Format::Zstd);
+        }
+
+        #[test]
+        fn lz4_skippable() {
+            let (_, compression) = sniff(Box::new(LZ4_SKIPPABLE_FILE)).expect("Error in read file");
+            assert_eq!(compression, compression::Format::Lz4);
+        }
+
+        #[test]
+        fn zstd_legacy() {
+            let (_, compression) = sniff(Box::new(ZSTD_LEGACY_FILE)).expect("Error in read file");
+            assert_eq!(compression, compression::Format::Zstd);
+        }
+
+        #[test]
+        fn detect() {
+            assert_eq!(
+                compression::Format::detect(GZIP_FILE),
+                Some(compression::Format::Gzip)
+            );
+            assert_eq!(compression::Format::detect(&LZO_FILE[..8]), None);
+            assert_eq!(
+                compression::Format::detect(&LZO_FILE[..9]),
+                Some(compression::Format::Lzo)
+            );
+            assert_eq!(compression::Format::detect(&LOREM_IPSUM[..15]), None);
+            assert_eq!(
+                compression::Format::detect(&LOREM_IPSUM[..16]),
+                Some(compression::Format::No)
+            );
+
+            // the frame after skippable frames gives the format
+            assert_eq!(compression::Format::detect(&LZ4_SKIPPABLE_FILE[..11]), None);
+            assert_eq!(
+                compression::Format::detect(LZ4_SKIPPABLE_FILE),
+                Some(compression::Format::Lz4)
+            );
+        }
+
+        #[test]
+        fn bufread() {
+            let reader = io::BufReader::new(LZO_FILE);
+            let (mut reader, compression) = sniff_bufread(reader).expect("Error in read file");
+            assert_eq!(compression, compression::Format::Lzo);
+
+            // nothing was consumed
+            let mut contents = Vec::new();
+            reader
+                .read_to_end(&mut contents)
+                .expect("Error during reading");
+            assert_eq!(contents, LZO_FILE);
+
+            assert!(matches!(
+                sniff_bufread(SHORT_FILE),
+                Err(Error::FileTooShort)
+            ));
+        }
+
+        #[test]
+        fn bufread_chunked() {
+            /// Reads at most `size` bytes at once.
+            struct Chunked<'a> {
+                data: &'a [u8],
+                size: usize,
+            }
+
+            impl io::Read for Chunked<'_> {
+                fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                    let len = buf.len().min(self.size).min(self.data.len());
+                    buf[..len].copy_from_slice(&self.data[..len]);
+                    self.data = &self.data[len..];
+                    Ok(len)
+                }
+            }
+
+            for (data, format) in [
+                (LZO_FILE, compression::Format::Lzo),
+                (SNAPPY_FILE, compression::Format::Snappy),
+                (LZMA_FILE, compression::Format::Lzma),
+                (LOREM_IPSUM, compression::Format::No),
+            ] {
+                for size in [3, 8] {
+                    let reader = io::BufReader::new(Chunked { data, size });
+                    let (mut reader, compression) =
+                        sniff_bufread(reader).expect("Error in read file");
+                    assert_eq!(compression, format, "{size} bytes chunks");
+
+                    // the bytes read to find out the format are read again
+                    let mut contents = Vec::new();
+                    reader
+                        .read_to_end(&mut contents)
+                        .expect("Error during reading");
+                    assert_eq!(contents, data);
+                }
+            }
+        }
+
+        #[test]
+        fn too_short() {
+            let result = sniff(Box::new(SHORT_FILE));
+            assert!(result.is_err());
+        }
+
+        #[test]
+        fn no_compression() {
+            let (_, compression) = sniff(Box::new(LOREM_IPSUM)).expect("Error in read file");
+            assert_eq!(compression, compression::Format::No);
+        }
+
+        #[test]
+        fn binary() {
+            // xorshift generator, for the same bytes on each run
+            let mut state = 0x2545_f491_4f6c_dd1d_u64;
+            let random = (0..4096)
+                .map(|_| {
+                    state ^= state << 13;
+                    state ^= state >> 7;
+                    state ^= state << 17;
+                    state as u8
+                })
+                .collect::<Vec<u8>>();
+
+            for input in [&[0; 13][..], &[0; 64], &random] {
+                let (mut reader, compression) =
+                    get_reader(Box::new(input)).expect("Error in read file");
+                assert_eq!(compression, compression::Format::No);
+
+                let mut contents = Vec::new();
+                reader.read_to_end(&mut contents).unwrap();
+                assert_eq!(contents, input);
+            }
+        }
+    }
+
+    mod content_detection {
+        use super::*;
+
+        #[cfg(feature = "gz")]
+        #[test]
+        fn bam() {
+            let mut buffer = vec![];
+            {
+                let mut writer =
+                    get_writer(Box::new(&mut buffer), compression::Format::Gzip, Level::One)
+                        .unwrap();
+                writer
+                    .write_all(b"BAM\x01\x00\x00\x00\x00")
+                    .expect("Error during write of data");
+            }
+
+            let (reader, format) = get_reader(Box::new(&buffer[..])).expect("Error reading");
+            let (mut reader, content) = sniff_content(reader).expect("Error reading content");
+            assert_eq!(format, compression::Format::Gzip);
+            assert_eq!(content, Content::Bam);
+
+            let mut contents = Vec::new();
+            reader
+                .read_to_end(&mut contents)
+                .expect("Error during reading");
+            assert_eq!(contents, b"BAM\x01\x00\x00\x00\x00");
+        }
+
+        #[test]
+        fn long_stream() {
+            let data = b"@read\nACGT\n+\nIIII\n".repeat(1000);
+            let (mut reader, content) =
+                sniff_content(Box::new(&data[..])).expect("Error reading content");
+            assert_eq!(content, Content::Fastq);
+
+            let mut contents = Vec::new();
+            reader
+                .read_to_end(&mut contents)
+                .expect("Error during reading");
+            assert_eq!(contents, data);
+        }
+
+        #[test]
+        fn open_path() {
+            let file = NamedTempFile::new().expect("Can't create tmpfile");
+            {
+                let mut writer = to_path(file.path(), compression::Format::No, Level::One)
+                    .expect("Error creating file");
+                writer
+                    .write_all(b"chr1\t100\t200\n")
+                    .expect("Error during write of data");
+            }
+
+            let (mut reader, format, content) = open_any(file.path()).expect("Error opening file");
+            assert_eq!(format, compression::Format::No);
+            assert_eq!(content, Content::Bed);
+
+            let mut contents = Vec::new();
+            reader
+                .read_to_end(&mut contents)
+                .expect("Error during reading");
+            assert_eq!(contents, b"chr1\t100\t200\n");
+        }
+    }
+
+    mod extension {
+        use super::*;
+
+        #[test]
+        fn from_extension() {
+            for (extension, format) in [
+                ("gz", Some(compression::Format::Gzip)),
+                ("bgz", Some(compression::Format::Gzip)),
+                ("tar.gz", Some(compression::Format::Gzip)),
+                (".fq.gz", Some(compression::Format::Gzip)),
+                ("bz2", Some(compression::Format::Bzip)),
+                ("xz", Some(compression::Format::Lzma)),
+                ("ZST", Some(compression::Format::Zstd)),
+                ("Z", Some(compression::Format::Compress)),
+                ("z", None),
+                ("fastq", None),
+                ("", None),
+            ] {
+                assert_eq!(compression::Format::from_extension(extension), format);
+            }
+
+            assert_eq!(
+                compression::Format::from_path("data/archive.tar.zst"),
+                Some(compression::Format::Zstd)
+            );
+            assert_eq!(compression::Format::from_path("data/reads"), None);
+        }
+
+        #[cfg(feature = "gz")]
+        #[test]
+        fn auto_format() {
+            let dir = tempfile::tempdir().expect("Can't create tmpdir");
+            let path = dir.path().join("reads.fq.gz");
+            {
+                let mut writer = to_path_auto(&path, Level::One).expect("Error creating file");
+                writer
+                    .write_all(LOREM_IPSUM)
+                    .expect("Error during write of data");
+            }
+
+            let (mut reader, format, mismatch) =
+                from_path_checked(&path, ExtensionCheck::Reject).expect("Error reading file");
+            assert_eq!(format, compression::Format::Gzip);
+            assert_eq!(mismatch, None);
+
+            let mut contents = Vec::new();
+            reader
+                .read_to_end(&mut contents)
+                .expect("Error during reading");
+            assert_eq!(contents, LOREM_IPSUM);
+        }
+
+        #[test]
+        fn mismatch() {
+            // an uncompressed file with a compressed file extension
+            let dir = tempfile::tempdir().expect("Can't create tmpdir");
+            let path = dir.path().join("reads.fq.gz");
+            std::fs::write(&path, LOREM_IPSUM).expect("Error during write of data");
+
+            let (_, format, mismatch) =
+                from_path_checked(&path, ExtensionCheck::Report).expect("Error reading file");
+            assert_eq!(format, compression::Format::No);
+            assert_eq!(
+                mismatch,
+                Some(compression::ExtensionMismatch {
+                    expected: compression::Format::Gzip,
+                    detected: compression::Format::No,
+                })
+            );
+
+            assert!(matches!(
+                from_path_checked(&path, ExtensionCheck::Reject),
+                Err(Error::ExtensionMismatch { .. })
+            ));
+        }
+    }
+
+    mod buffered {
+        use super::*;
+        use std::io::BufRead;
+
+        #[cfg(feature = "gz")]
+        #[test]
+        fn bufread() {
+            let mut buffer = vec![];
+            {
+                let mut writer =
+                    get_writer(Box::new(&mut buffer), compression::Format::Gzip, Level::One)
+                        .unwrap();
+                writer
+                    .write_all(b"@read1\nACGT\n+\nIIII\n")
+                    .expect("Error during write of data");
+            }
+
+            let (reader, format) = get_bufread(Box::new(&buffer[..]), 4).expect("Error reading");
+            assert_eq!(format, compression::Format::Gzip);
+
+            let lines = reader
+                .lines()
+                .collect::<Result<Vec<_>, _>>()
+                .expect("Error during reading");
+            assert_eq!(lines, ["@read1", "ACGT", "+", "IIII"]);
+        }
+
+        #[test]
+        fn path_uncompressed() {
+            let file = NamedTempFile::new().expect("Can't create tmpfile");
+            std::fs::write(file.path(), LOREM_IPSUM).expect("Error during write of data");
+
+            // the format is detected with a buffer smaller than the magic numbers
+            let (reader, format) = from_path_bufread(file.path(), 1).expect("Error reading");
+            assert_eq!(format, compression::Format::No);
+
+            let lines = reader
+                .lines()
+                .collect::<Result<Vec<_>, _>>()
+                .expect("Error during reading");
+            assert_eq!(lines, [String::from_utf8_lossy(LOREM_IPSUM)]);
+        }
+
+        #[cfg(feature = "gz")]
+        #[test]
+        fn path_compressed() {
+            let file = NamedTempFile::new().expect("Can't create tmpfile");
+            {
+                let mut writer = to_path(file.path(), compression::Format::Gzip, Level::One)
+                    .expect("Error creating file");
+                writer
+                    .write_all(LOREM_IPSUM)
+                    .expect("Error during write of data");
+            }
+
+            let (mut reader, format) =
+                from_path_bufread(file.path(), 1 << 16).expect("Error reading");
+            assert_eq!(format, compression::Format::Gzip);
+
+            let mut contents = Vec::new();
+            reader
+                .read_to_end(&mut contents)
+                .expect("Error during reading");
+            assert_eq!(contents, LOREM_IPSUM);
+        }
+    }
+
+    mod custom_codec {
+        use super::*;
+
+        /// A magic number in front of uncompressed data.
+        struct Tagged;
+
+        impl codec::Codec for Tagged {
+            fn format(&self) -> compression::Format {
+                compression::Format::Custom("tagged")
+            }
+
+            fn matches(&self, first_bytes: &[u8]) -> bool {
+                first_bytes.starts_with(b"TAGGED")
+            }
+
+            fn decoder<'a>(
+                &self,
+                mut in_stream: Box<dyn io::Read + 'a>,
+            ) -> Result<Box<dyn io::Read + 'a>, Error> {
+                in_stream.read_exact(&mut [0; 6])?;
+                Ok(in_stream)
+            }
+
+            fn encoder<'a>(
+                &self,
+                mut out_stream: Box<dyn io::Write + 'a>,
+                _: Level,
+            ) -> Result<Box<dyn io::Write + 'a>, Error> {
+                out_stream.write_all(b"TAGGED")?;
+                Ok(out_stream)
+            }
+        }
+
+        #[test]
+        fn custom() {
+            codec::register(Tagged);
+
+            let mut buffer = vec![];
+            {
+                let mut writer = get_writer(
+                    Box::new(&mut buffer),
+                    compression::Format::Custom("tagged"),
+                    Level::One,
+                )
+                .expect("Error creating writer");
+                writer
+                    .write_all(LOREM_IPSUM)
+                    .expect("Error during write of data");
+            }
+            assert!(buffer.starts_with(b"TAGGED"));
+
+            let (mut reader, compression) =
+                get_reader(Box::new(&buffer[..])).expect("Error reading from buffer");
+            assert_eq!(compression, compression::Format::Custom("tagged"));
+
+            let mut contents = Vec::new();
+            reader
+                .read_to_end(&mut contents)
+                .expect("Error during reading");
+            assert_eq!(contents, LOREM_IPSUM);
+
+            // generic readers don't box the stream, registered codecs can't read it
+            assert!(matches!(
+                crate::generic::get_reader(&buffer[..]),
+                Err(Error::UnsupportedFormat { format, feature: None })
+                    if format == compression::Format::Custom("tagged")
+            ));
+        }
+
+        /// Matches gzip streams with a shadow header, it's tried before the built-in codecs.
+        struct Shadow;
+
+        impl codec::Codec for Shadow {
+            fn format(&self) -> compression::Format {
+                compression::Format::Custom("shadow")
+            }
+
+            fn matches(&self, first_bytes: &[u8]) -> bool {
+                first_bytes.starts_with(b"\x1f\x8bSHADOW")
+            }
+
+            fn decoder<'a>(
+                &self,
+                in_stream: Box<dyn io::Read + 'a>,
+            ) -> Result<Box<dyn io::Read + 'a>, Error> {
+                Ok(in_stream)
+            }
+
+            fn encoder<'a>(
+                &self,
+                out_stream: Box<dyn io::Write + 'a>,
+                _: Level,
+            ) -> Result<Box<dyn io::Write + 'a>, Error> {
+                Ok(out_stream)
+            }
+        }
+
+        #[test]
+        fn registered_first() {
+            codec::register(Shadow);
+
+            let (_, compression) =
+                sniff(Box::new(&b"\x1f\x8bSHADOW header"[..])).expect("Error in read file");
+            assert_eq!(compression, compression::Format::Custom("shadow"));
+
+            let (_, compression) =
+                sniff(Box::new(&b"\x1f\x8b\x08 gzip header"[..])).expect("Error in read file");
+            assert_eq!(compression, compression::Format::Gzip);
+        }
+
+        #[test]
+        fn built_in() {
+            let formats: Vec<_> = codec::codecs().iter().map(|c| c.format()).collect();
+            assert!(formats.contains(&compression::Format::Gzip));
+            assert!(formats.contains(&compression::Format::Brotli));
+            assert!(!formats.contains(&compression::Format::No));
+        }
+
+        #[test]
+        fn unknown() {
+            assert!(matches!(
+                get_writer(
+                    Box::new(vec![]),
+                    compression::Format::Custom("unknown"),
+                    Level::One
+                ),
+                Err(Error::UnknownFormat(_))
+            ));
+            assert!(matches!(
+                get_reader_with_format(
+                    Box::new(LOREM_IPSUM),
+                    compression::Format::Custom("unknown")
+                ),
+                Err(Error::UnknownFormat(_))
+            ));
+        }
+    }
+
+    mod errors {
+        use super::*;
+
+        /// Inner error of the error of a reader.
+        #[cfg(any(feature = "gz", feature = "lzma"))]
+        fn read_error(mut reader: Box<dyn io::Read + '_>) -> Error {
+            let mut contents = Vec::new();
+            let error = reader
+                .read_to_end(&mut contents)
+                .expect_err("Corrupted stream is read");
+
+            *error
+                .into_inner()
+                .expect("Error without inner error")
+                .downcast::<Error>()
+                .expect("Inner error isn't a niffler error")
+        }
+
+        #[cfg(feature = "gz")]
+        fn gzip() -> Vec<u8> {
+            let mut buffer = vec![];
+            {
+                let mut writer =
+                    get_writer(Box::new(&mut buffer), compression::Format::Gzip, Level::One)
+                        .unwrap();
+                writer
+                    .write_all(LOREM_IPSUM)
+                    .expect("Error during write of data");
+            }
+
+            buffer
+        }
+
+        #[cfg(not(feature = "gz"))]
+        #[test]
+        fn unsupported_format() {
+            assert!(matches!(
+                get_writer(Box::new(vec![]), compression::Format::Gzip, Level::One),
+                Err(Error::UnsupportedFormat { format, feature: Some("gz") }) if format == compression::Format::Gzip
+            ));
+        }
+
+        #[cfg(feature = "gz")]
+        #[test]
+        fn corrupt_data() {
+            let mut buffer = gzip();
+            // the first deflate block has the reserved block type
+            buffer[10] = 0x07;
+
+            let (reader, _) = get_reader(Box::new(&buffer[..])).expect("Error reading");
+            assert!(matches!(
+                read_error(reader),
+                Error::CorruptData { format, compressed_offset } if format == compression::Format::Gzip && compressed_offset > 10
+            ));
+        }
+
+        #[cfg(feature = "gz")]
+        #[test]
+        fn checksum_mismatch() {
+            let mut buffer = gzip();
+            let crc = buffer.len() - 8;
+            buffer[crc] ^= 0xff;
+
+            let (reader, _) = get_reader(Box::new(&buffer[..])).expect("Error reading");
+            assert!(matches!(
+                read_error(reader),
+                Error::ChecksumMismatch { format } if format == compression::Format::Gzip
+            ));
+        }
+
+        #[cfg(feature = "lzma")]
+        #[test]
+        fn truncated() {
+            let mut buffer = vec![];
+            {
+                let mut writer =
+                    get_writer(Box::new(&mut buffer), compression::Format::Lzma, Level::One)
+                        .unwrap();
+                writer
+                    .write_all(LOREM_IPSUM)
+                    .expect("Error during write of data");
+            }
+            buffer.truncate(buffer.len() / 2);
+
+            let (reader, _) = get_reader(Box::new(&buffer[..])).expect("Error reading");
+            assert!(matches!(
+                read_error(reader),
+                Error::Truncated { format, .. } if format == compression::Format::Lzma
+            ));
+        }
+
+        #[cfg(feature = "zstd")]
+        #[test]
+        fn input_error() {
+            struct Failing;
+
+            impl io::Read for Failing {
+                fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                    Err(io::Error::other("connection reset"))
+                }
+            }
+
+            // errors of the compressed stream aren't decoding errors
+            let input = io::Cursor::new(ZSTD_FILE).chain(Failing);
+            let (mut reader, _) =
+                get_reader_with_format(Box::new(input), compression::Format::Zstd)
+                    .expect("Error reading");
+            let error = reader.read_to_end(&mut vec![]).unwrap_err();
+            assert_eq!(error.to_string(), "connection reset");
+        }
+
+        #[test]
+        fn path() {
+            let dir = tempfile::tempdir().expect("Can't create tmpdir");
+            let path = dir.path().join("missing.fq.gz");
+
+            assert!(matches!(
+                from_path(&path),
+                Err(Error::PathIOError { path: error_path, source })
+                    if error_path == path && source.kind() == io::ErrorKind::NotFound
+            ));
+            assert!(matches!(
+                to_path(
+                    dir.path().join("missing/reads.fq"),
+                    compression::Format::No,
+                    Level::One
+                ),
+                Err(Error::PathIOError { .. })
+            ));
+        }
+    }
+
+    #[cfg(any(
+        feature = "gz",
+        feature = "bz2",
+        feature = "lzma",
+        feature = "zstd",
+        feature = "lz4",
+        feature = "brotli",
+        feature = "snappy",
+        feature = "lzip",
+        feature = "compress",
+        feature = "lzo",
+        feature = "zip"
+    ))]
+    mod truncated {
+        use super::*;
+
+        fn compressed(format: compression::Format) -> Vec<u8> {
+            let mut buffer = vec![];
+            {
+                let mut writer = get_writer(Box::new(&mut buffer), format, Level::One).unwrap();
+                writer
+                    .write_all(LOREM_IPSUM)
+                    .expect("Error during write of data");
+            }
+
+            buffer
+        }
+
+        /// Check the error of reading `format` cut after `cut` bytes.
+        fn assert_truncated_at(format: compression::Format, buffer: &[u8], cut: usize) {
+            let input = Box::new(&buffer[..cut]);
+            let reader = if format.is_undetectable() {
+                get_reader_with_format(input, format)
+            } else {
+                get_reader(input)
+            };
+            let error = match reader {
+                Ok((mut reader, _)) => {
+                    let mut contents = Vec::new();
+                    let error = reader
+                        .read_to_end(&mut contents)
+                        .expect_err("Truncated stream is read");
+                    *error
+                        .into_inner()
+                        .expect("Error without inner error")
+                        .downcast::<Error>()
+                        .expect("Inner error isn't a niffler error")
+                }
+                Err(error) => error,
+            };
+
+            assert!(
+                matches!(&error, Error::Truncated { format: name, .. } if *name == format),
+                "{format:?} cut at {cut}: {error}"
+            );
+        }
+
+        /// Check the errors of reading `format` cut at the middle and before its last byte.
+        #[cfg(any(
+            feature = "gz",
+            feature = "bz2",
+            feature = "lzma",
+            feature = "zstd",
+            feature = "lz4",
+            feature = "brotli",
+            feature = "snappy",
+            feature = "lzip",
+            feature = "lzo",
+            feature = "zip"
+        ))]
+        fn assert_truncated(format: compression::Format) {
+            let buffer = compressed(format);
+            assert_truncated_at(format, &buffer, buffer.len() / 2);
+            assert_truncated_at(format, &buffer, buffer.len() - 1);
+        }
+
+        #[cfg(feature = "gz")]
+        #[test]
+        fn gzip() {
+            assert_truncated(compression::Format::Gzip);
+        }
+
+        #[cfg(feature = "gz")]
+        #[test]
+        fn zlib() {
+            assert_truncated(compression::Format::Zlib);
+        }
+
+        #[cfg(feature = "gz")]
+        #[test]
+        fn deflate() {
+            assert_truncated(compression::Format::Deflate);
+        }
+
+        #[cfg(feature = "bz2")]
+        #[test]
+        fn bzip() {
+            assert_truncated(compression::Format::Bzip);
+        }
+
+        #[cfg(feature = "lzma")]
+        #[test]
+        fn lzma() {
+            assert_truncated(compression::Format::Lzma);
+            assert_truncated(compression::Format::LzmaAlone);
+        }
+
+        #[cfg(feature = "zstd")]
+        #[test]
+        fn zstd() {
+            assert_truncated(compression::Format::Zstd);
+        }
+
+        #[cfg(feature = "lz4")]
+        #[test]
+        fn lz4() {
+            assert_truncated(compression::Format::Lz4);
+        }
+
+        #[cfg(feature = "brotli")]
+        #[test]
+        fn brotli() {
+            assert_truncated(compression::Format::Brotli);
+        }
+
+        #[cfg(feature = "snappy")]
+        #[test]
+        fn snappy() {
+            assert_truncated(compression::Format::Snappy);
+        }
+
+        #[cfg(feature = "lzip")]
+        #[test]
+        fn lzip() {
+            assert_truncated(compression::Format::Lzip);
+        }
+
+        #[cfg(feature = "compress")]
+        #[test]
+        fn compress() {
+            // without end marker, only streams cut inside a code are truncated, the
+            // 9 bits codes after the 3 bytes header are cut after 1 + 9n bytes
+            let buffer = compressed(compression::Format::Compress);
+            for cut in [13, 49, 103] {
+                assert_truncated_at(compression::Format::Compress, &buffer, cut);
+            }
+        }
+
+        #[cfg(feature = "lzo")]
+        #[test]
+        fn lzo() {
+            assert_truncated(compression::Format::Lzo);
+        }
+
+        #[cfg(feature = "zip")]
+        #[test]
+        fn zip() {
+            assert_truncated(compression::Format::Zip);
+        }
+
+        #[cfg(feature = "gz")]
+        #[test]
+        fn decompressed() {
+            let mut buffer = compressed(compression::Format::Gzip);
+            // only the size of the data is missing
+            buffer.pop();
+
+            let (mut reader, _) = get_reader(Box::new(&buffer[..])).expect("Error reading");
+            let error = reader.read_to_end(&mut vec![]).unwrap_err();
+            assert_eq!(
+                error.to_string(),
+                format!(
+                    "Gzip stream is truncated, after {} decompressed bytes",
+                    LOREM_IPSUM.len()
+                )
+            );
         }
     }
 }
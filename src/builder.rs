@@ -0,0 +1,1052 @@
+//! Readers and writers with more options than the free functions.
+//!
+//! [from_path](crate::from_path), [to_path](crate::to_path) and their variants are
+//! [ReaderBuilder] and [WriterBuilder] with the default options.
+
+/* standard use */
+use std::io;
+use std::path::Path;
+
+/* project use */
+use crate::basic::codec;
+use crate::basic::compression::Format;
+use crate::error::Error;
+use crate::generic::{BoxedRead, BoxedWrite, Decoder, Encoder};
+use crate::level::Level;
+use crate::utils::Consumed;
+
+/// Size of the buffers of [io::BufReader] and [io::BufWriter].
+pub(crate) const DEFAULT_BUFFER_SIZE: usize = 8 * 1024;
+
+/// `LZMA_IGNORE_CHECK` of liblzma, [liblzma::stream::IGNORE_CHECK] has the value of another flag.
+#[cfg(any(feature = "lzma", feature = "lzip"))]
+const LZMA_IGNORE_CHECK: u32 = 0x10;
+
+/// Options of the decoders of built-in formats.
+#[derive(Debug, Clone)]
+pub(crate) struct DecoderOptions {
+    pub(crate) multi_member: bool,
+    pub(crate) verify_checksums: bool,
+    /// `None` keeps the default of zstd
+    pub(crate) zstd_window_log_max: Option<u32>,
+    pub(crate) xz_memlimit: u64,
+}
+
+impl Default for DecoderOptions {
+    fn default() -> Self {
+        DecoderOptions {
+            multi_member: true,
+            verify_checksums: true,
+            zstd_window_log_max: None,
+            xz_memlimit: u64::MAX,
+        }
+    }
+}
+
+impl DecoderOptions {
+    /// Flags of liblzma decoders.
+    #[cfg(any(feature = "lzma", feature = "lzip"))]
+    pub(crate) fn xz_flags(&self) -> u32 {
+        let mut flags = 0;
+        if self.multi_member {
+            flags |= liblzma::stream::CONCATENATED;
+        }
+        if !self.verify_checksums {
+            flags |= LZMA_IGNORE_CHECK;
+        }
+
+        flags
+    }
+}
+
+/// Options of the encoders of built-in formats.
+#[derive(Debug, Clone)]
+pub(crate) struct EncoderOptions {
+    pub(crate) level: Level,
+    pub(crate) threads: u32,
+    /// `None` keeps the default of the format
+    pub(crate) checksum: Option<bool>,
+    pub(crate) zstd_window_log: Option<u32>,
+    pub(crate) brotli_window: u32,
+    pub(crate) entry_name: String,
+}
+
+impl Default for EncoderOptions {
+    fn default() -> Self {
+        EncoderOptions {
+            level: Level::Six,
+            threads: 1,
+            checksum: None,
+            zstd_window_log: None,
+            brotli_window: 22,
+            entry_name: "-".to_string(),
+        }
+    }
+}
+
+/// Create readable streams with options.
+///
+/// # Example
+/// ```
+/// use std::io::BufRead;
+/// use niffler::{Error, ReaderBuilder, compression::Format};
+/// # fn main() -> Result<(), Error> {
+///
+/// # #[cfg(feature = "gz")] {
+/// let mut buffer = vec![];
+/// {
+///   let mut writer = niffler::get_writer(Box::new(&mut buffer), Format::Gzip, niffler::Level::One)?;
+///   writer.write_all(b"@read1\nACGT\n+\nIIII\n")?;
+/// }
+///
+/// let (reader, format) = ReaderBuilder::new()
+///     .formats([Format::Gzip, Format::No])
+///     .max_size(1 << 30)
+///     .output_buffer_size(64 * 1024)
+///     .get_bufread(Box::new(&buffer[..]))?;
+///
+/// assert_eq!(format, Format::Gzip);
+/// assert_eq!(reader.lines().count(), 4);
+/// # }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ReaderBuilder {
+    format: Option<Format>,
+    formats: Option<Vec<Format>>,
+    denied_formats: Vec<Format>,
+    input_buffer_size: usize,
+    output_buffer_size: usize,
+    limits: Limits,
+    options: DecoderOptions,
+}
+
+impl Default for ReaderBuilder {
+    fn default() -> Self {
+        ReaderBuilder {
+            format: None,
+            formats: None,
+            denied_formats: Vec::new(),
+            input_buffer_size: DEFAULT_BUFFER_SIZE,
+            output_buffer_size: DEFAULT_BUFFER_SIZE,
+            limits: Limits::default(),
+            options: DecoderOptions::default(),
+        }
+    }
+}
+
+impl ReaderBuilder {
+    /// A builder with the default options, the options of [get_reader](crate::get_reader).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read streams with this compression format, without trying to detect it.
+    pub fn format(mut self, format: Format) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    /// Only read streams compressed with these formats, [Format::No] must be given to
+    /// read uncompressed streams. Streams in other formats fail with [Error::FormatNotAllowed].
+    pub fn formats<I: IntoIterator<Item = Format>>(mut self, formats: I) -> Self {
+        self.formats = Some(formats.into_iter().collect());
+        self
+    }
+
+    /// Don't read streams compressed with these formats, they fail with [Error::FormatNotAllowed].
+    /// Formats that are both allowed by [ReaderBuilder::formats] and denied aren't read.
+    pub fn deny_formats<I: IntoIterator<Item = Format>>(mut self, formats: I) -> Self {
+        self.denied_formats = formats.into_iter().collect();
+        self
+    }
+
+    /// Size of the buffer used to read files, 8 KiB by default.
+    pub fn input_buffer_size(mut self, size: usize) -> Self {
+        self.input_buffer_size = size;
+        self
+    }
+
+    /// Size of the buffer of the decompressed stream returned by [ReaderBuilder::get_bufread]
+    /// and [ReaderBuilder::from_path_bufread], 8 KiB by default.
+    pub fn output_buffer_size(mut self, size: usize) -> Self {
+        self.output_buffer_size = size;
+        self
+    }
+
+    /// Maximum size of the decompressed stream, reading more fails with an [io::Error] whose
+    /// inner error is [Error::SizeLimitExceeded].
+    pub fn max_size(mut self, size: u64) -> Self {
+        self.limits.max_size = Some(size);
+        self
+    }
+
+    /// Maximum ratio of the decompressed size to the compressed size, reading more fails with an
+    /// [io::Error] whose inner error is [Error::RatioLimitExceeded].
+    ///
+    /// Together with [ReaderBuilder::max_size] it protects against decompression bombs, small
+    /// streams that decompress to huge ones. The ratio is checked from the first decompressed
+    /// bytes, small streams of repeated data can have a ratio of a few hundreds.
+    pub fn max_ratio(mut self, ratio: u64) -> Self {
+        self.limits.max_ratio = Some(ratio);
+        self
+    }
+
+    /// Verify checksums of the decompressed data, true by default.
+    ///
+    /// Verification can be disabled for xz, lzip, lzop and zip streams. The decoders of gzip,
+    /// zlib, bzip2, zstd, LZ4 and snappy streams always verify their checksums: reading them
+    /// fails with [Error::ChecksumRequired] when verification is disabled. The other formats
+    /// have no checksum, and codecs registered with [codec::register] aren't given this option.
+    pub fn verify_checksums(mut self, verify: bool) -> Self {
+        self.options.verify_checksums = verify;
+        self
+    }
+
+    /// Read all the members of gzip, bzip2, xz and lzip streams, and all the frames of zstd
+    /// streams, true by default. Only the first member or frame is read otherwise.
+    pub fn multi_member(mut self, multi_member: bool) -> Self {
+        self.options.multi_member = multi_member;
+        self
+    }
+
+    /// Maximum window log of zstd decoders, frames with a window larger than `2^window_log`
+    /// bytes fail with an [io::Error] whose inner error is [Error::WindowTooLarge].
+    ///
+    /// The default of 27 (128 MiB) is the limit of `zstd`, files written with `zstd --long=31`
+    /// need a limit of 31, and as much memory to be decoded.
+    pub fn zstd_window_log_max(mut self, window_log: u32) -> Self {
+        self.options.zstd_window_log_max = Some(window_log);
+        self
+    }
+
+    /// Maximum memory used by xz, lzma and lzip decoders, in bytes, unlimited by default.
+    /// Streams that need more fail with an [io::Error] whose inner error is
+    /// [Error::MemoryLimitExceeded].
+    pub fn xz_memlimit(mut self, memlimit: u64) -> Self {
+        self.options.xz_memlimit = memlimit;
+        self
+    }
+
+    /// Create a readable stream that decompress `in_stream`, also returns its compression format.
+    ///
+    /// Like [get_reader](crate::get_reader), zip archives with several files fail with
+    /// [Error::MultipleEntries] once their first file is read.
+    ///
+    /// Formats of codecs registered with [codec::register] are read by these codecs, without
+    /// the options of the decoders.
+    pub fn get_reader<'a>(
+        &self,
+        in_stream: Box<dyn io::Read + 'a>,
+    ) -> Result<(Box<dyn io::Read + 'a>, Format), Error> {
+        let (decoder, format) = self.boxed_decoder(in_stream)?;
+
+        Ok((Box::new(decoder), format))
+    }
+
+    /// Similar to [ReaderBuilder::get_reader] but the stream isn't boxed, the [Decoder] is
+    /// [Send] and [Sync] when `R` is.
+    ///
+    /// Like [generic::get_reader](crate::generic::get_reader), streams in the formats of codecs
+    /// registered with [codec::register] fail with [Error::UnsupportedFormat].
+    pub fn get_decoder<R: io::Read>(&self, in_stream: R) -> Result<(Decoder<R>, Format), Error> {
+        self.decoder(in_stream, Decoder::new)
+    }
+
+    /// Similar to [ReaderBuilder::get_decoder] but formats of registered codecs are read by them.
+    // only the boxed readers of niffler use registered codecs
+    #[allow(private_bounds)]
+    pub(crate) fn boxed_decoder<R: BoxedRead>(
+        &self,
+        in_stream: R,
+    ) -> Result<(Decoder<R>, Format), Error> {
+        self.decoder(in_stream, Decoder::boxed)
+    }
+
+    /// The decoder created by `new` for the format of `in_stream`, with the limits of the builder.
+    fn decoder<R: io::Read>(
+        &self,
+        in_stream: R,
+        new: impl FnOnce(Vec<u8>, R, Format, &DecoderOptions) -> Result<Decoder<R>, Error>,
+    ) -> Result<(Decoder<R>, Format), Error> {
+        let (first_bytes, in_stream, format) = match self.format {
+            // the stream is read by the decoder, from its start
+            Some(format) => (vec![], in_stream, format),
+            None => {
+                let (first_bytes, in_stream) = crate::utils::get_first_bytes_read(in_stream)?;
+                let format = codec::detect(&first_bytes);
+                (first_bytes, in_stream, format)
+            }
+        };
+
+        if !self.allows(format) {
+            return Err(Error::FormatNotAllowed(format));
+        }
+
+        let decoder = new(first_bytes, in_stream, format, &self.options)?;
+
+        Ok((decoder.limits(self.limits), format))
+    }
+
+    /// Similar to [ReaderBuilder::get_reader] but the decompressed stream is buffered.
+    pub fn get_bufread<'a>(
+        &self,
+        in_stream: Box<dyn io::Read + 'a>,
+    ) -> Result<(Box<dyn io::BufRead + 'a>, Format), Error> {
+        let (reader, format) = self.get_reader(in_stream)?;
+
+        Ok((
+            Box::new(io::BufReader::with_capacity(
+                self.output_buffer_size,
+                reader,
+            )),
+            format,
+        ))
+    }
+
+    /// Open a possibly compressed file and decompress it, see [from_path](crate::from_path).
+    pub fn from_path<'a, P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> Result<(Box<dyn io::Read + 'a>, Format), Error> {
+        let (readable, builder) = self.open_path(path.as_ref())?;
+
+        builder.get_reader(Box::new(readable))
+    }
+
+    /// Similar to [ReaderBuilder::from_path] but the decompressed stream is buffered.
+    ///
+    /// The file is read with a buffer of the output buffer size: uncompressed files are buffered
+    /// once, and returned without copy by [BufRead::fill_buf](io::BufRead::fill_buf).
+    pub fn from_path_bufread<'a, P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> Result<(Box<dyn io::BufRead + 'a>, Format), Error> {
+        match self.open_path_bufread(path.as_ref())? {
+            (readable, None) => Ok((Box::new(readable), Format::No)),
+            (readable, Some(builder)) => builder.get_bufread(Box::new(readable)),
+        }
+    }
+
+    /// The file read by [ReaderBuilder::from_path], with the builder that decompress it.
+    pub(crate) fn open_path(
+        &self,
+        path: &Path,
+    ) -> Result<(io::BufReader<std::fs::File>, ReaderBuilder), Error> {
+        let readable = io::BufReader::with_capacity(self.input_buffer_size, open(path)?);
+
+        Ok((readable, self.path_format(path)))
+    }
+
+    /// The file read by [ReaderBuilder::from_path_bufread], with the builder that decompress it.
+    /// There is no builder for uncompressed files returned as they are.
+    pub(crate) fn open_path_bufread(
+        &self,
+        path: &Path,
+    ) -> Result<
+        (
+            crate::Sniffed<io::BufReader<std::fs::File>>,
+            Option<ReaderBuilder>,
+        ),
+        Error,
+    > {
+        let builder = self.path_format(path);
+
+        // large enough to detect the format
+        let capacity = self.output_buffer_size.max(crate::utils::MAGIC_LEN);
+        let readable = io::BufReader::with_capacity(capacity, open(path)?);
+        let (readable, format) = match builder.format {
+            Some(format) => (crate::Sniffed::new(readable), format),
+            None => crate::sniff_bufread(readable)?,
+        };
+
+        if format == Format::No && self.allows(format) && self.limits.is_none() {
+            return Ok((readable, None));
+        }
+
+        Ok((readable, Some(builder.format(format))))
+    }
+
+    /// The format is in the allowed formats, if they are given, and isn't denied.
+    fn allows(&self, format: Format) -> bool {
+        self.formats
+            .as_ref()
+            .is_none_or(|formats| formats.contains(&format))
+            && !self.denied_formats.contains(&format)
+    }
+
+    /// Files in formats that can't be detected, like `.br` files, are read in the format given
+    /// by their extension, unless a format is given.
+    fn path_format(&self, path: &Path) -> ReaderBuilder {
+        let mut builder = self.clone();
+        if builder.format.is_none() {
+            builder.format = Format::from_path(path).filter(|format| format.is_undetectable());
+        }
+
+        builder
+    }
+}
+
+/// Open a file, zip archives are checked to contain a single file.
+fn open(path: &Path) -> Result<std::fs::File, Error> {
+    let file = crate::utils::open_file(path)?;
+
+    // the central directory of zip archives tells if they have several files before reading them
+    #[cfg(feature = "zip")]
+    crate::formats::zip::check_single_file(&file)?;
+
+    Ok(file)
+}
+
+/// Create writable streams with options.
+///
+/// # Example
+/// ```
+/// use niffler::{Error, Level, WriterBuilder, compression::Format};
+/// # fn main() -> Result<(), Error> {
+///
+/// # #[cfg(feature = "zstd")] {
+/// let mut buffer = vec![];
+/// {
+///   let mut writer = WriterBuilder::new(Format::Zstd)
+///       .level(Level::Three)
+///       .checksum(true)
+///       .zstd_window_log(27)
+///       .get_writer(Box::new(&mut buffer))?;
+///   writer.write_all(b"I'm compress in zstd\n")?;
+/// }
+///
+/// let (mut reader, format) = niffler::get_reader(Box::new(&buffer[..]))?;
+/// let mut contents = String::new();
+/// reader.read_to_string(&mut contents)?;
+///
+/// assert_eq!(format, Format::Zstd);
+/// assert_eq!(contents, "I'm compress in zstd\n");
+/// # }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct WriterBuilder {
+    format: Format,
+    buffer_size: usize,
+    entry_name: Option<String>,
+    options: EncoderOptions,
+}
+
+impl WriterBuilder {
+    /// A builder of streams compressed with `format`, at [Level::Six] by default.
+    pub fn new(format: Format) -> Self {
+        WriterBuilder {
+            format,
+            buffer_size: DEFAULT_BUFFER_SIZE,
+            entry_name: None,
+            options: EncoderOptions::default(),
+        }
+    }
+
+    /// Compression level.
+    pub fn level(mut self, level: Level) -> Self {
+        self.options.level = level;
+        self
+    }
+
+    /// Size of the buffer used to write files, 8 KiB by default.
+    pub fn buffer_size(mut self, size: usize) -> Self {
+        self.buffer_size = size;
+        self
+    }
+
+    /// Number of threads used to compress zstd streams, with the `zstd-mt` feature.
+    /// It's ignored for other formats.
+    pub fn threads(mut self, threads: u32) -> Self {
+        self.options.threads = threads;
+        self
+    }
+
+    /// Include a checksum of the data in xz, zstd and LZ4 streams. By default xz and LZ4
+    /// streams have one, zstd streams don't. Other formats always or never have checksums.
+    pub fn checksum(mut self, checksum: bool) -> Self {
+        self.options.checksum = Some(checksum);
+        self
+    }
+
+    /// Enable long distance matching of zstd, with a window of `2^window_log` bytes.
+    ///
+    /// Windows larger than 2^27 bytes (128 MiB) need a larger limit to be decompressed,
+    /// see [ReaderBuilder::zstd_window_log_max].
+    pub fn zstd_window_log(mut self, window_log: u32) -> Self {
+        self.options.zstd_window_log = Some(window_log);
+        self
+    }
+
+    /// Window of Brotli streams, `2^window - 16` bytes, between 10 and 24, 22 by default.
+    pub fn brotli_window(mut self, window: u32) -> Self {
+        self.options.brotli_window = window.clamp(10, 24);
+        self
+    }
+
+    /// Name of the entry of zip archives, `-` by default, or the name of the file without its
+    /// extension with [WriterBuilder::to_path].
+    pub fn zip_entry_name<S: Into<String>>(mut self, name: S) -> Self {
+        self.entry_name = Some(name.into());
+        self
+    }
+
+    /// Create a writable stream that compress data to `out_stream`.
+    ///
+    /// Formats of codecs registered with [codec::register] are written by these codecs, without
+    /// the options of the encoders.
+    pub fn get_writer<'a>(
+        &self,
+        out_stream: Box<dyn io::Write + 'a>,
+    ) -> Result<Box<dyn io::Write + 'a>, Error> {
+        Ok(Box::new(self.encoder(out_stream)?))
+    }
+
+    /// Create a file compressed with the format, see [to_path](crate::to_path).
+    pub fn to_path<'a, P: AsRef<Path>>(&self, path: P) -> Result<Box<dyn io::Write + 'a>, Error> {
+        let (writable, builder) = self.create(path.as_ref())?;
+
+        builder.get_writer(Box::new(writable))
+    }
+
+    /// The encoder of the format, formats of registered codecs are written by them.
+    pub(crate) fn encoder<W: BoxedWrite>(&self, out_stream: W) -> Result<Encoder<W>, Error> {
+        let mut options = self.options.clone();
+        if let Some(name) = &self.entry_name {
+            options.entry_name = name.clone();
+        }
+
+        Encoder::boxed(out_stream, self.format, &options)
+    }
+
+    /// Create the file of [WriterBuilder::to_path], the returned builder names the archive
+    /// entry like the file, without its extension.
+    pub(crate) fn create(
+        &self,
+        path: &Path,
+    ) -> Result<(io::BufWriter<std::fs::File>, WriterBuilder), Error> {
+        let writable =
+            io::BufWriter::with_capacity(self.buffer_size, crate::utils::create_file(path)?);
+
+        let mut builder = self.clone();
+        builder.entry_name.get_or_insert_with(|| {
+            path.file_stem()
+                .map_or("-".into(), |stem| stem.to_string_lossy().into_owned())
+        });
+
+        Ok((writable, builder))
+    }
+}
+
+/// Limits of the decompressed streams of reader builders.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Limits {
+    pub(crate) max_size: Option<u64>,
+    pub(crate) max_ratio: Option<u64>,
+}
+
+impl Limits {
+    pub(crate) fn is_none(&self) -> bool {
+        self.max_size.is_none() && self.max_ratio.is_none()
+    }
+
+    /// Fail if `decompressed` bytes are over the limits, `compressed` are the bytes read from
+    /// the compressed stream.
+    pub(crate) fn check(&self, decompressed: u64, compressed: &Consumed) -> io::Result<()> {
+        if let Some(limit) = self.max_size {
+            if decompressed > limit {
+                return Err(io::Error::other(Error::SizeLimitExceeded(limit)));
+            }
+        }
+
+        // decoders read compressed bytes before they decompress them, the ratio is never
+        // overestimated
+        if let Some(ratio) = self.max_ratio {
+            if decompressed > ratio.saturating_mul(compressed.bytes()) {
+                return Err(io::Error::other(Error::RatioLimitExceeded(ratio)));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Two members, `I'm compress in gzip\n` in each member.
+    #[cfg(feature = "gz")]
+    fn gzip_members() -> Vec<u8> {
+        let mut buffer = vec![];
+        for _ in 0..2 {
+            let mut writer =
+                crate::get_writer(Box::new(&mut buffer), Format::Gzip, Level::One).unwrap();
+            writer.write_all(b"I'm compress in gzip\n").unwrap();
+        }
+
+        buffer
+    }
+
+    fn read_all(mut reader: Box<dyn io::Read + '_>) -> io::Result<Vec<u8>> {
+        let mut contents = vec![];
+        reader.read_to_end(&mut contents)?;
+
+        Ok(contents)
+    }
+
+    #[cfg(feature = "gz")]
+    #[test]
+    fn formats() {
+        let buffer = gzip_members();
+
+        let builder = ReaderBuilder::new().formats([Format::No, Format::Zstd]);
+        assert!(matches!(
+            builder.get_reader(Box::new(&buffer[..])),
+            Err(Error::FormatNotAllowed(format)) if format == Format::Gzip
+        ));
+
+        let builder = builder.formats([Format::Gzip]);
+        assert!(builder.get_reader(Box::new(&buffer[..])).is_ok());
+        assert!(matches!(
+            builder.get_reader(Box::new(&b"uncompressed"[..])),
+            Err(Error::FormatNotAllowed(format)) if format == Format::No
+        ));
+    }
+
+    #[cfg(feature = "gz")]
+    #[test]
+    fn deny_formats() {
+        let buffer = gzip_members();
+
+        let builder = ReaderBuilder::new().deny_formats([Format::Gzip]);
+        assert!(matches!(
+            builder.get_reader(Box::new(&buffer[..])),
+            Err(Error::FormatNotAllowed(format)) if format == Format::Gzip
+        ));
+        assert!(builder.get_reader(Box::new(&b"uncompressed"[..])).is_ok());
+
+        let builder = builder.formats([Format::Gzip, Format::No]);
+        assert!(builder.get_reader(Box::new(&buffer[..])).is_err());
+        assert!(builder.get_reader(Box::new(&b"uncompressed"[..])).is_ok());
+    }
+
+    #[cfg(feature = "gz")]
+    #[test]
+    fn get_decoder() {
+        let buffer = gzip_members();
+
+        let builder = ReaderBuilder::new().deny_formats([Format::Gzip]);
+        assert!(matches!(
+            builder.get_decoder(&buffer[..]),
+            Err(Error::FormatNotAllowed(format)) if format == Format::Gzip
+        ));
+
+        // the decoder of a sendable stream is sendable
+        let (mut reader, format) = ReaderBuilder::new()
+            .max_size(30)
+            .get_decoder(io::Cursor::new(buffer))
+            .unwrap();
+        let error = std::thread::spawn(move || {
+            let mut contents = vec![];
+            io::Read::read_to_end(&mut reader, &mut contents).unwrap_err()
+        })
+        .join()
+        .unwrap();
+        assert_eq!(format, Format::Gzip);
+        assert!(matches!(
+            error.into_inner().unwrap().downcast_ref::<Error>(),
+            Some(Error::SizeLimitExceeded(30))
+        ));
+    }
+
+    #[cfg(feature = "gz")]
+    #[test]
+    fn max_size() {
+        let buffer = gzip_members();
+
+        let (reader, _) = ReaderBuilder::new()
+            .max_size(42)
+            .get_reader(Box::new(&buffer[..]))
+            .unwrap();
+        assert_eq!(read_all(reader).unwrap().len(), 42);
+
+        let (reader, _) = ReaderBuilder::new()
+            .max_size(41)
+            .get_reader(Box::new(&buffer[..]))
+            .unwrap();
+        let error = read_all(reader).unwrap_err();
+        assert!(matches!(
+            error.into_inner().unwrap().downcast_ref::<Error>(),
+            Some(Error::SizeLimitExceeded(41))
+        ));
+    }
+
+    #[cfg(feature = "gz")]
+    #[test]
+    fn max_size_read_again() {
+        let buffer = gzip_members();
+
+        let (mut reader, _) = ReaderBuilder::new()
+            .max_size(41)
+            .get_reader(Box::new(&buffer[..]))
+            .unwrap();
+        assert!(reader.read_to_end(&mut vec![]).is_err());
+
+        // retries fail with the same error
+        for _ in 0..2 {
+            let error = reader.read(&mut [0; 16]).unwrap_err();
+            assert!(matches!(
+                error.into_inner().unwrap().downcast_ref::<Error>(),
+                Some(Error::SizeLimitExceeded(41))
+            ));
+        }
+    }
+
+    #[cfg(feature = "gz")]
+    #[test]
+    fn max_ratio() {
+        let mut buffer = vec![];
+        {
+            let mut writer =
+                crate::get_writer(Box::new(&mut buffer), Format::Gzip, Level::Nine).unwrap();
+            writer.write_all(&[0; 1 << 20]).unwrap();
+        }
+
+        let (reader, _) = ReaderBuilder::new()
+            .max_ratio(100)
+            .get_reader(Box::new(&buffer[..]))
+            .unwrap();
+        let error = read_all(reader).unwrap_err();
+        assert!(matches!(
+            error.into_inner().unwrap().downcast_ref::<Error>(),
+            Some(Error::RatioLimitExceeded(100))
+        ));
+
+        let (reader, _) = ReaderBuilder::new()
+            .max_ratio(10_000)
+            .max_size(1 << 20)
+            .get_reader(Box::new(&buffer[..]))
+            .unwrap();
+        assert_eq!(read_all(reader).unwrap().len(), 1 << 20);
+
+        // uncompressed streams have a ratio of one
+        let (reader, _) = ReaderBuilder::new()
+            .max_ratio(1)
+            .get_reader(Box::new(&[b'A'; 1 << 20][..]))
+            .unwrap();
+        assert_eq!(read_all(reader).unwrap().len(), 1 << 20);
+    }
+
+    #[cfg(feature = "gz")]
+    #[test]
+    fn multi_member() {
+        let buffer = gzip_members();
+
+        let (reader, _) = ReaderBuilder::new()
+            .get_reader(Box::new(&buffer[..]))
+            .unwrap();
+        assert_eq!(
+            read_all(reader).unwrap(),
+            b"I'm compress in gzip\n".repeat(2)
+        );
+
+        let (reader, _) = ReaderBuilder::new()
+            .multi_member(false)
+            .get_reader(Box::new(&buffer[..]))
+            .unwrap();
+        assert_eq!(read_all(reader).unwrap(), b"I'm compress in gzip\n");
+    }
+
+    #[cfg(feature = "lzma")]
+    #[test]
+    fn xz_streams() {
+        let mut buffer = vec![];
+        for _ in 0..2 {
+            let mut writer =
+                crate::get_writer(Box::new(&mut buffer), Format::Lzma, Level::One).unwrap();
+            writer.write_all(b"I'm compress in xz\n").unwrap();
+        }
+
+        let (reader, _) = crate::get_reader(Box::new(&buffer[..])).unwrap();
+        assert_eq!(read_all(reader).unwrap(), b"I'm compress in xz\n".repeat(2));
+
+        let (reader, _) = ReaderBuilder::new()
+            .multi_member(false)
+            .get_reader(Box::new(&buffer[..]))
+            .unwrap();
+        assert_eq!(read_all(reader).unwrap(), b"I'm compress in xz\n");
+    }
+
+    #[cfg(feature = "lzo")]
+    #[test]
+    fn verify_checksums() {
+        let mut buffer = vec![];
+        {
+            let mut writer =
+                crate::get_writer(Box::new(&mut buffer), Format::Lzo, Level::One).unwrap();
+            writer.write_all(b"abcde").unwrap();
+        }
+
+        // the block is stored, its checksum is before its data and the end of stream marker
+        let checksum = buffer.len() - 4 - 5 - 4;
+        buffer[checksum] ^= 0xff;
+
+        let (reader, _) = ReaderBuilder::new()
+            .get_reader(Box::new(&buffer[..]))
+            .unwrap();
+        assert!(read_all(reader).is_err());
+
+        let (reader, _) = ReaderBuilder::new()
+            .verify_checksums(false)
+            .get_reader(Box::new(&buffer[..]))
+            .unwrap();
+        assert_eq!(read_all(reader).unwrap(), b"abcde");
+    }
+
+    #[cfg(feature = "zip")]
+    #[test]
+    fn verify_zip_checksums() {
+        let mut buffer = vec![];
+        {
+            let mut writer =
+                crate::get_writer(Box::new(&mut buffer), Format::Zip, Level::One).unwrap();
+            writer.write_all(b"abcde").unwrap();
+        }
+
+        // the CRC32 is the first field of the data descriptor, after the deflated data
+        let descriptor = buffer
+            .windows(4)
+            .position(|w| w == [0x50, 0x4b, 0x07, 0x08])
+            .unwrap();
+        buffer[descriptor + 4] ^= 0xff;
+
+        let (reader, _) = ReaderBuilder::new()
+            .get_reader(Box::new(&buffer[..]))
+            .unwrap();
+        assert!(read_all(reader).is_err());
+
+        let (reader, _) = ReaderBuilder::new()
+            .verify_checksums(false)
+            .get_reader(Box::new(&buffer[..]))
+            .unwrap();
+        assert_eq!(read_all(reader).unwrap(), b"abcde");
+    }
+
+    #[cfg(feature = "gz")]
+    #[test]
+    fn checksum_required() {
+        let mut buffer = vec![];
+        {
+            let mut writer =
+                crate::get_writer(Box::new(&mut buffer), Format::Gzip, Level::One).unwrap();
+            writer.write_all(b"abcde").unwrap();
+        }
+
+        let result = ReaderBuilder::new()
+            .verify_checksums(false)
+            .get_reader(Box::new(&buffer[..]));
+        assert!(matches!(
+            result,
+            Err(Error::ChecksumRequired {
+                format: Format::Gzip
+            })
+        ));
+    }
+
+    #[test]
+    fn checksum_mismatch() {
+        // formats with the position of a checksum from the end of a stream of 5 bytes
+        #[allow(unused_mut)]
+        let mut formats: Vec<(Format, usize)> = vec![];
+        #[cfg(feature = "gz")]
+        formats.push((Format::Gzip, 8));
+        #[cfg(feature = "zstd")]
+        formats.push((Format::Zstd, 4));
+        #[cfg(feature = "lz4")]
+        formats.push((Format::Lz4, 4));
+        // the only chunk has its checksum before its 5 bytes
+        #[cfg(feature = "snappy")]
+        formats.push((Format::Snappy, 9));
+
+        for (format, position) in formats {
+            let mut buffer = vec![];
+            {
+                let mut writer = WriterBuilder::new(format)
+                    .checksum(true)
+                    .get_writer(Box::new(&mut buffer))
+                    .unwrap();
+                writer.write_all(b"abcde").unwrap();
+            }
+            let checksum = buffer.len() - position;
+            buffer[checksum] ^= 0xff;
+
+            let (reader, _) = ReaderBuilder::new()
+                .format(format)
+                .get_reader(Box::new(&buffer[..]))
+                .unwrap();
+            let error = read_all(reader).expect_err("Corrupted stream is read");
+            assert!(
+                matches!(
+                    error.get_ref().and_then(|e| e.downcast_ref::<Error>()),
+                    Some(Error::ChecksumMismatch { format: name }) if *name == format
+                ),
+                "{format:?}: {error}"
+            );
+        }
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn zstd_options() {
+        let mut plain = vec![];
+        let mut options = vec![];
+        for (buffer, builder) in [
+            (&mut plain, WriterBuilder::new(Format::Zstd)),
+            (
+                &mut options,
+                WriterBuilder::new(Format::Zstd)
+                    .level(Level::Three)
+                    .checksum(true)
+                    .zstd_window_log(20)
+                    .threads(2),
+            ),
+        ] {
+            let mut writer = builder.get_writer(Box::new(buffer)).unwrap();
+            writer.write_all(b"I'm compress in zstd\n").unwrap();
+        }
+
+        // the frame header has a checksum flag
+        assert_ne!(plain, options);
+
+        let (reader, format) = crate::get_reader(Box::new(&options[..])).unwrap();
+        assert_eq!(format, Format::Zstd);
+        assert_eq!(read_all(reader).unwrap(), b"I'm compress in zstd\n");
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn zstd_window_log_max() {
+        let mut buffer = vec![];
+        {
+            let mut writer = WriterBuilder::new(Format::Zstd)
+                .zstd_window_log(28)
+                .get_writer(Box::new(&mut buffer))
+                .unwrap();
+            writer.write_all(b"I'm compress in zstd\n").unwrap();
+        }
+
+        let (reader, _) = crate::get_reader(Box::new(&buffer[..])).unwrap();
+        let error = read_all(reader).unwrap_err();
+        assert!(matches!(
+            error.into_inner().unwrap().downcast_ref::<Error>(),
+            Some(Error::WindowTooLarge(27))
+        ));
+
+        let (reader, _) = ReaderBuilder::new()
+            .zstd_window_log_max(28)
+            .get_reader(Box::new(&buffer[..]))
+            .unwrap();
+        assert_eq!(read_all(reader).unwrap(), b"I'm compress in zstd\n");
+    }
+
+    #[cfg(feature = "lzma")]
+    #[test]
+    fn xz_memlimit() {
+        let mut buffer = vec![];
+        {
+            let mut writer =
+                crate::get_writer(Box::new(&mut buffer), Format::Lzma, Level::One).unwrap();
+            writer.write_all(b"I'm compress in xz\n").unwrap();
+        }
+
+        let (reader, _) = ReaderBuilder::new()
+            .xz_memlimit(1 << 10)
+            .get_reader(Box::new(&buffer[..]))
+            .unwrap();
+        let error = read_all(reader).unwrap_err();
+        assert!(matches!(
+            error.into_inner().unwrap().downcast_ref::<Error>(),
+            Some(Error::MemoryLimitExceeded(1024))
+        ));
+
+        let (reader, _) = ReaderBuilder::new()
+            .xz_memlimit(1 << 30)
+            .get_reader(Box::new(&buffer[..]))
+            .unwrap();
+        assert_eq!(read_all(reader).unwrap(), b"I'm compress in xz\n");
+    }
+
+    #[cfg(feature = "brotli")]
+    #[test]
+    fn brotli_window() {
+        let mut buffer = vec![];
+        {
+            let mut writer = WriterBuilder::new(Format::Brotli)
+                .brotli_window(10)
+                .get_writer(Box::new(&mut buffer))
+                .unwrap();
+            writer.write_all(b"I'm compress in brotli\n").unwrap();
+        }
+
+        let (reader, _) = ReaderBuilder::new()
+            .format(Format::Brotli)
+            .get_reader(Box::new(&buffer[..]))
+            .unwrap();
+        assert_eq!(read_all(reader).unwrap(), b"I'm compress in brotli\n");
+    }
+
+    #[cfg(feature = "zip")]
+    #[test]
+    fn zip_entry_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("archive.zip");
+        {
+            let mut writer = WriterBuilder::new(Format::Zip)
+                .zip_entry_name("reads.fq")
+                .buffer_size(16)
+                .to_path(&path)
+                .unwrap();
+            writer.write_all(b"@read1\nACGT\n+\nIIII\n").unwrap();
+        }
+
+        let file = std::fs::File::open(&path).unwrap();
+        let (reader, _) = crate::get_entry_reader(Box::new(file), "reads.fq").unwrap();
+        assert_eq!(read_all(reader).unwrap(), b"@read1\nACGT\n+\nIIII\n");
+    }
+
+    #[test]
+    fn path_formats() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("reads.fq");
+        std::fs::write(&path, b"@read1\nACGT\n+\nIIII\n").unwrap();
+
+        let builder = ReaderBuilder::new().deny_formats([Format::No]);
+        assert!(matches!(
+            builder.from_path(&path),
+            Err(Error::FormatNotAllowed(format)) if format == Format::No
+        ));
+        assert!(matches!(
+            builder.from_path_bufread(&path),
+            Err(Error::FormatNotAllowed(format)) if format == Format::No
+        ));
+    }
+
+    #[test]
+    fn path() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("reads.fq");
+        std::fs::write(&path, b"@read1\nACGT\n+\nIIII\n").unwrap();
+
+        let builder = ReaderBuilder::new().input_buffer_size(4).max_size(8);
+        let (reader, format) = builder.from_path(&path).unwrap();
+        assert_eq!(format, Format::No);
+        assert!(read_all(reader).is_err());
+
+        let (reader, _) = builder.max_size(1024).from_path(&path).unwrap();
+        assert_eq!(read_all(reader).unwrap(), b"@read1\nACGT\n+\nIIII\n");
+    }
+}
@@ -0,0 +1,252 @@
+//! Find out what kind of data a decompressed stream contains, for the file formats
+//! commonly found in bioinformatics workflows.
+
+/// Number of bytes of the decompressed stream used to find out its content.
+pub const CONTENT_PEEK_LEN: usize = 8192;
+
+/// `Content` represent the kind of data of a decompressed stream.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum Content {
+    /// Sequences, each one after a `>` header line
+    Fasta,
+    /// Reads, as four lines records starting with `@`
+    Fastq,
+    /// Alignments as text, with `@HD`, `@SQ`, ... header lines or 11 tab separated fields
+    Sam,
+    /// Binary alignments, the BGZF payload starts with `BAM\1`
+    Bam,
+    /// Variants, with a `##fileformat=VCF` header
+    Vcf,
+    /// Genomic intervals, with at least 3 tab separated fields: chromosome, start and end
+    Bed,
+    /// Genomic features with 9 tab separated fields, in GFF or GTF format
+    Gff,
+    /// Other UTF-8 text
+    Text,
+    /// Anything else
+    Binary,
+}
+
+impl Content {
+    /// Find out the content of a decompressed stream from its first bytes,
+    /// [CONTENT_PEEK_LEN] bytes are enough.
+    ///
+    /// # Example
+    /// ```
+    /// use niffler::Content;
+    ///
+    /// assert_eq!(Content::detect(b">seq1\nACGT\n"), Content::Fasta);
+    /// assert_eq!(Content::detect(b"@read1\nACGT\n+\nIIII\n"), Content::Fastq);
+    /// assert_eq!(Content::detect(b"chr1\t10\t20\n"), Content::Bed);
+    /// assert_eq!(Content::detect(b"hello\n"), Content::Text);
+    /// ```
+    pub fn detect(bytes: &[u8]) -> Content {
+        if bytes.starts_with(b"BAM\x01") {
+            return Content::Bam;
+        }
+
+        let text = match text(bytes) {
+            Some(text) => text,
+            None => return Content::Binary,
+        };
+
+        let mut lines = text.lines().skip_while(|line| line.trim().is_empty());
+        let first = match lines.next() {
+            Some(first) => first,
+            None => return Content::Text,
+        };
+
+        if first.starts_with("##fileformat=VCF") || first.starts_with("#CHROM\tPOS\tID") {
+            return Content::Vcf;
+        }
+        if first.starts_with("##gff-version") {
+            return Content::Gff;
+        }
+        if ["@HD\t", "@SQ\t", "@RG\t", "@PG\t", "@CO\t"]
+            .iter()
+            .any(|tag| first.starts_with(tag))
+        {
+            return Content::Sam;
+        }
+        if first.starts_with('>') {
+            return Content::Fasta;
+        }
+        if first.starts_with('@') {
+            // the third line of a record is the `+` separator
+            return match lines.nth(1) {
+                Some(line) if !line.starts_with('+') => Content::Text,
+                _ => Content::Fastq,
+            };
+        }
+
+        // tabular formats, after comments and track lines
+        let record = text
+            .lines()
+            .find(|line| {
+                !(line.trim().is_empty()
+                    || line.starts_with('#')
+                    || line.starts_with("track")
+                    || line.starts_with("browser"))
+            })
+            .unwrap_or_default();
+
+        if is_sam(record) {
+            Content::Sam
+        } else if is_gff(record) {
+            Content::Gff
+        } else if is_bed(record) {
+            Content::Bed
+        } else {
+            Content::Text
+        }
+    }
+}
+
+/// Bytes as UTF-8 text, a character cut at the end is ignored. Control characters
+/// other than whitespaces make it binary.
+fn text(bytes: &[u8]) -> Option<&str> {
+    let text = match std::str::from_utf8(bytes) {
+        Ok(text) => text,
+        Err(e) if e.error_len().is_none() => {
+            std::str::from_utf8(&bytes[..e.valid_up_to()]).unwrap_or_default()
+        }
+        Err(_) => return None,
+    };
+
+    if text
+        .chars()
+        .any(|c| c.is_control() && !matches!(c, '\t' | '\n' | '\r' | '\x0c'))
+    {
+        return None;
+    }
+
+    Some(text)
+}
+
+fn is_integer(field: &str) -> bool {
+    !field.is_empty() && field.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Alignment lines have 11 mandatory fields, FLAG, POS, MAPQ, PNEXT are integers.
+fn is_sam(line: &str) -> bool {
+    let fields: Vec<_> = line.split('\t').collect();
+
+    fields.len() >= 11 && [1, 3, 4, 7].iter().all(|i| is_integer(fields[*i]))
+}
+
+/// Feature lines have 9 fields, start and end are integers and strand is `+`, `-`, `.` or `?`.
+fn is_gff(line: &str) -> bool {
+    let fields: Vec<_> = line.split('\t').collect();
+
+    fields.len() == 9
+        && is_integer(fields[3])
+        && is_integer(fields[4])
+        && matches!(fields[6], "+" | "-" | "." | "?")
+}
+
+/// Interval lines have at least 3 fields, start and end are integers.
+fn is_bed(line: &str) -> bool {
+    let fields: Vec<_> = line.split('\t').collect();
+
+    fields.len() >= 3 && is_integer(fields[1]) && is_integer(fields[2])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn fasta() {
+        assert_eq!(
+            Content::detect(b">seq1 description\nACGT\nTTGA\n>seq2\nAC"),
+            Content::Fasta
+        );
+    }
+
+    #[test]
+    fn fastq() {
+        assert_eq!(
+            Content::detect(b"@read1\nACGT\n+\nIIII\n@read2\nAC"),
+            Content::Fastq
+        );
+        assert_eq!(Content::detect(b"@read1\nACGT\n"), Content::Fastq);
+        assert_eq!(
+            Content::detect(b"@someone said\nhello\nto me\n"),
+            Content::Text
+        );
+    }
+
+    #[test]
+    fn sam() {
+        assert_eq!(
+            Content::detect(b"@HD\tVN:1.6\tSO:coordinate\n@SQ\tSN:chr1\tLN:100\n"),
+            Content::Sam
+        );
+        assert_eq!(
+            Content::detect(b"r001\t99\tchr1\t7\t30\t8M\t=\t37\t39\tTTAGATAA\t*\n"),
+            Content::Sam
+        );
+    }
+
+    #[test]
+    fn bam() {
+        assert_eq!(Content::detect(b"BAM\x01\x2a\x00\x00\x00@HD"), Content::Bam);
+    }
+
+    #[test]
+    fn vcf() {
+        assert_eq!(
+            Content::detect(b"##fileformat=VCFv4.3\n##contig=<ID=chr1>\n"),
+            Content::Vcf
+        );
+        assert_eq!(
+            Content::detect(b"#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\n"),
+            Content::Vcf
+        );
+    }
+
+    #[test]
+    fn bed() {
+        assert_eq!(
+            Content::detect(b"chr1\t100\t200\tname\t0\t+\n"),
+            Content::Bed
+        );
+        assert_eq!(
+            Content::detect(b"track name=peaks\n# comment\nchr2\t5\t10\n"),
+            Content::Bed
+        );
+    }
+
+    #[test]
+    fn gff() {
+        assert_eq!(
+            Content::detect(b"##gff-version 3\nchr1\t.\tgene\t1\t9\t.\t+\t.\tID=g\n"),
+            Content::Gff
+        );
+        assert_eq!(
+            Content::detect(b"chr1\tHAVANA\texon\t11869\t12227\t.\t+\t.\tgene_id \"ENSG\";\n"),
+            Content::Gff
+        );
+    }
+
+    #[test]
+    fn text() {
+        assert_eq!(
+            Content::detect(b"Lorem ipsum dolor sit amet\n"),
+            Content::Text
+        );
+        assert_eq!(Content::detect("caf\u{e9}".as_bytes()), Content::Text);
+        // an UTF-8 character cut by the end of the peeked bytes
+        assert_eq!(Content::detect(&"caf\u{e9}".as_bytes()[..4]), Content::Text);
+        assert_eq!(Content::detect(b""), Content::Text);
+    }
+
+    #[test]
+    fn binary() {
+        assert_eq!(
+            Content::detect(&[0x00, 0x01, 0x02, 0xff, 0xfe]),
+            Content::Binary
+        );
+        assert_eq!(Content::detect(b"text\x00with a null"), Content::Binary);
+    }
+}
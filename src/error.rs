@@ -1,17 +1,126 @@
+/* standard use */
+use std::path::PathBuf;
+
+/* crates use */
 use thiserror::Error;
 
+/* project use */
+use crate::basic::compression::Format;
+
 /// Errors that can be generated by niffler
+///
+/// Readers fail with [io::Error](std::io::Error)s, the errors of decoders have one of the
+/// [Truncated](Error::Truncated), [ChecksumMismatch](Error::ChecksumMismatch) or
+/// [CorruptData](Error::CorruptData) variants as inner error, with the same kind.
+///
+/// Formats are given as [Format]s, also for [send](crate::send) streams.
 #[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum Error {
-    /// This feature is disabled, you can enable it in cargo dependencies sections.
-    #[error("Feature disabled, enabled it during compilation")]
-    FeatureDisabled,
+    /// This compression format can't be read or written, it needs the cargo `feature` that is
+    /// disabled. Without `feature`, these functions don't support the format at all.
+    #[error(
+        "Compression format {format:?} isn't supported{}",
+        .feature.map(|feature| format!(", enable the `{feature}` feature")).unwrap_or_default()
+    )]
+    UnsupportedFormat {
+        format: Format,
+        feature: Option<&'static str>,
+    },
 
     /// This file is shorter than five bytes, it's probably not a compression file or corrupted.
     #[error("File is too short, less than five bytes")]
     FileTooShort,
 
+    /// This archive contains several files, the one to read must be chosen.
+    #[error("Archive contains several entries: {}", .0.join(", "))]
+    MultipleEntries(Vec<String>),
+
+    /// No entry of the archive has this name.
+    #[error("Entry {0} not found in archive")]
+    EntryNotFound(String),
+
+    /// Entries can only be selected in archives.
+    #[error("Stream isn't an archive, it has no entries")]
+    NotAnArchive,
+
+    /// No codec is registered for this format.
+    #[error("No codec registered for format {0:?}")]
+    UnknownFormat(Format),
+
+    /// The extension of the file doesn't match its compression format.
+    #[error("File extension is for {expected:?} but the compression format is {detected:?}")]
+    ExtensionMismatch { expected: Format, detected: Format },
+
+    /// The compression format of the stream isn't one of the formats allowed by a
+    /// [ReaderBuilder](crate::ReaderBuilder).
+    #[error("Compression format {0:?} isn't allowed")]
+    FormatNotAllowed(Format),
+
+    /// The decompressed stream is larger than the limit of a [ReaderBuilder](crate::ReaderBuilder),
+    /// it's returned as the inner error of an [io::Error](std::io::Error) by readers.
+    #[error("Decompressed stream is larger than the limit of {0} bytes")]
+    SizeLimitExceeded(u64),
+
+    /// The decompressed stream is larger than the compressed stream times the ratio limit of a
+    /// [ReaderBuilder](crate::ReaderBuilder), it's returned as the inner error of an
+    /// [io::Error](std::io::Error) by readers.
+    #[error("Decompressed stream is more than {0} times larger than the compressed stream")]
+    RatioLimitExceeded(u64),
+
+    /// The window of a zstd frame is larger than `2^window_log_max` bytes, the limit of decoders,
+    /// see [ReaderBuilder::zstd_window_log_max](crate::ReaderBuilder::zstd_window_log_max).
+    /// It's returned as the inner error of an [io::Error](std::io::Error) by readers.
+    #[error("zstd frame window is larger than the limit of 2^{0} bytes")]
+    WindowTooLarge(u32),
+
+    /// An xz, lzma or lzip stream needs more memory than the limit of decoders,
+    /// see [ReaderBuilder::xz_memlimit](crate::ReaderBuilder::xz_memlimit).
+    /// It's returned as the inner error of an [io::Error](std::io::Error) by readers.
+    #[error("Decoding needs more memory than the limit of {0} bytes")]
+    MemoryLimitExceeded(u64),
+
+    /// The compressed stream ends before the end of its data, its last member, frame or block
+    /// is incomplete. `decompressed` bytes were read from the stream before the error.
+    #[error("{format:?} stream is truncated, after {decompressed} decompressed bytes")]
+    Truncated { format: Format, decompressed: u64 },
+
+    /// The checksum of the decompressed data doesn't match the checksum of the stream.
+    #[error("{format:?} stream checksum mismatch")]
+    ChecksumMismatch { format: Format },
+
+    /// The decoder of this format always verifies checksums, they can't be ignored with
+    /// [ReaderBuilder::verify_checksums](crate::ReaderBuilder::verify_checksums).
+    #[error("{format:?} checksums are always verified, they can't be ignored")]
+    ChecksumRequired { format: Format },
+
+    /// The compressed stream isn't valid, the error was found after reading `compressed_offset`
+    /// bytes of the stream. Decoders read their input in blocks, the corrupted bytes are before
+    /// this offset but not always right before it.
+    #[error("{format:?} stream is corrupted, before offset {compressed_offset}")]
+    CorruptData {
+        format: Format,
+        compressed_offset: u64,
+    },
+
+    /// A file can't be opened or created.
+    #[error("{}: {source}", .path.display())]
+    PathIOError {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
     #[error("I/O error")]
     IOError(#[from] std::io::Error),
 }
+
+impl Error {
+    /// `format` can't be used, the cargo feature it needs is disabled.
+    pub(crate) fn feature_disabled(format: Format) -> Self {
+        Error::UnsupportedFormat {
+            format,
+            feature: format.feature(),
+        }
+    }
+}
@@ -0,0 +1,59 @@
+/* standard use */
+use std::io;
+use std::io::Read;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Decoder for brotli streams that tells truncated streams apart from corrupted ones.
+///
+/// The brotli decoder fails with [io::ErrorKind::InvalidData] in both cases, this one
+/// fails with [io::ErrorKind::UnexpectedEof] when the input ended before the error.
+pub(crate) struct BrotliDecoder<R: Read> {
+    inner: brotli::Decompressor<EndOfInput<R>>,
+    ended: Arc<AtomicBool>,
+}
+
+impl<R: Read> BrotliDecoder<R> {
+    pub(crate) fn new(inner: R) -> Self {
+        let ended = Arc::new(AtomicBool::new(false));
+        let input = EndOfInput {
+            inner,
+            ended: Arc::clone(&ended),
+        };
+
+        BrotliDecoder {
+            inner: brotli::Decompressor::new(input, 4096),
+            ended,
+        }
+    }
+}
+
+impl<R: Read> Read for BrotliDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf).map_err(|e| {
+            // the decoder only reads past the end of its input when it needs more of it
+            if e.kind() == io::ErrorKind::InvalidData && self.ended.load(Ordering::Relaxed) {
+                io::Error::new(io::ErrorKind::UnexpectedEof, "brotli stream is truncated")
+            } else {
+                e
+            }
+        })
+    }
+}
+
+/// Record when the input of the decoder ends.
+struct EndOfInput<R> {
+    inner: R,
+    ended: Arc<AtomicBool>,
+}
+
+impl<R: Read> Read for EndOfInput<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.inner.read(buf)?;
+        if len == 0 && !buf.is_empty() {
+            self.ended.store(true, Ordering::Relaxed);
+        }
+
+        Ok(len)
+    }
+}
@@ -0,0 +1,60 @@
+//! Checksum errors of the decoders of compression crates, told apart from other errors by
+//! their error codes or types.
+
+/* standard use */
+use std::io;
+
+/// Error of a gzip decoder of flate2 on a member whose CRC32 or size doesn't match its data.
+#[cfg(feature = "gz")]
+pub(crate) fn is_gzip_mismatch(error: &io::Error) -> bool {
+    // flate2 fails with this message only, the same for the checksum of the header
+    error.kind() == io::ErrorKind::InvalidInput
+        && error.to_string() == "corrupt gzip stream does not have a matching checksum"
+}
+
+/// Error of a zstd decoder on a frame whose checksum doesn't match its data.
+#[cfg(feature = "zstd")]
+pub(crate) fn is_zstd_mismatch(error: &io::Error) -> bool {
+    use zstd::zstd_safe::zstd_sys::ZSTD_ErrorCode;
+
+    // zstd errors only have the name of their error code
+    error.to_string() == zstd_error_name(ZSTD_ErrorCode::ZSTD_error_checksum_wrong)
+}
+
+/// Name of a zstd error code, the message of the errors of zstd decoders.
+#[cfg(feature = "zstd")]
+pub(crate) fn zstd_error_name(code: zstd::zstd_safe::zstd_sys::ZSTD_ErrorCode) -> &'static str {
+    // functions of zstd return error codes as negative numbers
+    zstd::zstd_safe::get_error_name(0usize.wrapping_sub(code as usize))
+}
+
+/// Error of a lz4 decoder on a frame whose header, block or content checksum doesn't match.
+#[cfg(feature = "lz4")]
+pub(crate) fn is_lz4_mismatch(error: &io::Error) -> bool {
+    // LZ4F_ERROR_headerChecksum_invalid, blockChecksum_invalid and contentChecksum_invalid
+    const CHECKSUM_ERRORS: [usize; 3] = [7, 17, 18];
+
+    if !error
+        .get_ref()
+        .is_some_and(|inner| inner.is::<lz4::liblz4::LZ4Error>())
+    {
+        return false;
+    }
+
+    // lz4 errors only have the name of their error code
+    let message = error.to_string();
+    CHECKSUM_ERRORS.into_iter().any(|code| {
+        lz4::liblz4::check_error(0usize.wrapping_sub(code)).is_err_and(|e| e.to_string() == message)
+    })
+}
+
+/// Error of a snappy decoder on a chunk whose checksum doesn't match its data.
+#[cfg(feature = "snappy")]
+pub(crate) fn is_snappy_mismatch(error: &io::Error) -> bool {
+    matches!(
+        error
+            .get_ref()
+            .and_then(|inner| inner.downcast_ref::<snap::Error>()),
+        Some(snap::Error::Checksum { .. })
+    )
+}
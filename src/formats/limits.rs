@@ -0,0 +1,42 @@
+//! Errors of decoders that need more memory than their limits.
+
+/* standard use */
+use std::io;
+
+/* project use */
+use crate::error::Error;
+
+/// Default maximum window log of zstd decoders, windows of 128 MiB like `zstd` without `--long`.
+#[cfg(feature = "zstd")]
+pub(crate) const ZSTD_WINDOW_LOG_MAX: u32 = 27;
+
+/// Replace the error of a zstd decoder reading a frame with a window larger than
+/// `2^window_log_max` bytes by [Error::WindowTooLarge].
+#[cfg(feature = "zstd")]
+pub(crate) fn zstd_error(error: io::Error, window_log_max: u32) -> io::Error {
+    use zstd::zstd_safe::zstd_sys::ZSTD_ErrorCode;
+
+    // zstd errors only have the name of their error code
+    let name =
+        super::checksum::zstd_error_name(ZSTD_ErrorCode::ZSTD_error_frameParameter_windowTooLarge);
+    if error.to_string() == name {
+        io::Error::other(Error::WindowTooLarge(window_log_max))
+    } else {
+        error
+    }
+}
+
+/// Replace the error of a liblzma decoder needing more than `memlimit` bytes by
+/// [Error::MemoryLimitExceeded].
+#[cfg(any(feature = "lzma", feature = "lzip"))]
+pub(crate) fn xz_error(error: io::Error, memlimit: u64) -> io::Error {
+    match error
+        .get_ref()
+        .and_then(|inner| inner.downcast_ref::<liblzma::stream::Error>())
+    {
+        Some(liblzma::stream::Error::MemLimit) => {
+            io::Error::other(Error::MemoryLimitExceeded(memlimit))
+        }
+        _ => error,
+    }
+}
@@ -0,0 +1,217 @@
+/* standard use */
+use std::io;
+use std::io::{Read, Write};
+
+/* Magic numbers, as little-endian u32 */
+const FRAME_MAGIC: u32 = 0x184D_2204;
+const LEGACY_MAGIC: u32 = 0x184C_2102;
+const SKIPPABLE_MAGIC: u32 = 0x184D_2A50;
+const SKIPPABLE_MASK: u32 = 0xFFFF_FFF0;
+
+/// Legacy frames are made of independent blocks of at most 8 MiB.
+const LEGACY_BLOCK_SIZE: usize = 8 << 20;
+const LEGACY_BLOCK_BOUND: usize = LEGACY_BLOCK_SIZE + LEGACY_BLOCK_SIZE / 255 + 16;
+
+enum State<R> {
+    Magic(R),
+    Frame(lz4::Decoder<io::Chain<io::Cursor<[u8; 4]>, R>>),
+    Legacy(R),
+    Done,
+}
+
+/// Decode a stream of concatenated LZ4 frames, in the current and the legacy frame format.
+///
+/// Skippable frames are ignored.
+pub(crate) struct Lz4Decoder<R> {
+    state: State<R>,
+    block: Vec<u8>,
+    pos: usize,
+    compressed: Vec<u8>,
+}
+
+impl<R: Read> Lz4Decoder<R> {
+    pub(crate) fn new(inner: R) -> Self {
+        Lz4Decoder {
+            state: State::Magic(inner),
+            block: Vec::new(),
+            pos: 0,
+            compressed: Vec::new(),
+        }
+    }
+
+    /// Prepare the decoding of the frame starting with `magic`, skippable frames are consumed.
+    fn start_frame(mut inner: R, magic: u32) -> io::Result<State<R>> {
+        match magic {
+            FRAME_MAGIC => Ok(State::Frame(lz4::Decoder::new(
+                io::Cursor::new(magic.to_le_bytes()).chain(inner),
+            )?)),
+            LEGACY_MAGIC => Ok(State::Legacy(inner)),
+            m if m & SKIPPABLE_MASK == SKIPPABLE_MAGIC => {
+                let size = read_u32(&mut inner)?.ok_or_else(truncated)? as u64;
+                if io::copy(&mut (&mut inner).take(size), &mut io::sink())? != size {
+                    return Err(truncated());
+                }
+                Ok(State::Magic(inner))
+            }
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Unknown lz4 frame magic number",
+            )),
+        }
+    }
+
+    /// Read and decompress the next block of a legacy frame into `self.block`.
+    fn next_legacy_block(&mut self, mut inner: R) -> io::Result<State<R>> {
+        let size = match read_u32(&mut inner)? {
+            None => return Ok(State::Done),
+            Some(size) => size,
+        };
+
+        // a legacy frame ends when another frame starts
+        if size == FRAME_MAGIC || size == LEGACY_MAGIC || size & SKIPPABLE_MASK == SKIPPABLE_MAGIC {
+            return Self::start_frame(inner, size);
+        }
+
+        let size = size as usize;
+        if size > LEGACY_BLOCK_BOUND {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "lz4 legacy block is too large",
+            ));
+        }
+
+        self.compressed.resize(size, 0);
+        inner.read_exact(&mut self.compressed).map_err(|e| {
+            if e.kind() == io::ErrorKind::UnexpectedEof {
+                truncated()
+            } else {
+                e
+            }
+        })?;
+
+        self.block.resize(LEGACY_BLOCK_SIZE, 0);
+        let len = lz4::block::decompress_to_buffer(
+            &self.compressed,
+            Some(LEGACY_BLOCK_SIZE as i32),
+            &mut self.block,
+        )?;
+        self.block.truncate(len);
+        self.pos = 0;
+
+        Ok(State::Legacy(inner))
+    }
+}
+
+impl<R: Read> Read for Lz4Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        loop {
+            if self.pos < self.block.len() {
+                let len = buf.len().min(self.block.len() - self.pos);
+                buf[..len].copy_from_slice(&self.block[self.pos..self.pos + len]);
+                self.pos += len;
+                return Ok(len);
+            }
+
+            if let State::Frame(decoder) = &mut self.state {
+                let len = decoder.read(buf)?;
+                if len > 0 {
+                    return Ok(len);
+                }
+            }
+
+            self.state = match std::mem::replace(&mut self.state, State::Done) {
+                State::Magic(mut inner) => match read_u32(&mut inner)? {
+                    None => State::Done,
+                    Some(magic) => Self::start_frame(inner, magic)?,
+                },
+                State::Frame(decoder) => {
+                    let (chain, result) = decoder.finish();
+                    // the decoder only reports an incomplete frame on finish
+                    result.map_err(|_| truncated())?;
+                    State::Magic(chain.into_inner().1)
+                }
+                State::Legacy(inner) => self.next_legacy_block(inner)?,
+                State::Done => return Ok(0),
+            };
+        }
+    }
+}
+
+/// LZ4 frame encoder, the frame is completed when the encoder is dropped.
+pub(crate) struct Lz4Encoder<W: Write> {
+    inner: Option<lz4::Encoder<W>>,
+}
+
+impl<W: Write> Lz4Encoder<W> {
+    /// The frame has a checksum of its content if `checksum` is true.
+    pub(crate) fn new(out: W, level: u32, checksum: bool) -> io::Result<Self> {
+        let checksum = if checksum {
+            lz4::ContentChecksum::ChecksumEnabled
+        } else {
+            lz4::ContentChecksum::NoChecksum
+        };
+
+        Ok(Lz4Encoder {
+            inner: Some(
+                lz4::EncoderBuilder::new()
+                    .level(level)
+                    .checksum(checksum)
+                    .build(out)?,
+            ),
+        })
+    }
+}
+
+// SAFETY: the compression context is only used through `&mut self`, like `lz4::Decoder`
+// that is `Sync` for the same reason.
+unsafe impl<W: Write + Sync> Sync for Lz4Encoder<W> {}
+
+impl<W: Write> Write for Lz4Encoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner
+            .as_mut()
+            .expect("lz4 encoder used after finish")
+            .write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner
+            .as_mut()
+            .expect("lz4 encoder used after finish")
+            .flush()
+    }
+}
+
+impl<W: Write> Drop for Lz4Encoder<W> {
+    fn drop(&mut self) {
+        if let Some(encoder) = self.inner.take() {
+            let (mut out, _) = encoder.finish();
+            let _ = out.flush();
+        }
+    }
+}
+
+/// Read a little-endian u32, `None` if the stream ends before the first byte.
+fn read_u32<R: Read>(inner: &mut R) -> io::Result<Option<u32>> {
+    let mut buf = [0u8; 4];
+    let mut len = 0;
+    while len < buf.len() {
+        match inner.read(&mut buf[len..]) {
+            Ok(0) if len == 0 => return Ok(None),
+            Ok(0) => return Err(truncated()),
+            Ok(n) => len += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(Some(u32::from_le_bytes(buf)))
+}
+
+fn truncated() -> io::Error {
+    io::Error::new(io::ErrorKind::UnexpectedEof, "lz4 stream is truncated")
+}
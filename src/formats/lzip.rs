@@ -0,0 +1,100 @@
+/* standard use */
+use std::io;
+use std::io::Write;
+
+/* crates use */
+use liblzma::stream::{Filters, LzmaOptions, Stream};
+
+/// Dictionary size used by each liblzma preset, all of them are powers of two.
+const PRESET_DICT_SIZES: [u32; 10] = [
+    1 << 18,
+    1 << 20,
+    1 << 21,
+    1 << 22,
+    1 << 22,
+    1 << 23,
+    1 << 23,
+    1 << 24,
+    1 << 25,
+    1 << 26,
+];
+
+const HEADER_SIZE: u64 = 6;
+const TRAILER_SIZE: u64 = 20;
+
+/// Write a single member lzip file, the member trailer is written when the encoder is dropped.
+///
+/// A member is an LZMA stream (with lc = 3, lp = 0 and pb = 2) using an end of stream
+/// marker, between a 6 bytes header and a trailer with the CRC32 of uncompressed data,
+/// the uncompressed size and the member size.
+pub(crate) struct LzipEncoder<W: Write> {
+    inner: Option<liblzma::write::XzEncoder<W>>,
+    crc: crc32fast::Hasher,
+    data_size: u64,
+}
+
+impl<W: Write> LzipEncoder<W> {
+    pub(crate) fn new(mut out: W, preset: u32) -> io::Result<Self> {
+        let dict_size = PRESET_DICT_SIZES[preset.min(9) as usize];
+
+        let mut options = LzmaOptions::new_preset(preset.min(9))?;
+        options
+            .dict_size(dict_size)
+            .literal_context_bits(3)
+            .literal_position_bits(0)
+            .position_bits(2);
+        let stream = Stream::new_raw_encoder(Filters::new().lzma1(&options))?;
+
+        // magic, version and dictionary size as a base-2 logarithm
+        out.write_all(&[b'L', b'Z', b'I', b'P', 1, dict_size.trailing_zeros() as u8])?;
+
+        Ok(LzipEncoder {
+            inner: Some(liblzma::write::XzEncoder::new_stream(out, stream)),
+            crc: crc32fast::Hasher::new(),
+            data_size: 0,
+        })
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        if let Some(mut encoder) = self.inner.take() {
+            encoder.try_finish()?;
+            let member_size = HEADER_SIZE + encoder.total_out() + TRAILER_SIZE;
+            let mut out = encoder.finish()?;
+
+            let crc = std::mem::take(&mut self.crc).finalize();
+            out.write_all(&crc.to_le_bytes())?;
+            out.write_all(&self.data_size.to_le_bytes())?;
+            out.write_all(&member_size.to_le_bytes())?;
+            out.flush()?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for LzipEncoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = self
+            .inner
+            .as_mut()
+            .expect("lzip encoder used after finish")
+            .write(buf)?;
+        self.crc.update(&buf[..len]);
+        self.data_size += len as u64;
+
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner
+            .as_mut()
+            .expect("lzip encoder used after finish")
+            .flush()
+    }
+}
+
+impl<W: Write> Drop for LzipEncoder<W> {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
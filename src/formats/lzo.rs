@@ -0,0 +1,655 @@
+/* standard use */
+use std::io;
+use std::io::{Read, Write};
+
+/* project use */
+use crate::basic::compression::Format;
+use crate::error::Error;
+
+pub(crate) const MAGIC: [u8; 9] = [0x89, 0x4c, 0x5a, 0x4f, 0x00, 0x0d, 0x0a, 0x1a, 0x0a];
+
+/// Versions written in the header, like lzop 1.03.
+const VERSION: u16 = 0x1030;
+const LIB_VERSION: u16 = 0x2080;
+const VERSION_NEEDED: u16 = 0x0940;
+
+/// Methods are variants of LZO1X compressors, they all use the same decompressor.
+const M_LZO1X_1: u8 = 1;
+const M_LZO1X_1_15: u8 = 2;
+const M_LZO1X_999: u8 = 3;
+
+const F_ADLER32_D: u32 = 0x0000_0001;
+const F_ADLER32_C: u32 = 0x0000_0002;
+const F_H_EXTRA_FIELD: u32 = 0x0000_0040;
+const F_CRC32_D: u32 = 0x0000_0100;
+const F_CRC32_C: u32 = 0x0000_0200;
+const F_MULTIPART: u32 = 0x0000_0400;
+const F_H_FILTER: u32 = 0x0000_0800;
+const F_H_CRC32: u32 = 0x0000_1000;
+const F_OS_UNIX: u32 = 0x0300_0000;
+
+/// lzop writes blocks of 256 KiB and refuses blocks larger than 64 MiB.
+const BLOCK_SIZE: usize = 256 * 1024;
+const MAX_BLOCK_SIZE: usize = 64 * 1024 * 1024;
+
+/// Largest match distances of the M2, M3 and M4 instructions.
+const M2_MAX_OFFSET: usize = 0x0800;
+const M3_MAX_OFFSET: usize = 0x4000;
+const M4_MAX_OFFSET: usize = 0xbfff;
+
+const HASH_BITS: u32 = 14;
+
+/// Adler-32 of `data`, continuing from `adler` (1 for a new checksum).
+fn adler32(adler: u32, data: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+    // largest number of bytes that can't overflow `b` before the modulo
+    const CHUNK: usize = 5552;
+
+    let (mut a, mut b) = (adler & 0xffff, adler >> 16);
+    for chunk in data.chunks(CHUNK) {
+        for byte in chunk {
+            a += u32::from(*byte);
+            b += a;
+        }
+        a %= MOD;
+        b %= MOD;
+    }
+
+    (b << 16) | a
+}
+
+/// Checksum of the header, Adler-32 unless the header says CRC32.
+fn header_checksum(flags: u32, data: &[u8]) -> u32 {
+    if flags & F_H_CRC32 != 0 {
+        crc32fast::hash(data)
+    } else {
+        adler32(1, data)
+    }
+}
+
+/// Decompress a LZO1X block into `dst`, which must be exactly `dst_len` bytes long.
+///
+/// Each instruction is a literal run or a match, the low 2 bits of a match give the
+/// number of literals (up to 3) copied after it. Longer literal runs are instructions
+/// below 16, read as a short match when they follow a match with literals.
+fn decompress(src: &[u8], dst: &mut Vec<u8>, dst_len: usize) -> io::Result<()> {
+    enum State {
+        /// After a match without literals, or at the start of the block
+        Instruction,
+        /// After a run of at least 4 literals
+        LongLiterals,
+        /// After 1 to 3 literals that follow a match
+        ShortLiterals,
+    }
+
+    let mut ip = 0;
+    let next = |ip: &mut usize| -> io::Result<usize> {
+        let byte = *src.get(*ip).ok_or_else(corrupted)?;
+        *ip += 1;
+        Ok(usize::from(byte))
+    };
+    // lengths too long for their instruction continue with zero bytes worth 255 each
+    let length = |ip: &mut usize, base: usize| -> io::Result<usize> {
+        let mut len = base;
+        loop {
+            match next(ip)? {
+                0 => len += 255,
+                byte => return Ok(len + byte),
+            }
+            if len > dst_len {
+                return Err(corrupted());
+            }
+        }
+    };
+    let literals = |ip: &mut usize, dst: &mut Vec<u8>, len: usize| -> io::Result<()> {
+        let run = src.get(*ip..*ip + len).ok_or_else(corrupted)?;
+        if dst.len() + len > dst_len {
+            return Err(corrupted());
+        }
+        dst.extend_from_slice(run);
+        *ip += len;
+        Ok(())
+    };
+    let copy_match = |dst: &mut Vec<u8>, distance: usize, len: usize| -> io::Result<()> {
+        if distance > dst.len() || dst.len() + len > dst_len {
+            return Err(corrupted());
+        }
+        let start = dst.len() - distance;
+        if distance >= len {
+            dst.extend_from_within(start..start + len);
+        } else {
+            for i in start..start + len {
+                dst.push(dst[i]);
+            }
+        }
+        Ok(())
+    };
+
+    dst.clear();
+    dst.reserve(dst_len);
+
+    // a block can start with a literal run written as 17 + length
+    let mut state = State::Instruction;
+    if let Some(first) = src.first().filter(|first| **first > 17) {
+        ip = 1;
+        let len = usize::from(*first) - 17;
+        literals(&mut ip, dst, len)?;
+        state = if len < 4 {
+            State::ShortLiterals
+        } else {
+            State::LongLiterals
+        };
+    }
+
+    loop {
+        let t = next(&mut ip)?;
+
+        let trailing = if t >= 64 {
+            // M2: length 3 to 8, distance up to 2 KiB
+            let distance = 1 + ((t >> 2) & 7) + (next(&mut ip)? << 3);
+            copy_match(dst, distance, (t >> 5) + 1)?;
+            t & 3
+        } else if t >= 32 {
+            // M3: distance up to 16 KiB
+            let len = match t & 31 {
+                0 => length(&mut ip, 31)?,
+                len => len,
+            } + 2;
+            let (low, high) = (next(&mut ip)?, next(&mut ip)?);
+            copy_match(dst, 1 + (low >> 2) + (high << 6), len)?;
+            low & 3
+        } else if t >= 16 {
+            // M4: distance up to 48 KiB, a null distance ends the block
+            let len = match t & 7 {
+                0 => length(&mut ip, 7)?,
+                len => len,
+            } + 2;
+            let (low, high) = (next(&mut ip)?, next(&mut ip)?);
+            let distance = ((t & 8) << 11) + (low >> 2) + (high << 6);
+            if distance == 0 {
+                break;
+            }
+            copy_match(dst, distance + 0x4000, len)?;
+            low & 3
+        } else {
+            match state {
+                State::Instruction => {
+                    let len = match t {
+                        0 => length(&mut ip, 15)?,
+                        len => len,
+                    } + 3;
+                    literals(&mut ip, dst, len)?;
+                    state = State::LongLiterals;
+                    continue;
+                }
+                State::LongLiterals => {
+                    let distance = 1 + M2_MAX_OFFSET + (t >> 2) + (next(&mut ip)? << 2);
+                    copy_match(dst, distance, 3)?;
+                    t & 3
+                }
+                State::ShortLiterals => {
+                    let distance = 1 + (t >> 2) + (next(&mut ip)? << 2);
+                    copy_match(dst, distance, 2)?;
+                    t & 3
+                }
+            }
+        };
+
+        if trailing == 0 {
+            state = State::Instruction;
+        } else {
+            literals(&mut ip, dst, trailing)?;
+            state = State::ShortLiterals;
+        }
+    }
+
+    if ip != src.len() || dst.len() != dst_len {
+        return Err(corrupted());
+    }
+
+    Ok(())
+}
+
+/// Compress a block with LZO1X, using the longest match at the last position with the same hash.
+///
+/// Matches are at least 4 bytes long and only use M2, M3 and M4 instructions, the block
+/// always starts with a literal as the first position has no match.
+fn compress(src: &[u8], dst: &mut Vec<u8>) {
+    fn hash(bytes: &[u8]) -> usize {
+        let value = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        (value.wrapping_mul(0x9e37_79b1) >> (32 - HASH_BITS)) as usize
+    }
+
+    fn push_length(dst: &mut Vec<u8>, mut len: usize) {
+        while len > 255 {
+            dst.push(0);
+            len -= 255;
+        }
+        dst.push(len as u8);
+    }
+
+    fn push_literals(dst: &mut Vec<u8>, literals: &[u8]) {
+        let len = literals.len();
+        if len == 0 {
+            return;
+        } else if dst.is_empty() && len <= 238 {
+            dst.push(17 + len as u8);
+        } else if len <= 3 {
+            // in the low 2 bits of the previous match
+            let last = dst.len() - 2;
+            dst[last] |= len as u8;
+        } else if len <= 18 {
+            dst.push(len as u8 - 3);
+        } else {
+            dst.push(0);
+            push_length(dst, len - 18);
+        }
+        dst.extend_from_slice(literals);
+    }
+
+    fn push_match(dst: &mut Vec<u8>, distance: usize, len: usize) {
+        if len <= 8 && distance <= M2_MAX_OFFSET {
+            let distance = distance - 1;
+            dst.push((((len - 1) << 5) | ((distance & 7) << 2)) as u8);
+            dst.push((distance >> 3) as u8);
+            return;
+        }
+
+        let distance = if distance <= M3_MAX_OFFSET {
+            if len <= 33 {
+                dst.push(32 | (len - 2) as u8);
+            } else {
+                dst.push(32);
+                push_length(dst, len - 33);
+            }
+            distance - 1
+        } else {
+            let distance = distance - 0x4000;
+            let high = ((distance >> 11) & 8) as u8;
+            if len <= 9 {
+                dst.push(16 | high | (len - 2) as u8);
+            } else {
+                dst.push(16 | high);
+                push_length(dst, len - 9);
+            }
+            distance & 0x3fff
+        };
+        dst.push((distance << 2) as u8);
+        dst.push((distance >> 6) as u8);
+    }
+
+    dst.clear();
+    let mut table = vec![0u32; 1 << HASH_BITS];
+    let mut literal_start = 0;
+    let mut pos = 0;
+
+    while pos + 4 <= src.len() {
+        let slot = &mut table[hash(&src[pos..])];
+        let candidate = *slot as usize;
+        *slot = pos as u32 + 1;
+
+        if candidate > 0 {
+            let candidate = candidate - 1;
+            let distance = pos - candidate;
+            if distance <= M4_MAX_OFFSET && src[candidate..candidate + 4] == src[pos..pos + 4] {
+                let len = 4 + src[pos + 4..]
+                    .iter()
+                    .zip(&src[candidate + 4..])
+                    .take_while(|(a, b)| a == b)
+                    .count();
+
+                push_literals(dst, &src[literal_start..pos]);
+                push_match(dst, distance, len);
+                pos += len;
+                literal_start = pos;
+                continue;
+            }
+        }
+
+        pos += 1;
+    }
+
+    push_literals(dst, &src[literal_start..]);
+    // M4 with a null distance
+    dst.extend_from_slice(&[16 | 1, 0, 0]);
+}
+
+/// Decoder for lzop files, a header followed by blocks compressed with LZO1X.
+///
+/// Blocks are stored as is when compression doesn't make them smaller. Each block has
+/// optional Adler-32 or CRC32 checksums of its compressed and uncompressed data, given by
+/// the header flags, they're all verified.
+pub(crate) struct LzopDecoder<R> {
+    inner: R,
+    flags: u32,
+    compressed: Vec<u8>,
+    out: Vec<u8>,
+    out_pos: usize,
+    done: bool,
+    verify: bool,
+}
+
+impl<R: Read> LzopDecoder<R> {
+    pub(crate) fn new(mut inner: R) -> io::Result<Self> {
+        let mut magic = [0u8; 9];
+        read_exact(&mut inner, &mut magic)?;
+        if magic != MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Not an lzop stream",
+            ));
+        }
+
+        // the header checksum covers all fields after the magic
+        let mut header = HeaderReader {
+            inner: &mut inner,
+            data: Vec::new(),
+        };
+        let version = header.u16()?;
+        header.u16()?; // library version
+        if version >= 0x0940 {
+            header.u16()?; // version needed to extract
+        }
+        let method = header.u8()?;
+        if version >= 0x0940 {
+            header.u8()?; // level
+        }
+        let flags = header.u32()?;
+        if flags & F_H_FILTER != 0 {
+            return Err(unsupported("lzop filters aren't supported"));
+        }
+        if flags & F_MULTIPART != 0 {
+            return Err(unsupported("multipart lzop files aren't supported"));
+        }
+        if !matches!(method, M_LZO1X_1 | M_LZO1X_1_15 | M_LZO1X_999) {
+            return Err(unsupported(&format!(
+                "lzop compression method {method} isn't supported"
+            )));
+        }
+        header.u32()?; // mode
+        header.u32()?; // modification time
+        if version >= 0x0940 {
+            header.u32()?; // modification time, high bits
+        }
+        let name_len = header.u8()?;
+        header.bytes(usize::from(name_len))?;
+
+        let checksum = header_checksum(flags, &header.data);
+        if read_u32(&mut inner)? != checksum {
+            return Err(checksum_mismatch());
+        }
+
+        if flags & F_H_EXTRA_FIELD != 0 {
+            let mut extra = HeaderReader {
+                inner: &mut inner,
+                data: Vec::new(),
+            };
+            let len = extra.u32()?;
+            extra.bytes(len as usize)?;
+
+            let checksum = header_checksum(flags, &extra.data);
+            if read_u32(&mut inner)? != checksum {
+                return Err(checksum_mismatch());
+            }
+        }
+
+        Ok(LzopDecoder {
+            inner,
+            flags,
+            compressed: Vec::new(),
+            out: Vec::new(),
+            out_pos: 0,
+            done: false,
+            verify: true,
+        })
+    }
+
+    /// Choose if checksums of blocks are verified, header checksums are always verified.
+    pub(crate) fn verify_checksums(mut self, verify: bool) -> Self {
+        self.verify = verify;
+        self
+    }
+
+    /// Decode the next block into `self.out`, return false at the end of the stream.
+    fn read_block(&mut self) -> io::Result<bool> {
+        let dst_len = read_u32(&mut self.inner)? as usize;
+        if dst_len == 0 {
+            return Ok(false);
+        }
+        if dst_len > MAX_BLOCK_SIZE {
+            return Err(corrupted());
+        }
+
+        let src_len = read_u32(&mut self.inner)? as usize;
+        if src_len == 0 || src_len > dst_len {
+            return Err(corrupted());
+        }
+
+        // flags and values of the checksums of uncompressed and compressed data
+        let mut checksums = vec![];
+        let mut compressed_checksums = vec![];
+        for flag in [F_ADLER32_D, F_CRC32_D] {
+            if self.flags & flag != 0 {
+                checksums.push((flag, read_u32(&mut self.inner)?));
+            }
+        }
+        // stored blocks only have checksums of their uncompressed data
+        let stored = src_len == dst_len;
+        for flag in [F_ADLER32_C, F_CRC32_C] {
+            if self.flags & flag != 0 && !stored {
+                compressed_checksums.push((flag, read_u32(&mut self.inner)?));
+            }
+        }
+
+        self.compressed.resize(src_len, 0);
+        read_exact(&mut self.inner, &mut self.compressed)?;
+
+        for (flag, expected) in compressed_checksums.into_iter().filter(|_| self.verify) {
+            if block_checksum(flag, &self.compressed) != expected {
+                return Err(checksum_mismatch());
+            }
+        }
+
+        if stored {
+            std::mem::swap(&mut self.out, &mut self.compressed);
+        } else {
+            decompress(&self.compressed, &mut self.out, dst_len)?;
+        }
+
+        for (flag, expected) in checksums.into_iter().filter(|_| self.verify) {
+            if block_checksum(flag, &self.out) != expected {
+                return Err(checksum_mismatch());
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+impl<R: Read> Read for LzopDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.out_pos == self.out.len() && !self.done {
+            self.out_pos = 0;
+            self.out.clear();
+            self.done = !self.read_block()?;
+        }
+
+        let len = buf.len().min(self.out.len() - self.out_pos);
+        buf[..len].copy_from_slice(&self.out[self.out_pos..self.out_pos + len]);
+        self.out_pos += len;
+
+        Ok(len)
+    }
+}
+
+/// Encoder for lzop files, data is compressed in blocks of 256 KiB with Adler-32
+/// checksums of compressed and uncompressed data.
+///
+/// The last block and the end of stream marker are written when the encoder is dropped.
+pub(crate) struct LzopEncoder<W: Write> {
+    inner: W,
+    block: Vec<u8>,
+    compressed: Vec<u8>,
+    finished: bool,
+}
+
+impl<W: Write> LzopEncoder<W> {
+    pub(crate) fn new(mut inner: W) -> io::Result<Self> {
+        let flags = F_ADLER32_D | F_ADLER32_C | F_OS_UNIX;
+
+        let mut header = Vec::with_capacity(25);
+        header.extend_from_slice(&VERSION.to_be_bytes());
+        header.extend_from_slice(&LIB_VERSION.to_be_bytes());
+        header.extend_from_slice(&VERSION_NEEDED.to_be_bytes());
+        header.extend_from_slice(&[M_LZO1X_1, 3]);
+        header.extend_from_slice(&flags.to_be_bytes());
+        header.extend_from_slice(&0o100644u32.to_be_bytes()); // mode
+        header.extend_from_slice(&[0; 8]); // modification time
+        header.push(0); // no file name
+
+        inner.write_all(&MAGIC)?;
+        inner.write_all(&header)?;
+        inner.write_all(&header_checksum(flags, &header).to_be_bytes())?;
+
+        Ok(LzopEncoder {
+            inner,
+            block: Vec::with_capacity(BLOCK_SIZE),
+            compressed: Vec::new(),
+            finished: false,
+        })
+    }
+
+    fn write_block(&mut self) -> io::Result<()> {
+        if self.block.is_empty() {
+            return Ok(());
+        }
+
+        compress(&self.block, &mut self.compressed);
+        let stored = self.compressed.len() >= self.block.len();
+        let data = if stored {
+            &self.block
+        } else {
+            &self.compressed
+        };
+
+        self.inner
+            .write_all(&(self.block.len() as u32).to_be_bytes())?;
+        self.inner.write_all(&(data.len() as u32).to_be_bytes())?;
+        self.inner
+            .write_all(&adler32(1, &self.block).to_be_bytes())?;
+        if !stored {
+            self.inner
+                .write_all(&adler32(1, &self.compressed).to_be_bytes())?;
+        }
+        self.inner.write_all(data)?;
+
+        self.block.clear();
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        if self.finished {
+            return Ok(());
+        }
+        self.finished = true;
+
+        self.write_block()?;
+        self.inner.write_all(&0u32.to_be_bytes())?;
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Write for LzopEncoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = buf.len().min(BLOCK_SIZE - self.block.len());
+        self.block.extend_from_slice(&buf[..len]);
+
+        if self.block.len() == BLOCK_SIZE {
+            self.write_block()?;
+        }
+
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Drop for LzopEncoder<W> {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
+
+/// Read header fields, big-endian, keeping their bytes for the checksum.
+struct HeaderReader<'a, R> {
+    inner: &'a mut R,
+    data: Vec<u8>,
+}
+
+impl<R: Read> HeaderReader<'_, R> {
+    fn bytes(&mut self, len: usize) -> io::Result<&[u8]> {
+        let start = self.data.len();
+        self.data.resize(start + len, 0);
+        read_exact(self.inner, &mut self.data[start..])?;
+
+        Ok(&self.data[start..])
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> io::Result<u16> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+}
+
+fn block_checksum(flag: u32, data: &[u8]) -> u32 {
+    if flag & (F_CRC32_D | F_CRC32_C) != 0 {
+        crc32fast::hash(data)
+    } else {
+        adler32(1, data)
+    }
+}
+
+fn read_u32<R: Read>(inner: &mut R) -> io::Result<u32> {
+    let mut bytes = [0u8; 4];
+    read_exact(inner, &mut bytes)?;
+
+    Ok(u32::from_be_bytes(bytes))
+}
+
+fn read_exact<R: Read>(inner: &mut R, buf: &mut [u8]) -> io::Result<()> {
+    inner.read_exact(buf).map_err(|e| {
+        if e.kind() == io::ErrorKind::UnexpectedEof {
+            io::Error::new(io::ErrorKind::UnexpectedEof, "lzop stream is truncated")
+        } else {
+            e
+        }
+    })
+}
+
+fn unsupported(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::Unsupported, message)
+}
+
+fn checksum_mismatch() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        Error::ChecksumMismatch {
+            format: Format::Lzo,
+        },
+    )
+}
+
+fn corrupted() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "lzop stream is corrupted")
+}
@@ -0,0 +1,406 @@
+/* standard use */
+use std::collections::HashMap;
+use std::io;
+use std::io::{Read, Write};
+
+const MAGIC: [u8; 2] = [0x1f, 0x9d];
+const BLOCK_MODE: u8 = 0x80;
+const BITS_MASK: u8 = 0x1f;
+
+const INIT_BITS: u32 = 9;
+const MAX_BITS: u32 = 16;
+const CLEAR: usize = 256;
+
+/// Largest code that can be read with `n_bits`, the last size can use all its codes.
+fn max_code(n_bits: u32, max_bits: u32) -> usize {
+    if n_bits == max_bits {
+        1 << max_bits
+    } else {
+        (1 << n_bits) - 1
+    }
+}
+
+/// Codes are written in groups of 8, so a group of `n_bits` codes is `n_bits` bytes long.
+/// When the code size changes the stream skips to the end of the current group.
+fn align(pos: u64, start: u64, n_bits: u32) -> u64 {
+    let group = u64::from(n_bits) * 8;
+    start + (pos - start).div_ceil(group) * group
+}
+
+/// Decoder for the LZW format used by Unix `compress` (`.Z` files).
+///
+/// The 3 bytes header gives the maximum code size and if the `CLEAR` code can be used
+/// to reset the dictionary (block mode).
+pub(crate) struct LzwDecoder<R> {
+    inner: R,
+    block_mode: bool,
+    max_bits: u32,
+
+    input: Vec<u8>,
+    input_start: u64,
+    eof: bool,
+    bit_pos: u64,
+    group_start: u64,
+
+    n_bits: u32,
+    max_code: usize,
+    free_ent: usize,
+    old_code: Option<usize>,
+    fin_char: u8,
+    prefix: Vec<u16>,
+    suffix: Vec<u8>,
+
+    stack: Vec<u8>,
+    out: Vec<u8>,
+    out_pos: usize,
+    done: bool,
+}
+
+impl<R: Read> LzwDecoder<R> {
+    pub(crate) fn new(mut inner: R) -> io::Result<Self> {
+        let mut header = [0u8; 3];
+        inner.read_exact(&mut header)?;
+
+        if header[..2] != MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Not a compress (.Z) stream",
+            ));
+        }
+
+        let max_bits = u32::from(header[2] & BITS_MASK);
+        if !(INIT_BITS..=MAX_BITS).contains(&max_bits) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unsupported compress maximum code size: {max_bits} bits"),
+            ));
+        }
+        let block_mode = header[2] & BLOCK_MODE != 0;
+
+        Ok(LzwDecoder {
+            inner,
+            block_mode,
+            max_bits,
+            input: Vec::with_capacity(1 << 16),
+            input_start: 0,
+            eof: false,
+            bit_pos: 0,
+            group_start: 0,
+            n_bits: INIT_BITS,
+            max_code: max_code(INIT_BITS, max_bits),
+            free_ent: if block_mode { CLEAR + 1 } else { CLEAR },
+            old_code: None,
+            fin_char: 0,
+            prefix: vec![0; 1 << max_bits],
+            suffix: (0..1usize << max_bits).map(|c| c as u8).collect(),
+            stack: Vec::new(),
+            out: Vec::new(),
+            out_pos: 0,
+            done: false,
+        })
+    }
+
+    /// Read the next code, `None` if the stream ends before a whole code is available.
+    ///
+    /// The last code is padded to a whole byte, a stream that ends with a byte or more of a
+    /// code is truncated. Streams cut between two codes can't be told apart from whole ones.
+    fn next_code(&mut self) -> io::Result<Option<usize>> {
+        let end = self.bit_pos + u64::from(self.n_bits);
+
+        while self.input_start + self.input.len() as u64 * 8 < end {
+            if self.eof {
+                let left =
+                    (self.input_start + self.input.len() as u64 * 8).saturating_sub(self.bit_pos);
+                if left >= 8 {
+                    return Err(truncated());
+                }
+                return Ok(None);
+            }
+
+            // drop bytes already used
+            let used = ((self.bit_pos - self.input_start) / 8) as usize;
+            let used = used.min(self.input.len());
+            self.input.drain(..used);
+            self.input_start += used as u64 * 8;
+
+            let len = self.input.len();
+            self.input.resize(len + (1 << 15), 0);
+            let read = loop {
+                match self.inner.read(&mut self.input[len..]) {
+                    Ok(n) => break n,
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                    Err(e) => return Err(e),
+                }
+            };
+            self.input.truncate(len + read);
+            self.eof = read == 0;
+        }
+
+        let offset = ((self.bit_pos - self.input_start) / 8) as usize;
+        let shift = (self.bit_pos % 8) as u32;
+        let mut word = 0u32;
+        for (i, byte) in self.input[offset..].iter().take(3).enumerate() {
+            word |= u32::from(*byte) << (8 * i);
+        }
+
+        self.bit_pos = end;
+        Ok(Some(((word >> shift) & ((1 << self.n_bits) - 1)) as usize))
+    }
+
+    fn skip_to_group_end(&mut self) {
+        self.bit_pos = align(self.bit_pos, self.group_start, self.n_bits);
+        self.group_start = self.bit_pos;
+    }
+
+    /// Decode the next code into `self.out`, return false at the end of the stream.
+    fn decode_next(&mut self) -> io::Result<bool> {
+        loop {
+            if self.free_ent > self.max_code {
+                self.skip_to_group_end();
+                self.n_bits += 1;
+                self.max_code = max_code(self.n_bits, self.max_bits);
+            }
+
+            let code = match self.next_code()? {
+                Some(code) => code,
+                None => return Ok(false),
+            };
+
+            let old_code = match self.old_code {
+                Some(old_code) => old_code,
+                None => {
+                    if code >= CLEAR {
+                        return Err(corrupted());
+                    }
+                    self.fin_char = code as u8;
+                    self.old_code = Some(code);
+                    self.out.push(self.fin_char);
+                    return Ok(true);
+                }
+            };
+
+            if code == CLEAR && self.block_mode {
+                self.free_ent = CLEAR;
+                self.skip_to_group_end();
+                self.n_bits = INIT_BITS;
+                self.max_code = max_code(INIT_BITS, self.max_bits);
+                continue;
+            }
+
+            let in_code = code;
+            let mut code = code;
+            self.stack.clear();
+
+            // code for the string we are currently building (KwKwK case)
+            if code >= self.free_ent {
+                if code > self.free_ent {
+                    return Err(corrupted());
+                }
+                self.stack.push(self.fin_char);
+                code = old_code;
+            }
+
+            while code > 255 {
+                if self.stack.len() > 1 << MAX_BITS {
+                    return Err(corrupted());
+                }
+                self.stack.push(self.suffix[code]);
+                code = usize::from(self.prefix[code]);
+            }
+            self.fin_char = code as u8;
+            self.stack.push(self.fin_char);
+            self.out.extend(self.stack.iter().rev());
+
+            if self.free_ent < 1 << self.max_bits {
+                self.prefix[self.free_ent] = old_code as u16;
+                self.suffix[self.free_ent] = self.fin_char;
+                self.free_ent += 1;
+            }
+
+            self.old_code = Some(in_code);
+            return Ok(true);
+        }
+    }
+}
+
+impl<R: Read> Read for LzwDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.out_pos == self.out.len() {
+            self.out.clear();
+            self.out_pos = 0;
+
+            while !self.done && self.out.len() < buf.len() {
+                self.done = !self.decode_next()?;
+            }
+        }
+
+        let len = buf.len().min(self.out.len() - self.out_pos);
+        buf[..len].copy_from_slice(&self.out[self.out_pos..self.out_pos + len]);
+        self.out_pos += len;
+
+        Ok(len)
+    }
+}
+
+/// Encoder for the LZW format used by Unix `compress`, in block mode with codes up to 16 bits.
+///
+/// The dictionary is cleared each time it is full. The last code is written when
+/// the encoder is dropped.
+pub(crate) struct LzwEncoder<W: Write> {
+    inner: W,
+    table: HashMap<(u16, u8), u16>,
+    ent: Option<u16>,
+    free_ent: usize,
+    n_bits: u32,
+
+    bits: u64,
+    bit_count: u32,
+    bit_pos: u64,
+    group_start: u64,
+    out: Vec<u8>,
+    finished: bool,
+}
+
+impl<W: Write> LzwEncoder<W> {
+    pub(crate) fn new(mut inner: W) -> io::Result<Self> {
+        inner.write_all(&[MAGIC[0], MAGIC[1], BLOCK_MODE | MAX_BITS as u8])?;
+
+        Ok(LzwEncoder {
+            inner,
+            table: HashMap::new(),
+            ent: None,
+            free_ent: CLEAR + 1,
+            n_bits: INIT_BITS,
+            bits: 0,
+            bit_count: 0,
+            bit_pos: 0,
+            group_start: 0,
+            out: Vec::with_capacity(1 << 15),
+            finished: false,
+        })
+    }
+
+    fn output(&mut self, code: usize) {
+        self.bits |= (code as u64) << self.bit_count;
+        self.bit_count += self.n_bits;
+        self.bit_pos += u64::from(self.n_bits);
+
+        while self.bit_count >= 8 {
+            self.out.push(self.bits as u8);
+            self.bits >>= 8;
+            self.bit_count -= 8;
+        }
+    }
+
+    /// Pad with zeros until the end of the current group of codes.
+    fn skip_to_group_end(&mut self) {
+        let end = align(self.bit_pos, self.group_start, self.n_bits);
+        if self.bit_count > 0 {
+            self.out.push(self.bits as u8);
+            self.bits = 0;
+            self.bit_count = 0;
+        }
+        let written = self.bit_pos.div_ceil(8);
+        self.out
+            .resize(self.out.len() + (end / 8 - written) as usize, 0);
+
+        self.bit_pos = end;
+        self.group_start = end;
+    }
+
+    fn encode(&mut self, byte: u8) {
+        let ent = match self.ent {
+            Some(ent) => ent,
+            None => {
+                self.ent = Some(u16::from(byte));
+                return;
+            }
+        };
+
+        if let Some(code) = self.table.get(&(ent, byte)) {
+            self.ent = Some(*code);
+            return;
+        }
+
+        self.output(usize::from(ent));
+        self.ent = Some(u16::from(byte));
+
+        if self.free_ent < 1 << MAX_BITS {
+            self.table.insert((ent, byte), self.free_ent as u16);
+            self.free_ent += 1;
+
+            if self.free_ent > 1 << self.n_bits && self.n_bits < MAX_BITS {
+                self.skip_to_group_end();
+                self.n_bits += 1;
+            }
+        } else {
+            self.output(CLEAR);
+            self.skip_to_group_end();
+            self.table.clear();
+            self.free_ent = CLEAR + 1;
+            self.n_bits = INIT_BITS;
+        }
+    }
+
+    fn write_out(&mut self) -> io::Result<()> {
+        self.inner.write_all(&self.out)?;
+        self.out.clear();
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        if self.finished {
+            return Ok(());
+        }
+        self.finished = true;
+
+        if let Some(ent) = self.ent {
+            self.output(usize::from(ent));
+        }
+        if self.bit_count > 0 {
+            self.out.push(self.bits as u8);
+            self.bit_count = 0;
+        }
+        self.write_out()?;
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Write for LzwEncoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for byte in buf {
+            self.encode(*byte);
+        }
+
+        if self.out.len() >= 1 << 15 {
+            self.write_out()?;
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.write_out()?;
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Drop for LzwEncoder<W> {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
+
+fn corrupted() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        "compress (.Z) stream is corrupted",
+    )
+}
+
+fn truncated() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::UnexpectedEof,
+        "compress (.Z) stream is truncated",
+    )
+}
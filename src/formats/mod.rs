@@ -0,0 +1,19 @@
+//! Decoders and encoders for formats that need more than a thin wrapper
+//! around a compression crate.
+
+#[cfg(feature = "brotli")]
+pub(crate) mod brotli;
+#[cfg(any(feature = "gz", feature = "zstd", feature = "lz4", feature = "snappy"))]
+pub(crate) mod checksum;
+#[cfg(any(feature = "zstd", feature = "lzma", feature = "lzip"))]
+pub(crate) mod limits;
+#[cfg(feature = "lz4")]
+pub(crate) mod lz4;
+#[cfg(feature = "lzip")]
+pub(crate) mod lzip;
+#[cfg(feature = "lzo")]
+pub(crate) mod lzo;
+#[cfg(feature = "compress")]
+pub(crate) mod lzw;
+#[cfg(feature = "zip")]
+pub(crate) mod zip;
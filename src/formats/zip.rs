@@ -0,0 +1,656 @@
+/* standard use */
+use std::io;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Take, Write};
+
+/* crates use */
+use flate2::bufread::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+
+/* project use */
+use crate::basic::compression::Format;
+use crate::error::Error;
+
+/* Signatures, as little-endian u32 */
+const LOCAL_HEADER: u32 = 0x0403_4b50;
+const CENTRAL_HEADER: u32 = 0x0201_4b50;
+const DATA_DESCRIPTOR: u32 = 0x0807_4b50;
+const END_OF_CENTRAL_DIRECTORY: u32 = 0x0605_4b50;
+const ZIP64_END_OF_CENTRAL_DIRECTORY: u32 = 0x0606_4b50;
+const ZIP64_LOCATOR: u32 = 0x0706_4b50;
+
+const ZIP64_EXTRA: u16 = 0x0001;
+
+const FLAG_ENCRYPTED: u16 = 1;
+const FLAG_DATA_DESCRIPTOR: u16 = 1 << 3;
+const FLAG_UTF8: u16 = 1 << 11;
+
+const STORED: u16 = 0;
+const DEFLATED: u16 = 8;
+
+/// Version needed to read zip64 extensions
+const VERSION: u16 = 45;
+/// 1980-01-01, the first date MS-DOS can represent
+const DOS_DATE: u16 = (1 << 5) | 1;
+
+const LOCAL_HEADER_SIZE: u64 = 30;
+const LOCAL_EXTRA_SIZE: u64 = 20;
+const DATA_DESCRIPTOR_SIZE: u64 = 24;
+const END_OF_CENTRAL_DIRECTORY_SIZE: u64 = 22;
+
+/// Header written before the data of each entry.
+struct LocalHeader {
+    name: String,
+    flags: u16,
+    method: u16,
+    crc: u32,
+    compressed_size: u64,
+    size: u64,
+    zip64: bool,
+}
+
+impl LocalHeader {
+    /// Read a header, its signature was already consumed.
+    fn read<R: Read>(inner: &mut R) -> io::Result<Self> {
+        let mut fixed = [0u8; LOCAL_HEADER_SIZE as usize - 4];
+        inner.read_exact(&mut fixed)?;
+
+        let mut name = vec![0; usize::from(u16_at(&fixed, 22))];
+        let mut extra = vec![0; usize::from(u16_at(&fixed, 24))];
+        inner.read_exact(&mut name)?;
+        inner.read_exact(&mut extra)?;
+
+        let mut header = LocalHeader {
+            name: String::from_utf8_lossy(&name).into_owned(),
+            flags: u16_at(&fixed, 2),
+            method: u16_at(&fixed, 4),
+            crc: u32_at(&fixed, 10),
+            compressed_size: u64::from(u32_at(&fixed, 14)),
+            size: u64::from(u32_at(&fixed, 18)),
+            zip64: false,
+        };
+
+        // sizes too large for the header are in the zip64 extra field
+        let mut fields = &extra[..];
+        while let [i0, i1, l0, l1, rest @ ..] = fields {
+            let len = usize::from(u16::from_le_bytes([*l0, *l1])).min(rest.len());
+            let (data, next) = rest.split_at(len);
+
+            if u16::from_le_bytes([*i0, *i1]) == ZIP64_EXTRA {
+                header.zip64 = true;
+
+                let mut values = data.chunks_exact(8).map(|v| u64_at(v, 0));
+                if header.size == u64::from(u32::MAX) {
+                    header.size = values.next().unwrap_or(header.size);
+                }
+                if header.compressed_size == u64::from(u32::MAX) {
+                    header.compressed_size = values.next().unwrap_or(header.compressed_size);
+                }
+            }
+
+            fields = next;
+        }
+
+        Ok(header)
+    }
+
+    fn has_data_descriptor(&self) -> bool {
+        self.flags & FLAG_DATA_DESCRIPTOR != 0
+    }
+
+    fn is_directory(&self) -> bool {
+        self.name.ends_with('/')
+    }
+}
+
+enum State<R> {
+    Stored(Take<BufReader<R>>),
+    Deflated(DeflateDecoder<BufReader<R>>),
+    Done,
+}
+
+/// Decode one entry of a zip archive read as a stream, stored and deflated entries are supported.
+///
+/// When no entry is requested the archive must contain a single file, directories are ignored.
+/// As the central directory is at the end of the archive, other entries are only found after
+/// the first one is read: the last read then fails with [Error::MultipleEntries].
+pub(crate) struct ZipDecoder<R> {
+    state: State<R>,
+    header: LocalHeader,
+    single: bool,
+    crc: crc32fast::Hasher,
+    size: u64,
+    verify: bool,
+}
+
+impl<R: Read> ZipDecoder<R> {
+    /// Prepare the decoding of the entry called `name`, or of the only file of the archive.
+    pub(crate) fn new(inner: R, name: Option<&str>) -> Result<Self, Error> {
+        let mut inner = BufReader::new(inner);
+
+        let header = loop {
+            if read_signature(&mut inner)? != Some(LOCAL_HEADER) {
+                return Err(match name {
+                    Some(name) => Error::EntryNotFound(name.to_string()),
+                    None => io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "zip archive doesn't contain any file",
+                    )
+                    .into(),
+                });
+            }
+
+            let header = LocalHeader::read(&mut inner)?;
+            let selected = match name {
+                Some(name) => header.name == name,
+                None => !header.is_directory(),
+            };
+            if selected {
+                break header;
+            }
+
+            skip_entry(&mut inner, &header)?;
+        };
+
+        if header.flags & FLAG_ENCRYPTED != 0 {
+            return Err(unsupported("encrypted zip entries are not supported").into());
+        }
+
+        let state = match header.method {
+            STORED if header.has_data_descriptor() && header.compressed_size == 0 => {
+                return Err(unsupported(
+                    "stored zip entries with their size after the data can't be read as a stream",
+                )
+                .into())
+            }
+            STORED => State::Stored(inner.take(header.compressed_size)),
+            DEFLATED => State::Deflated(DeflateDecoder::new(inner)),
+            method => {
+                return Err(unsupported(&format!(
+                    "zip compression method {method} is not supported"
+                ))
+                .into())
+            }
+        };
+
+        Ok(ZipDecoder {
+            state,
+            header,
+            single: name.is_none(),
+            crc: crc32fast::Hasher::new(),
+            size: 0,
+            verify: true,
+        })
+    }
+
+    /// Choose if the CRC32 of the entry is verified, its size is always checked.
+    pub(crate) fn verify_checksums(mut self, verify: bool) -> Self {
+        self.verify = verify;
+        self
+    }
+
+    /// Check the entry checksum and size, and that no other file follows if a single one is expected.
+    fn finish_entry(&mut self, mut inner: BufReader<R>) -> io::Result<()> {
+        let (crc, size) = if self.header.has_data_descriptor() {
+            read_data_descriptor(&mut inner, self.header.zip64)?
+        } else {
+            (self.header.crc, self.header.size)
+        };
+
+        // without zip64 extensions only the 32 lower bits of the size are kept
+        let decoded = if self.header.zip64 {
+            self.size
+        } else {
+            self.size & u64::from(u32::MAX)
+        };
+        if decoded < size {
+            return Err(truncated());
+        } else if decoded > size {
+            return Err(corrupted());
+        }
+
+        if self.verify && crc != std::mem::take(&mut self.crc).finalize() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                Error::ChecksumMismatch {
+                    format: Format::Zip,
+                },
+            ));
+        }
+
+        if self.single {
+            let mut files = vec![];
+            let mut signature = read_signature(&mut inner)?;
+            while signature == Some(LOCAL_HEADER) {
+                let header = LocalHeader::read(&mut inner)?;
+                if !header.is_directory() {
+                    files.push(header.name.clone());
+                }
+                if skip_entry(&mut inner, &header).is_err() {
+                    break;
+                }
+                signature = read_signature(&mut inner)?;
+            }
+
+            if !files.is_empty() {
+                files.insert(0, self.header.name.clone());
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    Error::MultipleEntries(files),
+                ));
+            }
+
+            if signature != Some(LOCAL_HEADER) {
+                check_end(&mut inner, signature)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for ZipDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = match &mut self.state {
+            State::Stored(inner) => inner.read(buf)?,
+            State::Deflated(inner) => inner.read(buf)?,
+            State::Done => return Ok(0),
+        };
+
+        if len > 0 || buf.is_empty() {
+            self.crc.update(&buf[..len]);
+            self.size += len as u64;
+            return Ok(len);
+        }
+
+        let inner = match std::mem::replace(&mut self.state, State::Done) {
+            State::Stored(inner) if inner.limit() > 0 => return Err(truncated()),
+            State::Stored(inner) => inner.into_inner(),
+            State::Deflated(inner) => inner.into_inner(),
+            State::Done => unreachable!(),
+        };
+        self.finish_entry(inner)?;
+
+        Ok(0)
+    }
+}
+
+/// List the entries of a zip archive from its central directory.
+///
+/// Return `None` if the central directory can't be found, e.g. when data was
+/// prepended to the archive.
+pub(crate) fn entries<R: Read + Seek>(inner: &mut R) -> io::Result<Option<Vec<String>>> {
+    // the end of central directory record is followed by a comment of at most 64 KiB
+    let len = inner.seek(SeekFrom::End(0))?;
+    let tail_len = len.min(END_OF_CENTRAL_DIRECTORY_SIZE + u64::from(u16::MAX));
+    inner.seek(SeekFrom::Start(len - tail_len))?;
+    let mut tail = vec![0; tail_len as usize];
+    inner.read_exact(&mut tail)?;
+
+    if tail.len() < END_OF_CENTRAL_DIRECTORY_SIZE as usize {
+        return Ok(None);
+    }
+    let last = tail.len() - END_OF_CENTRAL_DIRECTORY_SIZE as usize;
+    let end = match (0..=last)
+        .rev()
+        .find(|i| u32_at(&tail, *i) == END_OF_CENTRAL_DIRECTORY)
+    {
+        Some(end) => end,
+        None => return Ok(None),
+    };
+
+    let mut count = u64::from(u16_at(&tail, end + 10));
+    let mut offset = u64::from(u32_at(&tail, end + 16));
+
+    if count == u64::from(u16::MAX) || offset == u64::from(u32::MAX) {
+        if end < 20 || u32_at(&tail, end - 20) != ZIP64_LOCATOR {
+            return Ok(None);
+        }
+
+        let mut record = [0u8; 56];
+        inner.seek(SeekFrom::Start(u64_at(&tail, end - 12)))?;
+        inner.read_exact(&mut record)?;
+        if u32_at(&record, 0) != ZIP64_END_OF_CENTRAL_DIRECTORY {
+            return Ok(None);
+        }
+
+        count = u64_at(&record, 32);
+        offset = u64_at(&record, 48);
+    }
+
+    inner.seek(SeekFrom::Start(offset))?;
+    let mut names = vec![];
+    for _ in 0..count {
+        let mut fixed = [0u8; 46];
+        inner.read_exact(&mut fixed)?;
+        if u32_at(&fixed, 0) != CENTRAL_HEADER {
+            return Ok(None);
+        }
+
+        let mut name = vec![0; usize::from(u16_at(&fixed, 28))];
+        inner.read_exact(&mut name)?;
+        inner.seek(SeekFrom::Current(
+            i64::from(u16_at(&fixed, 30)) + i64::from(u16_at(&fixed, 32)),
+        ))?;
+
+        names.push(String::from_utf8_lossy(&name).into_owned());
+    }
+
+    Ok(Some(names))
+}
+
+/// Fail if a regular file is a zip archive with more than one file, then rewind it.
+///
+/// This reports archives [ZipDecoder] can't read before any data is decoded. Pipes and other
+/// files that can't seek aren't checked, their data would be consumed.
+pub(crate) fn check_single_file(file: &std::fs::File) -> Result<(), Error> {
+    if !file.metadata()?.file_type().is_file() {
+        return Ok(());
+    }
+
+    let mut inner = file;
+    let mut magic = [0u8; 4];
+    let is_zip = inner.read_exact(&mut magic).is_ok() && u32::from_le_bytes(magic) == LOCAL_HEADER;
+    let entries = if is_zip {
+        entries(&mut inner).ok().flatten()
+    } else {
+        None
+    };
+    inner.seek(SeekFrom::Start(0))?;
+
+    let files: Vec<String> = entries
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|name| !name.ends_with('/'))
+        .collect();
+    if files.len() > 1 {
+        return Err(Error::MultipleEntries(files));
+    }
+
+    Ok(())
+}
+
+/// Write a zip archive with a single deflated entry.
+///
+/// Like `zip` reading from standard input, sizes are unknown when the local header is
+/// written, so they follow the data in a zip64 data descriptor. The central directory
+/// is written when the encoder is dropped.
+pub(crate) struct ZipEncoder<W: Write> {
+    inner: Option<DeflateEncoder<W>>,
+    name: Vec<u8>,
+    crc: crc32fast::Hasher,
+    size: u64,
+}
+
+impl<W: Write> ZipEncoder<W> {
+    pub(crate) fn new(mut out: W, name: &str, level: flate2::Compression) -> io::Result<Self> {
+        let name = name.as_bytes().to_vec();
+        let name_len = u16::try_from(name.len()).map_err(|_| {
+            io::Error::new(io::ErrorKind::InvalidInput, "zip entry name is too long")
+        })?;
+
+        let mut header =
+            Vec::with_capacity((LOCAL_HEADER_SIZE + LOCAL_EXTRA_SIZE) as usize + name.len());
+        header.extend(LOCAL_HEADER.to_le_bytes());
+        header.extend(VERSION.to_le_bytes());
+        header.extend((FLAG_DATA_DESCRIPTOR | FLAG_UTF8).to_le_bytes());
+        header.extend(DEFLATED.to_le_bytes());
+        header.extend(0u16.to_le_bytes());
+        header.extend(DOS_DATE.to_le_bytes());
+        header.extend(0u32.to_le_bytes());
+        header.extend(u32::MAX.to_le_bytes());
+        header.extend(u32::MAX.to_le_bytes());
+        header.extend(name_len.to_le_bytes());
+        header.extend((LOCAL_EXTRA_SIZE as u16).to_le_bytes());
+        header.extend(&name);
+        header.extend(ZIP64_EXTRA.to_le_bytes());
+        header.extend(16u16.to_le_bytes());
+        header.extend([0; 16]);
+        out.write_all(&header)?;
+
+        Ok(ZipEncoder {
+            inner: Some(DeflateEncoder::new(out, level)),
+            name,
+            crc: crc32fast::Hasher::new(),
+            size: 0,
+        })
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        if let Some(mut encoder) = self.inner.take() {
+            encoder.try_finish()?;
+            let compressed_size = encoder.total_out();
+            let mut out = encoder.finish()?;
+            let crc = std::mem::take(&mut self.crc).finalize();
+
+            let mut descriptor = Vec::with_capacity(DATA_DESCRIPTOR_SIZE as usize);
+            descriptor.extend(DATA_DESCRIPTOR.to_le_bytes());
+            descriptor.extend(crc.to_le_bytes());
+            descriptor.extend(compressed_size.to_le_bytes());
+            descriptor.extend(self.size.to_le_bytes());
+            out.write_all(&descriptor)?;
+
+            // sizes that don't fit in 32 bits go in the zip64 extra field
+            let mut extra = vec![];
+            let mut fit = |value: u64| match u32::try_from(value) {
+                Ok(value) if value != u32::MAX => value,
+                _ => {
+                    extra.extend(value.to_le_bytes());
+                    u32::MAX
+                }
+            };
+            let size = fit(self.size);
+            let compressed = fit(compressed_size);
+            if !extra.is_empty() {
+                let len = extra.len() as u16;
+                extra.splice(
+                    0..0,
+                    ZIP64_EXTRA
+                        .to_le_bytes()
+                        .into_iter()
+                        .chain(len.to_le_bytes()),
+                );
+            }
+
+            let mut central = vec![];
+            central.extend(CENTRAL_HEADER.to_le_bytes());
+            central.extend(VERSION.to_le_bytes());
+            central.extend(VERSION.to_le_bytes());
+            central.extend((FLAG_DATA_DESCRIPTOR | FLAG_UTF8).to_le_bytes());
+            central.extend(DEFLATED.to_le_bytes());
+            central.extend(0u16.to_le_bytes());
+            central.extend(DOS_DATE.to_le_bytes());
+            central.extend(crc.to_le_bytes());
+            central.extend(compressed.to_le_bytes());
+            central.extend(size.to_le_bytes());
+            central.extend((self.name.len() as u16).to_le_bytes());
+            central.extend((extra.len() as u16).to_le_bytes());
+            central.extend([0; 14]); // comment length, disk, attributes and local header offset
+            central.extend(&self.name);
+            central.extend(&extra);
+
+            let offset = LOCAL_HEADER_SIZE
+                + LOCAL_EXTRA_SIZE
+                + self.name.len() as u64
+                + compressed_size
+                + DATA_DESCRIPTOR_SIZE;
+            let central_size = central.len() as u64;
+
+            if offset >= u64::from(u32::MAX) {
+                central.extend(ZIP64_END_OF_CENTRAL_DIRECTORY.to_le_bytes());
+                central.extend(44u64.to_le_bytes());
+                central.extend(VERSION.to_le_bytes());
+                central.extend(VERSION.to_le_bytes());
+                central.extend([0; 8]); // disk numbers
+                central.extend(1u64.to_le_bytes());
+                central.extend(1u64.to_le_bytes());
+                central.extend(central_size.to_le_bytes());
+                central.extend(offset.to_le_bytes());
+
+                central.extend(ZIP64_LOCATOR.to_le_bytes());
+                central.extend(0u32.to_le_bytes());
+                central.extend((offset + central_size).to_le_bytes());
+                central.extend(1u32.to_le_bytes());
+            }
+
+            central.extend(END_OF_CENTRAL_DIRECTORY.to_le_bytes());
+            central.extend([0; 4]); // disk numbers
+            central.extend(1u16.to_le_bytes());
+            central.extend(1u16.to_le_bytes());
+            central.extend((central_size as u32).to_le_bytes());
+            central.extend(u32::try_from(offset).unwrap_or(u32::MAX).to_le_bytes());
+            central.extend(0u16.to_le_bytes());
+
+            out.write_all(&central)?;
+            out.flush()?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for ZipEncoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = self
+            .inner
+            .as_mut()
+            .expect("zip encoder used after finish")
+            .write(buf)?;
+        self.crc.update(&buf[..len]);
+        self.size += len as u64;
+
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner
+            .as_mut()
+            .expect("zip encoder used after finish")
+            .flush()
+    }
+}
+
+impl<W: Write> Drop for ZipEncoder<W> {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
+
+/// Skip the data of an entry, and its data descriptor.
+fn skip_entry<R: BufRead>(inner: &mut R, header: &LocalHeader) -> io::Result<()> {
+    if !header.has_data_descriptor() {
+        let size = header.compressed_size;
+        if io::copy(&mut inner.take(size), &mut io::sink())? != size {
+            return Err(truncated());
+        }
+    } else if header.method == DEFLATED {
+        io::copy(&mut DeflateDecoder::new(&mut *inner), &mut io::sink())?;
+        read_data_descriptor(inner, header.zip64)?;
+    } else {
+        return Err(unsupported(
+            "zip entries of unknown size can only be skipped if they are deflated",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Check that the archive ends with a whole end of central directory record, the central
+/// directory starts with `signature`.
+fn check_end<R: Read>(inner: &mut R, signature: Option<u32>) -> io::Result<()> {
+    // the end of central directory record is followed by a comment of at most 64 KiB
+    let max_len = END_OF_CENTRAL_DIRECTORY_SIZE as usize + usize::from(u16::MAX);
+    let mut tail = signature.ok_or_else(truncated)?.to_le_bytes().to_vec();
+    let mut buf = [0u8; 8192];
+    loop {
+        let len = match inner.read(&mut buf) {
+            Ok(0) => break,
+            Ok(len) => len,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        tail.extend(&buf[..len]);
+        if tail.len() > 2 * max_len {
+            tail.drain(..tail.len() - max_len);
+        }
+    }
+
+    if tail.len() < END_OF_CENTRAL_DIRECTORY_SIZE as usize {
+        return Err(truncated());
+    }
+    let last = tail.len() - END_OF_CENTRAL_DIRECTORY_SIZE as usize;
+    let complete = (0..=last).rev().any(|i| {
+        u32_at(&tail, i) == END_OF_CENTRAL_DIRECTORY
+            && i + END_OF_CENTRAL_DIRECTORY_SIZE as usize + usize::from(u16_at(&tail, i + 20))
+                <= tail.len()
+    });
+    if !complete {
+        return Err(truncated());
+    }
+
+    Ok(())
+}
+
+/// Read the checksum and the uncompressed size in a data descriptor, its signature is optional.
+fn read_data_descriptor<R: Read>(inner: &mut R, zip64: bool) -> io::Result<(u32, u64)> {
+    let mut crc = read_signature(inner)?.ok_or_else(truncated)?;
+    if crc == DATA_DESCRIPTOR {
+        crc = read_signature(inner)?.ok_or_else(truncated)?;
+    }
+
+    let mut sizes = [0u8; 16];
+    let sizes = if zip64 {
+        &mut sizes[..]
+    } else {
+        &mut sizes[..8]
+    };
+    inner.read_exact(sizes).map_err(|_| truncated())?;
+
+    let size = if zip64 {
+        u64_at(sizes, 8)
+    } else {
+        u64::from(u32_at(sizes, 4))
+    };
+
+    Ok((crc, size))
+}
+
+/// Read a little-endian u32, `None` at the end of the stream.
+fn read_signature<R: Read>(inner: &mut R) -> io::Result<Option<u32>> {
+    let mut buf = [0u8; 4];
+    let mut len = 0;
+    while len < buf.len() {
+        match inner.read(&mut buf[len..]) {
+            Ok(0) if len == 0 => return Ok(None),
+            Ok(0) => return Err(truncated()),
+            Ok(n) => len += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(Some(u32::from_le_bytes(buf)))
+}
+
+fn u16_at(bytes: &[u8], pos: usize) -> u16 {
+    u16::from_le_bytes([bytes[pos], bytes[pos + 1]])
+}
+
+fn u32_at(bytes: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes([bytes[pos], bytes[pos + 1], bytes[pos + 2], bytes[pos + 3]])
+}
+
+fn u64_at(bytes: &[u8], pos: usize) -> u64 {
+    u64::from(u32_at(bytes, pos)) | u64::from(u32_at(bytes, pos + 4)) << 32
+}
+
+fn unsupported(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::Unsupported, message.to_string())
+}
+
+fn corrupted() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "zip archive is corrupted")
+}
+
+fn truncated() -> io::Error {
+    io::Error::new(io::ErrorKind::UnexpectedEof, "zip archive is truncated")
+}
@@ -0,0 +1,945 @@
+//! Readers and writers generic over the wrapped stream.
+//!
+//! [get_reader] returns a [Decoder] and [get_writer] an [Encoder], concrete types that
+//! dispatch statically to the decoder or encoder of the format. The wrapped stream isn't
+//! boxed: they are [Send] and [Sync] when it is, without the separate [send](crate::send)
+//! module, and reads aren't dispatched through trait objects.
+//!
+//! They are the readers and writers of every other module, which box them. Codecs registered
+//! in [codec] read and write trait objects: with the functions of this module,
+//! streams in their formats fail with [Error::UnsupportedFormat], they're read and written by
+//! the boxed functions like [get_reader](crate::get_reader).
+
+/* standard use */
+use std::io;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+/* project use */
+use crate::basic::codec;
+use crate::basic::compression::Format;
+use crate::builder::{DecoderOptions, EncoderOptions, Limits};
+use crate::error::Error;
+use crate::level::Level;
+use crate::utils::{decoder_error, decoding_error, Consumed, Counter};
+
+/// Input of the decoders, the bytes read to find out the format put back in front of the stream.
+pub(crate) type Peeked<R> = Counter<io::Chain<io::Cursor<Vec<u8>>, R>>;
+
+/// A readable stream that decompress `R`, created by [get_reader] or [get_reader_with_format].
+pub struct Decoder<R: Read> {
+    inner: DecoderInner<R>,
+    format: Format,
+    /// read from `R`, to give the position of errors
+    consumed: Arc<Consumed>,
+    /// returned by the decoder, to tell where the stream is truncated
+    decompressed: u64,
+    limits: Limits,
+}
+
+enum DecoderInner<R: Read> {
+    No(Peeked<R>),
+    #[cfg(feature = "gz")]
+    Gzip(flate2::read::MultiGzDecoder<Peeked<R>>),
+    #[cfg(feature = "gz")]
+    GzipMember(flate2::read::GzDecoder<Peeked<R>>),
+    #[cfg(feature = "gz")]
+    Zlib(flate2::read::ZlibDecoder<Peeked<R>>),
+    #[cfg(feature = "gz")]
+    Deflate(flate2::read::DeflateDecoder<Peeked<R>>),
+    #[cfg(feature = "bz2")]
+    Bzip(bzip2::read::MultiBzDecoder<Peeked<R>>),
+    #[cfg(feature = "bz2")]
+    BzipMember(bzip2::read::BzDecoder<Peeked<R>>),
+    // xz, lzma and lzip streams, with the memory limit of the decoder
+    #[cfg(any(feature = "lzma", feature = "lzip"))]
+    Xz(liblzma::read::XzDecoder<Peeked<R>>, u64),
+    // with the maximum window log of the decoder
+    #[cfg(feature = "zstd")]
+    Zstd(
+        zstd::stream::read::Decoder<'static, io::BufReader<Peeked<R>>>,
+        u32,
+    ),
+    #[cfg(feature = "lz4")]
+    Lz4(crate::formats::lz4::Lz4Decoder<Peeked<R>>),
+    // boxed, it's much larger than the other decoders
+    #[cfg(feature = "brotli")]
+    Brotli(Box<crate::formats::brotli::BrotliDecoder<Peeked<R>>>),
+    #[cfg(feature = "snappy")]
+    Snappy(snap::read::FrameDecoder<Peeked<R>>),
+    #[cfg(feature = "compress")]
+    Compress(crate::formats::lzw::LzwDecoder<Peeked<R>>),
+    #[cfg(feature = "lzo")]
+    Lzo(crate::formats::lzo::LzopDecoder<Peeked<R>>),
+    #[cfg(feature = "zip")]
+    Zip(crate::formats::zip::ZipDecoder<Peeked<R>>),
+    // the stream returned by a registered codec, `R` is then a boxed stream
+    Codec(R),
+}
+
+impl<R: Read> Decoder<R> {
+    /// Create the decoder of `format`, `first_bytes` were read from the start of `in_stream`.
+    ///
+    /// Formats of registered codecs need boxed streams, they aren't supported.
+    pub(crate) fn new(
+        first_bytes: Vec<u8>,
+        in_stream: R,
+        format: Format,
+        options: &DecoderOptions,
+    ) -> Result<Self, Error> {
+        if codec::find_registered(format).is_some() {
+            return Err(Error::UnsupportedFormat {
+                format,
+                feature: None,
+            });
+        }
+
+        Self::with_options(first_bytes, in_stream, format, options)
+    }
+
+    /// The decoder of a built-in format, with the options of a [ReaderBuilder](crate::ReaderBuilder).
+    pub(crate) fn with_options(
+        first_bytes: Vec<u8>,
+        in_stream: R,
+        format: Format,
+        options: &DecoderOptions,
+    ) -> Result<Self, Error> {
+        // frames of zstd versions before 0.8 can only be decoded with the `zstd-legacy` feature
+        #[cfg(all(feature = "zstd", not(feature = "zstd-legacy")))]
+        let (first_bytes, in_stream) = match format {
+            Format::Zstd if first_bytes.is_empty() => crate::utils::read_first_bytes(in_stream)?,
+            _ => (first_bytes, in_stream),
+        };
+        #[cfg(all(feature = "zstd", not(feature = "zstd-legacy")))]
+        if let (Format::Zstd, [0x25..=0x27, 0xb5, 0x2f, 0xfd, ..]) = (format, &first_bytes[..]) {
+            return Err(Error::UnsupportedFormat {
+                format: Format::Zstd,
+                feature: Some("zstd-legacy"),
+            });
+        }
+
+        Self::build(first_bytes, in_stream, format, |inp| {
+            DecoderInner::new(inp, format, options)
+        })
+    }
+
+    /// Fail once the decompressed stream is larger than the limits.
+    pub(crate) fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// The decoder returned by `inner` for the stream, with the first bytes put back in front of it.
+    fn build(
+        first_bytes: Vec<u8>,
+        in_stream: R,
+        format: Format,
+        inner: impl FnOnce(Peeked<R>) -> Result<DecoderInner<R>, Error>,
+    ) -> Result<Self, Error> {
+        let consumed = Arc::new(Consumed::default());
+        let inp = Counter::new(
+            io::Cursor::new(first_bytes).chain(in_stream),
+            Arc::clone(&consumed),
+        );
+        let inner = inner(inp).map_err(|e| decoder_error(e, format, &consumed))?;
+
+        Ok(Decoder {
+            inner,
+            format,
+            consumed,
+            decompressed: 0,
+            limits: Limits::default(),
+        })
+    }
+}
+
+// only the boxed readers of niffler use registered codecs
+#[allow(private_bounds)]
+impl<R: BoxedRead> Decoder<R> {
+    /// Similar to [Decoder::with_options] but formats of registered codecs are read by them.
+    pub(crate) fn boxed(
+        first_bytes: Vec<u8>,
+        in_stream: R,
+        format: Format,
+        options: &DecoderOptions,
+    ) -> Result<Self, Error> {
+        match codec::find_registered(format) {
+            Some(codec) => Self::build(first_bytes, in_stream, format, |inp| {
+                Ok(DecoderInner::Codec(R::decoder(&*codec, inp)?))
+            }),
+            None => Self::with_options(first_bytes, in_stream, format, options),
+        }
+    }
+}
+
+/// Boxed streams, the streams read by the decoders of registered codecs.
+pub(crate) trait BoxedRead: Read + Sized {
+    /// The decoder of `codec`, that reads `in_stream`.
+    fn decoder(codec: &dyn codec::Codec, in_stream: Peeked<Self>) -> Result<Self, Error>;
+}
+
+impl<'a> BoxedRead for Box<dyn Read + 'a> {
+    fn decoder(codec: &dyn codec::Codec, in_stream: Peeked<Self>) -> Result<Self, Error> {
+        codec.decoder(Box::new(in_stream))
+    }
+}
+
+impl<'a> BoxedRead for Box<dyn Read + Send + 'a> {
+    fn decoder(codec: &dyn codec::Codec, in_stream: Peeked<Self>) -> Result<Self, Error> {
+        codec.send_decoder(Box::new(in_stream))
+    }
+}
+
+impl<R: Read> DecoderInner<R> {
+    /// The decoder of `format`, its errors are given by [Decoder].
+    // `options` isn't used when no format using it is enabled
+    #[allow(unused_variables)]
+    fn new(inp: Peeked<R>, format: Format, options: &DecoderOptions) -> Result<Self, Error> {
+        // the decoders of these formats always verify their checksums
+        if !options.verify_checksums
+            && matches!(
+                format,
+                Format::Gzip
+                    | Format::Zlib
+                    | Format::Bzip
+                    | Format::Zstd
+                    | Format::Lz4
+                    | Format::Snappy
+            )
+        {
+            return Err(Error::ChecksumRequired { format });
+        }
+
+        #[allow(unreachable_patterns)]
+        let inner = match format {
+            Format::No => DecoderInner::No(inp),
+            #[cfg(feature = "gz")]
+            Format::Gzip if options.multi_member => {
+                DecoderInner::Gzip(flate2::read::MultiGzDecoder::new(inp))
+            }
+            #[cfg(feature = "gz")]
+            Format::Gzip => DecoderInner::GzipMember(flate2::read::GzDecoder::new(inp)),
+            #[cfg(feature = "gz")]
+            Format::Zlib => DecoderInner::Zlib(flate2::read::ZlibDecoder::new(inp)),
+            #[cfg(feature = "gz")]
+            Format::Deflate => DecoderInner::Deflate(flate2::read::DeflateDecoder::new(inp)),
+            #[cfg(feature = "bz2")]
+            Format::Bzip if options.multi_member => {
+                DecoderInner::Bzip(bzip2::read::MultiBzDecoder::new(inp))
+            }
+            #[cfg(feature = "bz2")]
+            Format::Bzip => DecoderInner::BzipMember(bzip2::read::BzDecoder::new(inp)),
+            #[cfg(feature = "lzma")]
+            Format::Lzma => {
+                let stream = liblzma::stream::Stream::new_stream_decoder(
+                    options.xz_memlimit,
+                    options.xz_flags(),
+                )
+                .map_err(io::Error::from)?;
+                DecoderInner::Xz(
+                    liblzma::read::XzDecoder::new_stream(inp, stream),
+                    options.xz_memlimit,
+                )
+            }
+            #[cfg(feature = "lzma")]
+            Format::LzmaAlone => {
+                let stream = liblzma::stream::Stream::new_lzma_decoder(options.xz_memlimit)
+                    .map_err(io::Error::from)?;
+                DecoderInner::Xz(
+                    liblzma::read::XzDecoder::new_stream(inp, stream),
+                    options.xz_memlimit,
+                )
+            }
+            #[cfg(feature = "lzip")]
+            Format::Lzip => {
+                let stream = liblzma::stream::Stream::new_lzip_decoder(
+                    options.xz_memlimit,
+                    options.xz_flags(),
+                )
+                .map_err(io::Error::from)?;
+                DecoderInner::Xz(
+                    liblzma::read::XzDecoder::new_stream(inp, stream),
+                    options.xz_memlimit,
+                )
+            }
+            #[cfg(feature = "zstd")]
+            Format::Zstd => {
+                let mut decoder = zstd::stream::read::Decoder::new(inp)?;
+                let window_log_max = options
+                    .zstd_window_log_max
+                    .unwrap_or(crate::formats::limits::ZSTD_WINDOW_LOG_MAX);
+                decoder.window_log_max(window_log_max)?;
+                if options.multi_member {
+                    DecoderInner::Zstd(decoder, window_log_max)
+                } else {
+                    DecoderInner::Zstd(decoder.single_frame(), window_log_max)
+                }
+            }
+            #[cfg(feature = "lz4")]
+            Format::Lz4 => DecoderInner::Lz4(crate::formats::lz4::Lz4Decoder::new(inp)),
+            #[cfg(feature = "brotli")]
+            Format::Brotli => {
+                DecoderInner::Brotli(Box::new(crate::formats::brotli::BrotliDecoder::new(inp)))
+            }
+            #[cfg(feature = "snappy")]
+            Format::Snappy => DecoderInner::Snappy(snap::read::FrameDecoder::new(inp)),
+            #[cfg(feature = "compress")]
+            Format::Compress => DecoderInner::Compress(crate::formats::lzw::LzwDecoder::new(inp)?),
+            #[cfg(feature = "lzo")]
+            Format::Lzo => DecoderInner::Lzo(
+                crate::formats::lzo::LzopDecoder::new(inp)?
+                    .verify_checksums(options.verify_checksums),
+            ),
+            #[cfg(feature = "zip")]
+            Format::Zip => DecoderInner::Zip(
+                crate::formats::zip::ZipDecoder::new(inp, None)?
+                    .verify_checksums(options.verify_checksums),
+            ),
+            Format::Custom(_) => return Err(Error::UnknownFormat(format)),
+            _ => return Err(Error::feature_disabled(format)),
+        };
+
+        Ok(inner)
+    }
+}
+
+impl<R: Read> Read for Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // once a limit is exceeded, later reads fail with the same error
+        self.limits.check(self.decompressed, &self.consumed)?;
+
+        // one byte more than the limit tells if the stream is larger
+        let max = match self.limits.max_size {
+            Some(limit) => limit
+                .saturating_sub(self.decompressed)
+                .saturating_add(1)
+                .min(buf.len() as u64) as usize,
+            None => buf.len(),
+        };
+
+        match self.inner.read(&mut buf[..max]) {
+            Ok(len) => {
+                self.decompressed += len as u64;
+                self.limits.check(self.decompressed, &self.consumed)?;
+                Ok(len)
+            }
+            Err(e) => Err(
+                match decoding_error(&e, self.format, &self.consumed, self.decompressed) {
+                    Some(error) => io::Error::new(e.kind(), error),
+                    None => e,
+                },
+            ),
+        }
+    }
+}
+
+impl<R: Read> Read for DecoderInner<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            DecoderInner::No(r) => r.read(buf),
+            #[cfg(feature = "gz")]
+            DecoderInner::Gzip(r) => r.read(buf),
+            #[cfg(feature = "gz")]
+            DecoderInner::GzipMember(r) => r.read(buf),
+            #[cfg(feature = "gz")]
+            DecoderInner::Zlib(r) => r.read(buf),
+            #[cfg(feature = "gz")]
+            DecoderInner::Deflate(r) => r.read(buf),
+            #[cfg(feature = "bz2")]
+            DecoderInner::Bzip(r) => r.read(buf),
+            #[cfg(feature = "bz2")]
+            DecoderInner::BzipMember(r) => r.read(buf),
+            #[cfg(any(feature = "lzma", feature = "lzip"))]
+            DecoderInner::Xz(r, memlimit) => r
+                .read(buf)
+                .map_err(|e| crate::formats::limits::xz_error(e, *memlimit)),
+            #[cfg(feature = "zstd")]
+            DecoderInner::Zstd(r, window_log_max) => r
+                .read(buf)
+                .map_err(|e| crate::formats::limits::zstd_error(e, *window_log_max)),
+            #[cfg(feature = "lz4")]
+            DecoderInner::Lz4(r) => r.read(buf),
+            #[cfg(feature = "brotli")]
+            DecoderInner::Brotli(r) => r.read(buf),
+            #[cfg(feature = "snappy")]
+            DecoderInner::Snappy(r) => r.read(buf),
+            #[cfg(feature = "compress")]
+            DecoderInner::Compress(r) => r.read(buf),
+            #[cfg(feature = "lzo")]
+            DecoderInner::Lzo(r) => r.read(buf),
+            #[cfg(feature = "zip")]
+            DecoderInner::Zip(r) => r.read(buf),
+            DecoderInner::Codec(r) => r.read(buf),
+        }
+    }
+}
+
+/// A writable stream that compress data to `W`, created by [get_writer].
+///
+/// Like the writers of [crate::get_writer], the compressed stream is finished when it's dropped.
+pub struct Encoder<W: Write>(EncoderInner<W>);
+
+enum EncoderInner<W: Write> {
+    No(W),
+    #[cfg(feature = "gz")]
+    Gzip(flate2::write::GzEncoder<W>),
+    #[cfg(feature = "gz")]
+    Zlib(flate2::write::ZlibEncoder<W>),
+    #[cfg(feature = "gz")]
+    Deflate(flate2::write::DeflateEncoder<W>),
+    #[cfg(feature = "bz2")]
+    Bzip(bzip2::write::BzEncoder<W>),
+    #[cfg(feature = "lzma")]
+    Xz(liblzma::write::XzEncoder<W>),
+    // finished on drop by Encoder, an auto-finishing encoder wouldn't be Sync
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::stream::write::Encoder<'static, W>),
+    #[cfg(feature = "lz4")]
+    Lz4(crate::formats::lz4::Lz4Encoder<W>),
+    // boxed, it's much larger than the other encoders
+    #[cfg(feature = "brotli")]
+    Brotli(Box<brotli::CompressorWriter<W>>),
+    // boxed, it's much larger than the other encoders
+    #[cfg(feature = "snappy")]
+    Snappy(Box<snap::write::FrameEncoder<W>>),
+    #[cfg(feature = "lzip")]
+    Lzip(crate::formats::lzip::LzipEncoder<W>),
+    #[cfg(feature = "compress")]
+    Compress(crate::formats::lzw::LzwEncoder<W>),
+    #[cfg(feature = "lzo")]
+    Lzo(crate::formats::lzo::LzopEncoder<W>),
+    #[cfg(feature = "zip")]
+    Zip(crate::formats::zip::ZipEncoder<W>),
+    // the stream returned by a registered codec, `W` is then a boxed stream
+    Codec(W),
+}
+
+impl<W: Write> Encoder<W> {
+    /// Create the encoder of `format`, zip archives get a single entry called `name`.
+    ///
+    /// Formats of registered codecs need boxed streams, they aren't supported.
+    pub(crate) fn new(out: W, format: Format, level: Level, name: &str) -> Result<Self, Error> {
+        if codec::find_registered(format).is_some() {
+            return Err(Error::UnsupportedFormat {
+                format,
+                feature: None,
+            });
+        }
+
+        let options = EncoderOptions {
+            level,
+            entry_name: name.to_string(),
+            ..Default::default()
+        };
+
+        Self::with_options(out, format, &options)
+    }
+
+    /// The encoder of a built-in format, with the options of a [WriterBuilder](crate::WriterBuilder).
+    // `options` isn't used when no format using it is enabled
+    #[allow(unused_variables)]
+    pub(crate) fn with_options(
+        out: W,
+        format: Format,
+        options: &EncoderOptions,
+    ) -> Result<Self, Error> {
+        let level = options.level;
+
+        #[allow(unreachable_patterns)]
+        let inner = match format {
+            Format::No => EncoderInner::No(out),
+            #[cfg(feature = "gz")]
+            Format::Gzip => EncoderInner::Gzip(flate2::write::GzEncoder::new(out, level.into())),
+            #[cfg(feature = "gz")]
+            Format::Zlib => EncoderInner::Zlib(flate2::write::ZlibEncoder::new(out, level.into())),
+            #[cfg(feature = "gz")]
+            Format::Deflate => {
+                EncoderInner::Deflate(flate2::write::DeflateEncoder::new(out, level.into()))
+            }
+            #[cfg(feature = "bz2")]
+            Format::Bzip => EncoderInner::Bzip(bzip2::write::BzEncoder::new(out, level.into())),
+            #[cfg(feature = "lzma")]
+            Format::Lzma => {
+                let check = if options.checksum.unwrap_or(true) {
+                    liblzma::stream::Check::Crc64
+                } else {
+                    liblzma::stream::Check::None
+                };
+                let stream = liblzma::stream::Stream::new_easy_encoder(level.into(), check)
+                    .map_err(io::Error::from)?;
+                EncoderInner::Xz(liblzma::write::XzEncoder::new_stream(out, stream))
+            }
+            #[cfg(feature = "lzma")]
+            Format::LzmaAlone => {
+                let lzma_options =
+                    liblzma::stream::LzmaOptions::new_preset(u32::from(level).min(9))
+                        .map_err(io::Error::from)?;
+                let stream = liblzma::stream::Stream::new_lzma_encoder(&lzma_options)
+                    .map_err(io::Error::from)?;
+                EncoderInner::Xz(liblzma::write::XzEncoder::new_stream(out, stream))
+            }
+            #[cfg(feature = "zstd")]
+            Format::Zstd => {
+                let mut encoder = zstd::stream::write::Encoder::new(out, level.into())?;
+                if let Some(checksum) = options.checksum {
+                    encoder.include_checksum(checksum)?;
+                }
+                if let Some(window_log) = options.zstd_window_log {
+                    encoder.long_distance_matching(true)?;
+                    encoder.window_log(window_log)?;
+                }
+                #[cfg(feature = "zstd-mt")]
+                if options.threads > 1 {
+                    encoder.multithread(options.threads)?;
+                }
+                EncoderInner::Zstd(encoder)
+            }
+            #[cfg(feature = "lz4")]
+            Format::Lz4 => EncoderInner::Lz4(crate::formats::lz4::Lz4Encoder::new(
+                out,
+                u32::from(level).min(12),
+                options.checksum.unwrap_or(true),
+            )?),
+            #[cfg(feature = "brotli")]
+            Format::Brotli => EncoderInner::Brotli(Box::new(brotli::CompressorWriter::new(
+                out,
+                4096,
+                u32::from(level).min(11),
+                options.brotli_window,
+            ))),
+            #[cfg(feature = "snappy")]
+            Format::Snappy => EncoderInner::Snappy(Box::new(snap::write::FrameEncoder::new(out))),
+            #[cfg(feature = "lzip")]
+            Format::Lzip => {
+                EncoderInner::Lzip(crate::formats::lzip::LzipEncoder::new(out, level.into())?)
+            }
+            #[cfg(feature = "compress")]
+            Format::Compress => EncoderInner::Compress(crate::formats::lzw::LzwEncoder::new(out)?),
+            #[cfg(feature = "lzo")]
+            Format::Lzo => EncoderInner::Lzo(crate::formats::lzo::LzopEncoder::new(out)?),
+            #[cfg(feature = "zip")]
+            Format::Zip => EncoderInner::Zip(crate::formats::zip::ZipEncoder::new(
+                out,
+                &options.entry_name,
+                level.into(),
+            )?),
+            Format::Custom(_) => return Err(Error::UnknownFormat(format)),
+            _ => return Err(Error::feature_disabled(format)),
+        };
+
+        Ok(Encoder(inner))
+    }
+}
+
+// only the boxed writers of niffler use registered codecs
+#[allow(private_bounds)]
+impl<W: BoxedWrite> Encoder<W> {
+    /// Similar to [Encoder::with_options] but formats of registered codecs are written by them.
+    pub(crate) fn boxed(out: W, format: Format, options: &EncoderOptions) -> Result<Self, Error> {
+        match codec::find_registered(format) {
+            Some(codec) => Ok(Encoder(EncoderInner::Codec(W::encoder(
+                &*codec,
+                out,
+                options.level,
+            )?))),
+            None => Self::with_options(out, format, options),
+        }
+    }
+}
+
+/// Boxed streams, the streams written by the encoders of registered codecs.
+pub(crate) trait BoxedWrite: Write + Sized {
+    /// The encoder of `codec`, that writes to `out_stream`.
+    fn encoder(codec: &dyn codec::Codec, out_stream: Self, level: Level) -> Result<Self, Error>;
+}
+
+impl<'a> BoxedWrite for Box<dyn Write + 'a> {
+    fn encoder(codec: &dyn codec::Codec, out_stream: Self, level: Level) -> Result<Self, Error> {
+        codec.encoder(out_stream, level)
+    }
+}
+
+impl<'a> BoxedWrite for Box<dyn Write + Send + 'a> {
+    fn encoder(codec: &dyn codec::Codec, out_stream: Self, level: Level) -> Result<Self, Error> {
+        codec.send_encoder(out_stream, level)
+    }
+}
+
+impl<W: Write> Write for Encoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match &mut self.0 {
+            EncoderInner::No(w) => w.write(buf),
+            #[cfg(feature = "gz")]
+            EncoderInner::Gzip(w) => w.write(buf),
+            #[cfg(feature = "gz")]
+            EncoderInner::Zlib(w) => w.write(buf),
+            #[cfg(feature = "gz")]
+            EncoderInner::Deflate(w) => w.write(buf),
+            #[cfg(feature = "bz2")]
+            EncoderInner::Bzip(w) => w.write(buf),
+            #[cfg(feature = "lzma")]
+            EncoderInner::Xz(w) => w.write(buf),
+            #[cfg(feature = "zstd")]
+            EncoderInner::Zstd(w) => w.write(buf),
+            #[cfg(feature = "lz4")]
+            EncoderInner::Lz4(w) => w.write(buf),
+            #[cfg(feature = "brotli")]
+            EncoderInner::Brotli(w) => w.write(buf),
+            #[cfg(feature = "snappy")]
+            EncoderInner::Snappy(w) => w.write(buf),
+            #[cfg(feature = "lzip")]
+            EncoderInner::Lzip(w) => w.write(buf),
+            #[cfg(feature = "compress")]
+            EncoderInner::Compress(w) => w.write(buf),
+            #[cfg(feature = "lzo")]
+            EncoderInner::Lzo(w) => w.write(buf),
+            #[cfg(feature = "zip")]
+            EncoderInner::Zip(w) => w.write(buf),
+            EncoderInner::Codec(w) => w.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.0 {
+            EncoderInner::No(w) => w.flush(),
+            #[cfg(feature = "gz")]
+            EncoderInner::Gzip(w) => w.flush(),
+            #[cfg(feature = "gz")]
+            EncoderInner::Zlib(w) => w.flush(),
+            #[cfg(feature = "gz")]
+            EncoderInner::Deflate(w) => w.flush(),
+            #[cfg(feature = "bz2")]
+            EncoderInner::Bzip(w) => w.flush(),
+            #[cfg(feature = "lzma")]
+            EncoderInner::Xz(w) => w.flush(),
+            #[cfg(feature = "zstd")]
+            EncoderInner::Zstd(w) => w.flush(),
+            #[cfg(feature = "lz4")]
+            EncoderInner::Lz4(w) => w.flush(),
+            #[cfg(feature = "brotli")]
+            EncoderInner::Brotli(w) => w.flush(),
+            #[cfg(feature = "snappy")]
+            EncoderInner::Snappy(w) => w.flush(),
+            #[cfg(feature = "lzip")]
+            EncoderInner::Lzip(w) => w.flush(),
+            #[cfg(feature = "compress")]
+            EncoderInner::Compress(w) => w.flush(),
+            #[cfg(feature = "lzo")]
+            EncoderInner::Lzo(w) => w.flush(),
+            #[cfg(feature = "zip")]
+            EncoderInner::Zip(w) => w.flush(),
+            EncoderInner::Codec(w) => w.flush(),
+        }
+    }
+}
+
+impl<W: Write> Drop for Encoder<W> {
+    fn drop(&mut self) {
+        // other encoders finish their stream when they are dropped
+        #[cfg(feature = "zstd")]
+        if let EncoderInner::Zstd(w) = &mut self.0 {
+            let _ = w.do_finish();
+        }
+    }
+}
+
+/// Similar to [get_reader](crate::get_reader) but the stream isn't boxed, the [Decoder] is
+/// [Send] and [Sync] when `R` is.
+///
+/// # Example
+/// ```
+/// # fn main() -> Result<(), niffler::Error> {
+/// use std::io::{Read, Write};
+/// use niffler::compression::Format;
+///
+/// # #[cfg(feature = "gz")] {
+/// let mut buffer = vec![];
+/// {
+///   let mut writer = niffler::generic::get_writer(&mut buffer, Format::Gzip, niffler::Level::One)?;
+///   writer.write_all(b"I'm compress in gzip\n")?;
+/// }
+///
+/// let (mut reader, format) = niffler::generic::get_reader(&buffer[..])?;
+///
+/// // the reader can be moved to another thread
+/// let contents = std::thread::scope(|s| {
+///     s.spawn(move || {
+///         let mut contents = String::new();
+///         reader.read_to_string(&mut contents).map(|_| contents)
+///     })
+///     .join()
+///     .unwrap()
+/// })?;
+///
+/// assert_eq!(format, Format::Gzip);
+/// assert_eq!(contents, "I'm compress in gzip\n");
+/// # }
+/// # Ok(())
+/// # }
+/// ```
+pub fn get_reader<R: Read>(in_stream: R) -> Result<(Decoder<R>, Format), Error> {
+    let (first_bytes, in_stream) = crate::utils::get_first_bytes_read(in_stream)?;
+
+    let format = codec::detect(&first_bytes);
+
+    let options = DecoderOptions::default();
+
+    Ok((
+        Decoder::new(first_bytes, in_stream, format, &options)?,
+        format,
+    ))
+}
+
+/// Similar to [get_reader_with_format](crate::get_reader_with_format) but the stream isn't boxed.
+pub fn get_reader_with_format<R: Read>(
+    in_stream: R,
+    format: Format,
+) -> Result<(Decoder<R>, Format), Error> {
+    let options = DecoderOptions::default();
+
+    Ok((Decoder::new(vec![], in_stream, format, &options)?, format))
+}
+
+/// Similar to [get_bufread](crate::get_bufread) but the stream isn't boxed.
+pub fn get_bufread<R: Read>(
+    in_stream: R,
+    capacity: usize,
+) -> Result<(io::BufReader<Decoder<R>>, Format), Error> {
+    let (reader, format) = get_reader(in_stream)?;
+
+    Ok((io::BufReader::with_capacity(capacity, reader), format))
+}
+
+/// Similar to [get_writer](crate::get_writer) but the stream isn't boxed, the [Encoder] is
+/// [Send] and [Sync] when `W` is.
+pub fn get_writer<W: Write>(
+    out_stream: W,
+    format: Format,
+    level: Level,
+) -> Result<Encoder<W>, Error> {
+    Encoder::new(out_stream, format, level, "-")
+}
+
+/// Similar to [from_path](crate::from_path) but the file isn't boxed.
+pub fn from_path<P: AsRef<Path>>(
+    path: P,
+) -> Result<(Decoder<io::BufReader<std::fs::File>>, Format), Error> {
+    let path = path.as_ref();
+    let file = crate::utils::open_file(path)?;
+
+    // the central directory of zip archives tells if they have several files before reading them
+    #[cfg(feature = "zip")]
+    crate::formats::zip::check_single_file(&file)?;
+
+    let readable = io::BufReader::new(file);
+
+    match Format::from_path(path).filter(|format| format.is_undetectable()) {
+        Some(format) => get_reader_with_format(readable, format),
+        None => get_reader(readable),
+    }
+}
+
+/// Similar to [to_path](crate::to_path) but the file isn't boxed.
+pub fn to_path<P: AsRef<Path>>(
+    path: P,
+    format: Format,
+    level: Level,
+) -> Result<Encoder<io::BufWriter<std::fs::File>>, Error> {
+    let path = path.as_ref();
+    let writable = io::BufWriter::new(crate::utils::create_file(path)?);
+
+    // the archive entry is named like the archive, without its extension
+    let name = path
+        .file_stem()
+        .map_or("-".into(), |stem| stem.to_string_lossy());
+
+    Encoder::new(writable, format, level, &name)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const FORMATS: [Format; 14] = [
+        Format::Gzip,
+        Format::Bzip,
+        Format::Lzma,
+        Format::LzmaAlone,
+        Format::Zstd,
+        Format::Lz4,
+        Format::Brotli,
+        Format::Snappy,
+        Format::Lzip,
+        Format::Compress,
+        Format::Lzo,
+        Format::Zlib,
+        Format::Deflate,
+        Format::Zip,
+    ];
+
+    fn is_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn send_sync() {
+        is_send_sync::<Decoder<&[u8]>>();
+        is_send_sync::<Encoder<Vec<u8>>>();
+        is_send_sync::<Decoder<io::BufReader<std::fs::File>>>();
+    }
+
+    #[test]
+    fn compress_uncompress() {
+        for format in FORMATS {
+            let mut buffer = vec![];
+            match get_writer(&mut buffer, format, Level::One) {
+                Ok(mut writer) => writer.write_all(b"I'm compress\n").unwrap(),
+                Err(Error::UnsupportedFormat { .. }) => continue,
+                Err(e) => panic!("{format:?}: {e}"),
+            }
+
+            let (mut reader, detected) = get_reader_with_format(&buffer[..], format).unwrap();
+            let mut contents = String::new();
+            reader.read_to_string(&mut contents).unwrap();
+
+            assert_eq!(detected, format);
+            assert_eq!(contents, "I'm compress\n");
+
+            if !format.is_undetectable() {
+                let (mut reader, detected) = get_reader(&buffer[..]).unwrap();
+                let mut contents = String::new();
+                reader.read_to_string(&mut contents).unwrap();
+
+                assert_eq!(detected, format);
+                assert_eq!(contents, "I'm compress\n");
+            }
+        }
+    }
+
+    #[test]
+    fn same_as_boxed() {
+        for format in FORMATS {
+            let mut buffer = vec![];
+            match crate::get_writer(Box::new(&mut buffer), format, Level::Six) {
+                Ok(mut writer) => writer.write_all(b"I'm compress\n").unwrap(),
+                Err(Error::UnsupportedFormat { .. }) => continue,
+                Err(e) => panic!("{format:?}: {e}"),
+            }
+
+            let mut generic = vec![];
+            get_writer(&mut generic, format, Level::Six)
+                .unwrap()
+                .write_all(b"I'm compress\n")
+                .unwrap();
+
+            assert_eq!(buffer, generic, "{format:?}");
+        }
+    }
+
+    #[test]
+    fn no_compression() {
+        let (mut reader, format) = get_reader(&b"I'm not compressed\n"[..]).unwrap();
+        let mut contents = String::new();
+        reader.read_to_string(&mut contents).unwrap();
+
+        assert_eq!(format, Format::No);
+        assert_eq!(contents, "I'm not compressed\n");
+
+        assert!(matches!(get_reader(&b"shrt"[..]), Err(Error::FileTooShort)));
+    }
+
+    #[test]
+    fn custom() {
+        assert!(matches!(
+            get_writer(vec![], Format::Custom("generic"), Level::One),
+            Err(Error::UnknownFormat(_))
+        ));
+        assert!(matches!(
+            get_reader_with_format(&b"data"[..], Format::Custom("generic")),
+            Err(Error::UnknownFormat(_))
+        ));
+    }
+
+    #[test]
+    fn path() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("generic.txt");
+        {
+            let mut writer = to_path(&path, Format::No, Level::One).unwrap();
+            writer.write_all(b"I'm in a file\n").unwrap();
+        }
+
+        let (mut reader, format) = from_path(&path).unwrap();
+        let mut contents = String::new();
+        reader.read_to_string(&mut contents).unwrap();
+
+        assert_eq!(format, Format::No);
+        assert_eq!(contents, "I'm in a file\n");
+
+        assert!(matches!(
+            from_path(dir.path().join("missing.txt")),
+            Err(Error::PathIOError { .. })
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn path_pipe() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("generic.txt");
+        let status = std::process::Command::new("mkfifo")
+            .arg(&path)
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        // opening a pipe blocks until its other end is opened
+        let writer_path = path.clone();
+        let writer = std::thread::spawn(move || std::fs::write(writer_path, "I'm in a pipe\n"));
+
+        let (mut reader, format) = from_path(&path).unwrap();
+        let mut contents = String::new();
+        reader.read_to_string(&mut contents).unwrap();
+        writer.join().unwrap().unwrap();
+
+        assert_eq!(format, Format::No);
+        assert_eq!(contents, "I'm in a pipe\n");
+    }
+
+    #[cfg(feature = "gz")]
+    #[test]
+    fn checksum_mismatch() {
+        let mut buffer = vec![];
+        get_writer(&mut buffer, Format::Gzip, Level::One)
+            .unwrap()
+            .write_all(b"I'm compress\n")
+            .unwrap();
+        let crc = buffer.len() - 8;
+        buffer[crc] ^= 0xff;
+
+        let (mut reader, _) = get_reader(&buffer[..]).unwrap();
+        let error = reader.read_to_end(&mut vec![]).unwrap_err();
+        assert!(matches!(
+            error.get_ref().and_then(|e| e.downcast_ref::<Error>()),
+            Some(Error::ChecksumMismatch { format }) if *format == Format::Gzip
+        ));
+    }
+
+    #[cfg(feature = "gz")]
+    #[test]
+    fn truncated() {
+        let mut buffer = vec![];
+        get_writer(&mut buffer, Format::Zlib, Level::One)
+            .unwrap()
+            .write_all(b"I'm compress\n")
+            .unwrap();
+        // the end of the deflate data and the checksum are missing
+        buffer.truncate(buffer.len() - 5);
+
+        let (mut reader, _) = get_reader_with_format(&buffer[..], Format::Zlib).unwrap();
+        let error = reader.read_to_end(&mut vec![]).unwrap_err();
+        assert!(matches!(
+            error.get_ref().and_then(|e| e.downcast_ref::<Error>()),
+            Some(Error::Truncated { format, .. }) if *format == Format::Zlib
+        ));
+    }
+}
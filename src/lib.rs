@@ -1,16 +1,32 @@
 #![doc = include_str!("../README.md")]
+// the deprecated modules keep their tests
+#![cfg_attr(test, allow(deprecated))]
 
 /* declare mod */
 pub mod basic;
+pub mod builder;
+pub mod content;
 pub mod error;
+pub(crate) mod formats;
+pub mod generic;
 pub mod level;
 pub mod seek;
+#[deprecated(
+    note = "only uncompressed seekable streams are returned, pass them to `seek` functions or read them directly"
+)]
 pub mod seeksend;
+#[deprecated(
+    note = "use the readers and writers of `generic`, they are `Send` when their stream is"
+)]
 pub mod send;
+#[cfg(feature = "tar")]
+pub mod tar;
 pub(crate) mod utils;
 
 /* reexport for convinent usage of niffler */
 pub use crate::basic::compression::Format;
 pub use crate::basic::*;
+pub use crate::builder::{ReaderBuilder, WriterBuilder};
+pub use crate::content::Content;
 pub use crate::error::Error;
 pub use crate::level::Level;
@@ -35,7 +35,11 @@ pub fn get_reader<'a>(
     // return readable and compression status
     match compression {
         compression::Format::No => Ok((in_stream, compression::Format::No)),
-        _ => Err(Error::FeatureDisabled),
+        // no feature supports compressed seekable streams yet
+        _ => Err(Error::UnsupportedFormat {
+            format: compression.to_basic(),
+            feature: None,
+        }),
     }
 }
 
@@ -49,7 +53,10 @@ pub fn get_writer<'a>(
 ) -> Result<Box<dyn compression::WriteSeek + 'a>, Error> {
     match format {
         compression::Format::No => Ok(Box::new(out_stream)),
-        _ => Err(Error::FeatureDisabled),
+        _ => Err(Error::UnsupportedFormat {
+            format: format.to_basic(),
+            feature: None,
+        }),
     }
 }
 
@@ -59,7 +66,7 @@ pub fn get_writer<'a>(
 pub fn from_path<'a, P: AsRef<Path>>(
     path: P,
 ) -> Result<(Box<dyn compression::ReadSeek + 'a>, compression::Format), Error> {
-    let readable = io::BufReader::new(std::fs::File::open(path)?);
+    let readable = io::BufReader::new(crate::utils::open_file(path.as_ref())?);
     get_reader(Box::new(readable))
 }
 
@@ -71,7 +78,7 @@ pub fn to_path<'a, P: AsRef<Path>>(
     format: compression::Format,
     level: Level,
 ) -> Result<Box<dyn compression::WriteSeek + 'a>, Error> {
-    let writable = io::BufWriter::new(std::fs::File::create(path)?);
+    let writable = io::BufWriter::new(crate::utils::create_file(path.as_ref())?);
     get_writer(Box::new(writable), format, level)
 }
 
@@ -5,6 +5,16 @@ pub enum Format {
     No,
 }
 
+impl Format {
+    /// The same format in [basic](crate::basic), bgzip files are gzip streams.
+    pub(crate) fn to_basic(self) -> crate::basic::compression::Format {
+        match self {
+            Format::BGzip => crate::basic::compression::Format::Gzip,
+            Format::No => crate::basic::compression::Format::No,
+        }
+    }
+}
+
 pub(crate) fn bytes2type(bytes: [u8; 17]) -> Format {
     match bytes {
         [0x1F, 0x8B, 0x8, 0x4, 0x0, 0x0, 0x0, 0x0, 0x0, 0xFF, 0x6, 0x0, 0x42, 0x43, 0x2, 0x0, 0x0] => {
@@ -32,7 +32,11 @@ pub fn get_reader<'a>(
     // return readable and compression status
     match compression {
         compression::Format::No => Ok((in_stream, compression::Format::No)),
-        _ => Err(Error::FeatureDisabled),
+        // no feature supports compressed seekable streams yet
+        _ => Err(Error::UnsupportedFormat {
+            format: compression.to_basic(),
+            feature: None,
+        }),
     }
 }
 
@@ -44,7 +48,10 @@ pub fn get_writer<'a>(
 ) -> Result<Box<dyn WriteSeek + Send + 'a>, Error> {
     match format {
         compression::Format::No => Ok(Box::new(out_stream)),
-        _ => Err(Error::FeatureDisabled),
+        _ => Err(Error::UnsupportedFormat {
+            format: format.to_basic(),
+            feature: None,
+        }),
     }
 }
 
@@ -52,7 +59,7 @@ pub fn get_writer<'a>(
 pub fn from_path<'a, P: AsRef<Path>>(
     path: P,
 ) -> Result<(Box<dyn ReadSeek + Send + 'a>, compression::Format), Error> {
-    let readable = io::BufReader::new(std::fs::File::open(path)?);
+    let readable = io::BufReader::new(crate::utils::open_file(path.as_ref())?);
     get_reader(Box::new(readable))
 }
 
@@ -62,7 +69,7 @@ pub fn to_path<'a, P: AsRef<Path>>(
     format: compression::Format,
     level: Level,
 ) -> Result<Box<dyn WriteSeek + Send + 'a>, Error> {
-    let writable = io::BufWriter::new(std::fs::File::create(path)?);
+    let writable = io::BufWriter::new(crate::utils::create_file(path.as_ref())?);
     get_writer(Box::new(writable), format, level)
 }
 
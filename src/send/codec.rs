@@ -0,0 +1,6 @@
+//! Similar to [codec](crate::codec) but streams are now sendable.
+//!
+//! The registry is the one of [codec](crate::codec), the functions of [send](crate::send)
+//! read and write streams with [Codec::send_decoder] and [Codec::send_encoder].
+
+pub use crate::basic::codec::{codecs, register, Codec};
@@ -1,146 +1,183 @@
-#![allow(clippy::unnecessary_wraps)]
-
-/* standard use */
-use std::io;
-
-/* crates use */
-use cfg_if::cfg_if;
-
 /* project use */
-use crate::error::Error;
-use crate::level::Level;
+use crate::basic::compression::is_before_next_frame;
+
+pub use crate::basic::compression::SkippableFrame;
 
 /* Format detection enum */
-/// `Format` represent a compression format of a file. Currently Gzip, Bzip, Lzma or No are supported.
+/// `Format` represent a compression format of a file, [No](Format::No) for uncompressed files.
+///
+/// Formats are read and written when the Cargo feature they need is enabled, more formats
+/// may be added in minor versions.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[non_exhaustive]
 pub enum Format {
     Gzip,
     Bzip,
     Lzma,
+    /// Legacy `.lzma` files, as written by LZMA Utils or the LZMA SDK. They have no magic
+    /// number, [sniff](crate::send::sniff) never returns this format. It's only used when
+    /// requested explicitly or for paths ending with `.lzma`.
+    LzmaAlone,
     Zstd,
+    Lz4,
+    /// Brotli streams have no magic number, [sniff](crate::send::sniff) never returns this format.
+    /// It's only used when requested explicitly or for paths ending with `.br`.
+    Brotli,
+    /// Snappy framing format, each chunk checksum is verified on read
+    Snappy,
+    /// lzip files, made of one or more LZMA members
+    Lzip,
+    /// Unix `compress` files (`.Z`), using LZW
+    Compress,
+    /// lzop files (`.lzo`), made of blocks compressed with LZO1X
+    Lzo,
+    /// zlib streams, a deflate stream with a 2 bytes header and an Adler-32 checksum.
+    /// The header matches some text, [sniff](crate::send::sniff) never returns this format.
+    /// It's only used when requested explicitly or for paths ending with `.zz` or `.zlib`.
+    Zlib,
+    /// Raw deflate streams have no header, [sniff](crate::send::sniff) never returns this format.
+    /// It's only used when requested explicitly.
+    Deflate,
+    /// Zip archives, only the entry of an archive with a single file is read,
+    /// [get_entry_reader](crate::send::get_entry_reader) reads an entry chosen by name.
+    /// Written archives hold one deflated entry, named `-`, or like the file
+    /// without its extension with [to_path](crate::send::to_path).
+    Zip,
     No,
+    /// Format of a [Codec](crate::send::codec::Codec) registered at runtime, identified by its name.
+    Custom(&'static str),
 }
 
 impl Format {
     /// An alias for Lzma, Format::Lzma == Format::Xz
     #[allow(non_upper_case_globals)]
     pub const Xz: Format = Format::Lzma;
-}
 
-pub(crate) fn bytes2type(bytes: [u8; 5]) -> Format {
-    match bytes {
-        [0x1f, 0x8b, ..] => Format::Gzip,
-        [0x42, 0x5a, ..] => Format::Bzip,
-        [0xfd, 0x37, 0x7a, 0x58, 0x5a] => Format::Lzma,
-        [0x28, 0xb5, 0x2f, 0xfd, ..] => Format::Zstd,
-        _ => Format::No,
+    /// Find out the compression format from the first bytes of a stream, without allocation.
+    ///
+    /// Return `None` if more bytes are needed to decide, 16 bytes are always enough. Formats
+    /// of [registered codecs](crate::send::codec::register) are also detected.
+    ///
+    /// # Example
+    /// ```
+    /// use niffler::send::compression::Format;
+    ///
+    /// assert_eq!(Format::detect(&[0x1f, 0x8b, 0x08, 0x00]), Some(Format::Gzip));
+    /// assert_eq!(Format::detect(&[0x1f]), None);
+    /// assert_eq!(Format::detect(b"Not compressed at all"), Some(Format::No));
+    /// ```
+    pub fn detect(bytes: &[u8]) -> Option<Format> {
+        let format = Format::from_basic(crate::basic::codec::detect(bytes));
+
+        let decided = bytes.len() >= crate::utils::MAGIC_LEN
+            || (format != Format::No && !is_before_next_frame(bytes));
+        decided.then_some(format)
     }
-}
-
-cfg_if! {
-    if #[cfg(feature = "gz")] {
-        pub(crate) fn new_gz_encoder<'a>(out: Box<dyn io::Write + Send + 'a>, level: Level) -> Result<Box<dyn io::Write + Send + 'a>, Error> {
-            Ok(Box::new(flate2::write::GzEncoder::new(
-        out,
-        level.into(),
-            )))
-        }
-
-        pub(crate) fn new_gz_decoder<'a>(
-            inp: Box<dyn io::Read + Send + 'a>,
-        ) -> Result<(Box<dyn io::Read + Send + 'a>, Format), Error> {
-            Ok((
-        Box::new(flate2::read::MultiGzDecoder::new(inp)),
-        Format::Gzip,
-            ))
-        }
-    } else {
-        pub(crate) fn new_gz_encoder<'a>(_: Box<dyn io::Write + Send + 'a>, _: Level) -> Result<Box<dyn io::Write + Send + 'a>, Error> {
-            Err(Error::FeatureDisabled)
-        }
-
-        pub(crate) fn new_gz_decoder<'a>(_: Box<dyn io::Read + Send + 'a>) -> Result<(Box<dyn io::Read + Send + 'a>, Format), Error> {
-            Err(Error::FeatureDisabled)
-        }
-    }
-}
 
-cfg_if! {
-    if #[cfg(feature = "bz2")] {
-        pub(crate) fn new_bz2_encoder<'a>(out: Box<dyn io::Write + Send + 'a>, level: Level) -> Result<Box<dyn io::Write + Send + 'a>, Error> {
-            Ok(Box::new(bzip2::write::BzEncoder::new(
-                out,
-                level.into(),
-            )))
+    /// Compression format usually given by a file extension, with or without the leading dot.
+    ///
+    /// Compound extensions like `tar.gz` or `fq.gz` give the format of their last part, and
+    /// `tgz` like extensions are supported. BGZF files are gzip files, `bgz` gives
+    /// [Gzip](Format::Gzip). Return `None` for extensions of uncompressed files.
+    ///
+    /// # Example
+    /// ```
+    /// use niffler::send::compression::Format;
+    ///
+    /// assert_eq!(Format::from_extension("zst"), Some(Format::Zstd));
+    /// assert_eq!(Format::from_extension(".tar.gz"), Some(Format::Gzip));
+    /// assert_eq!(Format::from_extension("fastq"), None);
+    /// ```
+    pub fn from_extension(extension: &str) -> Option<Format> {
+        let extension = extension.rsplit('.').next().unwrap_or_default();
+
+        // `.Z` is compress, `.z` was used by pack and early gzip versions
+        if extension == "Z" {
+            return Some(Format::Compress);
         }
 
-        pub(crate) fn new_bz2_decoder<'a>(
-            inp: Box<dyn io::Read + Send + 'a>,
-        ) -> Result<(Box<dyn io::Read + Send + 'a>, Format), Error> {
-            Ok((
-                Box::new(bzip2::read::MultiBzDecoder::new(inp)),
-                Format::Bzip,
-            ))
-        }
-    } else {
-        pub(crate) fn new_bz2_encoder<'a>(_: Box<dyn io::Write + Send + 'a>, _: Level) -> Result<Box<dyn io::Write + Send + 'a>, Error> {
-            Err(Error::FeatureDisabled)
-        }
-
-        pub(crate) fn new_bz2_decoder<'a>(_: Box<dyn io::Read + Send + 'a>) -> Result<(Box<dyn io::Read + Send + 'a>, Format), Error> {
-            Err(Error::FeatureDisabled)
+        match extension.to_ascii_lowercase().as_str() {
+            "gz" | "gzip" | "bgz" | "tgz" => Some(Format::Gzip),
+            "bz2" | "bz" | "bzip2" | "tbz" | "tbz2" => Some(Format::Bzip),
+            "xz" | "txz" => Some(Format::Lzma),
+            "lzma" | "tlz" => Some(Format::LzmaAlone),
+            "zst" | "zstd" | "tzst" => Some(Format::Zstd),
+            "lz4" => Some(Format::Lz4),
+            "br" => Some(Format::Brotli),
+            "sz" => Some(Format::Snappy),
+            "lz" => Some(Format::Lzip),
+            "lzo" => Some(Format::Lzo),
+            "zz" | "zlib" => Some(Format::Zlib),
+            "zip" => Some(Format::Zip),
+            _ => None,
         }
     }
-}
 
-cfg_if! {
-    if #[cfg(feature = "lzma")] {
-    pub(crate) fn new_lzma_encoder<'a>(out: Box<dyn io::Write + Send + 'a>, level: Level) -> Result<Box<dyn io::Write + Send + 'a>, Error> {
-            Ok(Box::new(liblzma::write::XzEncoder::new(out, level.into())))
+    /// Compression format given by the extension of a path, see [from_extension](Format::from_extension).
+    ///
+    /// It's used by [to_path_auto](crate::send::to_path_auto) to choose the format of a new file.
+    pub fn from_path<P: AsRef<std::path::Path>>(path: P) -> Option<Format> {
+        path.as_ref()
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(Format::from_extension)
     }
 
-    pub(crate) fn new_lzma_decoder<'a>(
-            inp: Box<dyn io::Read + Send + 'a>,
-    ) -> Result<(Box<dyn io::Read + Send + 'a>, Format), Error> {
-            Ok((
-        Box::new(liblzma::read::XzDecoder::new(inp)),
-        Format::Lzma,
-            ))
-    }
-    } else {
-    pub(crate) fn new_lzma_encoder<'a>(_: Box<dyn io::Write + Send + 'a>, _: Level) -> Result<Box<dyn io::Write + Send + 'a>, Error> {
-            Err(Error::FeatureDisabled)
+    /// The same format in [basic](crate::basic), to build decoders of [generic](crate::generic).
+    pub(crate) fn to_basic(self) -> crate::basic::compression::Format {
+        use crate::basic::compression::Format as Basic;
+
+        match self {
+            Format::Gzip => Basic::Gzip,
+            Format::Bzip => Basic::Bzip,
+            Format::Lzma => Basic::Lzma,
+            Format::LzmaAlone => Basic::LzmaAlone,
+            Format::Zstd => Basic::Zstd,
+            Format::Lz4 => Basic::Lz4,
+            Format::Brotli => Basic::Brotli,
+            Format::Snappy => Basic::Snappy,
+            Format::Lzip => Basic::Lzip,
+            Format::Compress => Basic::Compress,
+            Format::Lzo => Basic::Lzo,
+            Format::Zlib => Basic::Zlib,
+            Format::Deflate => Basic::Deflate,
+            Format::Zip => Basic::Zip,
+            Format::No => Basic::No,
+            Format::Custom(name) => Basic::Custom(name),
+        }
     }
 
-    pub(crate) fn new_lzma_decoder<'a>(_: Box<dyn io::Read + Send + 'a>) -> Result<(Box<dyn io::Read + Send + 'a>, Format), Error> {
-            Err(Error::FeatureDisabled)
-    }
+    /// The same format in [send](crate::send), for the formats of [basic](crate::basic) detection.
+    pub(crate) fn from_basic(format: crate::basic::compression::Format) -> Self {
+        use crate::basic::compression::Format as Basic;
+
+        match format {
+            Basic::Gzip => Format::Gzip,
+            Basic::Bzip => Format::Bzip,
+            Basic::Lzma => Format::Lzma,
+            Basic::LzmaAlone => Format::LzmaAlone,
+            Basic::Zstd => Format::Zstd,
+            Basic::Lz4 => Format::Lz4,
+            Basic::Brotli => Format::Brotli,
+            Basic::Snappy => Format::Snappy,
+            Basic::Lzip => Format::Lzip,
+            Basic::Compress => Format::Compress,
+            Basic::Lzo => Format::Lzo,
+            Basic::Zlib => Format::Zlib,
+            Basic::Deflate => Format::Deflate,
+            Basic::Zip => Format::Zip,
+            Basic::No => Format::No,
+            Basic::Custom(name) => Format::Custom(name),
+        }
     }
 }
 
-cfg_if! {
-    if #[cfg(feature = "zstd")] {
-        pub(crate) fn new_zstd_encoder<'a>(out: Box<dyn io::Write + Send + 'a>, level: Level) -> Result<Box<dyn io::Write +Send + 'a>, Error> {
-            Ok(Box::new(zstd::stream::write::Encoder::new(
-                        out,
-                        level.into(),
-            )?.auto_finish()))
-        }
-
-        pub(crate) fn new_zstd_decoder<'a>(
-            inp: Box<dyn io::Read +Send + 'a>,
-        ) -> Result<(Box<dyn io::Read + Send + 'a>, Format), Error> {
-            Ok((Box::new(zstd::stream::read::Decoder::new(inp)?),
-                         Format::Zstd,
-            ))
-        }
-    } else {
-        pub(crate) fn new_zstd_encoder<'a>(_: Box<dyn io::Write +Send + 'a>, _: Level) -> Result<Box<dyn io::Write+Send  + 'a>, Error> {
-            Err(Error::FeatureDisabled)
-        }
-
-        pub(crate) fn new_zstd_decoder<'a>(_: Box<dyn io::Read +Send + 'a>) -> Result<(Box<dyn io::Read+Send  + 'a>, Format), Error> {
-            Err(Error::FeatureDisabled)
-        }
-    }
+/// The extension of a file doesn't match its compression format, returned by
+/// [from_path_checked](crate::send::from_path_checked).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExtensionMismatch {
+    /// Format given by the extension, [No](Format::No) for extensions of uncompressed files
+    pub expected: Format,
+    /// Format detected from the content of the file
+    pub detected: Format,
 }
@@ -1,3 +1,4 @@
+pub mod codec;
 pub mod compression;
 
 /* standard use */
@@ -6,6 +7,9 @@ use std::io::Read;
 use std::path::Path;
 
 /* project use */
+use crate::basic::ExtensionCheck;
+use crate::builder::{ReaderBuilder, WriterBuilder};
+use crate::content::Content;
 use crate::error::Error;
 use crate::level::Level;
 
@@ -13,33 +17,120 @@ use crate::level::Level;
 pub fn sniff<'a>(
     in_stream: Box<dyn io::Read + Send + 'a>,
 ) -> Result<(Box<dyn io::Read + Send + 'a>, compression::Format), Error> {
-    let (first_bytes, in_stream) = crate::utils::get_first_five(in_stream)?;
-
-    let cursor = io::Cursor::new(first_bytes);
-    match compression::bytes2type(first_bytes) {
-        e @ compression::Format::Gzip
-        | e @ compression::Format::Bzip
-        | e @ compression::Format::Lzma
-        | e @ compression::Format::Zstd => Ok((Box::new(cursor.chain(in_stream)), e)),
-        _ => Ok((Box::new(cursor.chain(in_stream)), compression::Format::No)),
+    let (first_bytes, in_stream) = crate::utils::get_first_bytes_read(in_stream)?;
+
+    let format = compression::Format::from_basic(crate::basic::codec::detect(&first_bytes));
+
+    Ok((
+        Box::new(io::Cursor::new(first_bytes).chain(in_stream)),
+        format,
+    ))
+}
+
+/// Similar to [sniff_bufread](crate::sniff_bufread) but with the formats of sendable streams
+pub fn sniff_bufread<R: io::BufRead>(
+    in_stream: R,
+) -> Result<(crate::Sniffed<R>, compression::Format), Error> {
+    let mut in_stream = crate::Sniffed::new(in_stream);
+    in_stream.peek(|bytes| compression::Format::detect(bytes).is_some())?;
+
+    let first_bytes = io::BufRead::fill_buf(&mut in_stream)?;
+    if first_bytes.len() < 5 {
+        return Err(Error::FileTooShort);
     }
+
+    // short streams are decided with all their bytes
+    let format = compression::Format::detect(first_bytes).unwrap_or_else(|| {
+        compression::Format::from_basic(crate::basic::codec::detect(first_bytes))
+    });
+
+    Ok((in_stream, format))
 }
 
 /// Similar to [get_reader](crate::get_reader) but readable stream is now sendable
 pub fn get_reader<'a>(
     in_stream: Box<dyn io::Read + Send + 'a>,
 ) -> Result<(Box<dyn io::Read + Send + 'a>, compression::Format), Error> {
-    // check compression
-    let (in_stream, compression) = sniff(in_stream)?;
-
-    // return readable and compression status
-    match compression {
-        compression::Format::Gzip => compression::new_gz_decoder(in_stream),
-        compression::Format::Bzip => compression::new_bz2_decoder(in_stream),
-        compression::Format::Lzma => compression::new_lzma_decoder(in_stream),
-        compression::Format::Zstd => compression::new_zstd_decoder(in_stream),
-        compression::Format::No => Ok((in_stream, compression::Format::No)),
+    read_with(&ReaderBuilder::new(), in_stream)
+}
+
+/// Similar to [get_reader_recursive](crate::get_reader_recursive) but readable stream is now sendable
+pub fn get_reader_recursive<'a>(
+    in_stream: Box<dyn io::Read + Send + 'a>,
+    max_depth: usize,
+) -> Result<(Box<dyn io::Read + Send + 'a>, Vec<compression::Format>), Error> {
+    let (mut reader, mut format) = sniff(in_stream)?;
+    let mut formats = vec![];
+
+    while format != compression::Format::No && formats.len() < max_depth {
+        (reader, format) = get_reader_with_format(reader, format)?;
+        formats.push(format);
+
+        // decompressed data can be shorter than a magic number
+        let (first_bytes, inner) = crate::utils::read_first_bytes(reader)?;
+        format = compression::Format::from_basic(crate::basic::codec::detect(&first_bytes));
+        reader = Box::new(io::Cursor::new(first_bytes).chain(inner));
+    }
+
+    Ok((reader, formats))
+}
+
+/// Similar to [get_reader_with_format](crate::get_reader_with_format) but readable stream is now sendable
+pub fn get_reader_with_format<'a>(
+    in_stream: Box<dyn io::Read + Send + 'a>,
+    format: compression::Format,
+) -> Result<(Box<dyn io::Read + Send + 'a>, compression::Format), Error> {
+    read_with(&ReaderBuilder::new().format(format.to_basic()), in_stream)
+}
+
+/// Similar to [get_entry_reader](crate::get_entry_reader) but readable stream is now sendable
+#[cfg_attr(not(any(feature = "zip", feature = "tar")), allow(unused_variables))]
+pub fn get_entry_reader<'a>(
+    in_stream: Box<dyn io::Read + Send + 'a>,
+    entry: &str,
+) -> Result<(Box<dyn io::Read + Send + 'a>, compression::Format), Error> {
+    let (in_stream, format) = sniff(in_stream)?;
+
+    #[cfg(feature = "zip")]
+    if format == compression::Format::Zip {
+        let reader = crate::formats::zip::ZipDecoder::new(in_stream, Some(entry))?;
+        return Ok((Box::new(reader), format));
     }
+    #[cfg(not(feature = "zip"))]
+    if format == compression::Format::Zip {
+        return Err(Error::feature_disabled(format.to_basic()));
+    }
+
+    // other archives are tar archives, possibly compressed
+    #[cfg(feature = "tar")]
+    {
+        let (reader, format) = get_reader_with_format(in_stream, format)?;
+        let tar = crate::tar::check_tar(reader)?;
+
+        Ok((Box::new(crate::tar::find_entry(tar, entry)?), format))
+    }
+
+    #[cfg(not(feature = "tar"))]
+    Err(Error::NotAnArchive)
+}
+
+/// Similar to [read_skippable_frames](crate::read_skippable_frames) but readable stream is now sendable
+pub fn read_skippable_frames<'a>(
+    in_stream: Box<dyn io::Read + Send + 'a>,
+) -> Result<
+    (
+        Vec<compression::SkippableFrame>,
+        Box<dyn io::Read + Send + 'a>,
+    ),
+    Error,
+> {
+    let (frames, first_bytes, in_stream) =
+        crate::basic::compression::read_skippable_frames(in_stream)?;
+
+    Ok((
+        frames,
+        Box::new(io::Cursor::new(first_bytes).chain(in_stream)),
+    ))
 }
 
 /// Similar to [get_writer](crate::get_writer) but writable stream is now sendable
@@ -48,21 +139,120 @@ pub fn get_writer<'a>(
     format: compression::Format,
     level: Level,
 ) -> Result<Box<dyn io::Write + Send + 'a>, Error> {
-    match format {
-        compression::Format::Gzip => compression::new_gz_encoder(out_stream, level),
-        compression::Format::Bzip => compression::new_bz2_encoder(out_stream, level),
-        compression::Format::Lzma => compression::new_lzma_encoder(out_stream, level),
-        compression::Format::Zstd => compression::new_zstd_encoder(out_stream, level),
-        compression::Format::No => Ok(Box::new(out_stream)),
-    }
+    let writer = WriterBuilder::new(format.to_basic())
+        .level(level)
+        .encoder(out_stream)?;
+
+    Ok(Box::new(writer))
 }
 
 /// Similar to [from_path](crate::from_path) but readable stream is now sendable
 pub fn from_path<'a, P: AsRef<Path>>(
     path: P,
 ) -> Result<(Box<dyn io::Read + Send + 'a>, compression::Format), Error> {
-    let readable = io::BufReader::new(std::fs::File::open(path)?);
-    get_reader(Box::new(readable))
+    let (readable, builder) = ReaderBuilder::new().open_path(path.as_ref())?;
+
+    read_with(&builder, Box::new(readable))
+}
+
+/// Similar to [get_bufread](crate::get_bufread) but buffered stream is now sendable
+pub fn get_bufread<'a>(
+    in_stream: Box<dyn io::Read + Send + 'a>,
+    capacity: usize,
+) -> Result<(Box<dyn io::BufRead + Send + 'a>, compression::Format), Error> {
+    let (reader, format) = get_reader(in_stream)?;
+
+    Ok((
+        Box::new(io::BufReader::with_capacity(capacity, reader)),
+        format,
+    ))
+}
+
+/// Similar to [from_path_bufread](crate::from_path_bufread) but buffered stream is now sendable
+pub fn from_path_bufread<'a, P: AsRef<Path>>(
+    path: P,
+    capacity: usize,
+) -> Result<(Box<dyn io::BufRead + Send + 'a>, compression::Format), Error> {
+    let builder = ReaderBuilder::new().output_buffer_size(capacity);
+
+    match builder.open_path_bufread(path.as_ref())? {
+        (readable, None) => Ok((Box::new(readable), compression::Format::No)),
+        (readable, Some(builder)) => {
+            let (reader, format) = read_with(&builder, Box::new(readable))?;
+
+            Ok((
+                Box::new(io::BufReader::with_capacity(capacity, reader)),
+                format,
+            ))
+        }
+    }
+}
+
+/// Decompress a sendable stream with the options of `builder`.
+fn read_with<'a>(
+    builder: &ReaderBuilder,
+    in_stream: Box<dyn io::Read + Send + 'a>,
+) -> Result<(Box<dyn io::Read + Send + 'a>, compression::Format), Error> {
+    let (decoder, format) = builder.boxed_decoder(in_stream)?;
+
+    Ok((Box::new(decoder), compression::Format::from_basic(format)))
+}
+
+/// Stream, compression format and extension mismatch returned by [from_path_checked].
+type CheckedReader<'a> = (
+    Box<dyn io::Read + Send + 'a>,
+    compression::Format,
+    Option<compression::ExtensionMismatch>,
+);
+
+/// Similar to [from_path_checked](crate::from_path_checked) but readable stream is now sendable
+pub fn from_path_checked<'a, P: AsRef<Path>>(
+    path: P,
+    check: ExtensionCheck,
+) -> Result<CheckedReader<'a>, Error> {
+    let path = path.as_ref();
+    let (reader, detected) = from_path(path)?;
+
+    let expected = compression::Format::from_path(path).unwrap_or(compression::Format::No);
+    if expected == detected {
+        return Ok((reader, detected, None));
+    }
+
+    match check {
+        ExtensionCheck::Report => Ok((
+            reader,
+            detected,
+            Some(compression::ExtensionMismatch { expected, detected }),
+        )),
+        ExtensionCheck::Reject => Err(Error::ExtensionMismatch {
+            expected: expected.to_basic(),
+            detected: detected.to_basic(),
+        }),
+    }
+}
+
+/// Similar to [sniff_content](crate::sniff_content) but readable stream is now sendable
+pub fn sniff_content<'a>(
+    in_stream: Box<dyn io::Read + Send + 'a>,
+) -> Result<(Box<dyn io::Read + Send + 'a>, Content), Error> {
+    let (first_bytes, in_stream) =
+        crate::utils::read_up_to(in_stream, crate::content::CONTENT_PEEK_LEN)?;
+    let content = Content::detect(&first_bytes);
+
+    Ok((
+        Box::new(io::Cursor::new(first_bytes).chain(in_stream)),
+        content,
+    ))
+}
+
+/// Similar to [open_any](crate::open_any) but readable stream is now sendable
+pub fn open_any<'a, P: AsRef<Path>>(
+    path: P,
+) -> Result<(Box<dyn io::Read + Send + 'a>, compression::Format, Content), Error> {
+    let (reader, format) = from_path(path)?;
+    let (reader, content) = sniff_content(reader)?;
+
+    Ok((reader, format, content))
 }
 
 /// Similar to [to_path](crate::to_path) but writable stream is now sendable
@@ -71,8 +261,23 @@ pub fn to_path<'a, P: AsRef<Path>>(
     format: compression::Format,
     level: Level,
 ) -> Result<Box<dyn io::Write + Send + 'a>, Error> {
-    let writable = io::BufWriter::new(std::fs::File::create(path)?);
-    get_writer(Box::new(writable), format, level)
+    let (writable, builder) = WriterBuilder::new(format.to_basic())
+        .level(level)
+        .create(path.as_ref())?;
+
+    let out_stream: Box<dyn io::Write + Send + 'a> = Box::new(writable);
+
+    Ok(Box::new(builder.encoder(out_stream)?))
+}
+
+/// Similar to [to_path_auto](crate::to_path_auto) but writable stream is now sendable
+pub fn to_path_auto<'a, P: AsRef<Path>>(
+    path: P,
+    level: Level,
+) -> Result<Box<dyn io::Write + Send + 'a>, Error> {
+    let format = compression::Format::from_path(&path).unwrap_or(compression::Format::No);
+
+    to_path(path, format, level)
 }
 
 #[cfg(test)]
@@ -84,8 +289,43 @@ mod test {
     pub(crate) const GZIP_FILE: &'static [u8] = &[0o037, 0o213, 0o0, 0o0, 0o0];
     pub(crate) const BZIP_FILE: &'static [u8] = &[0o102, 0o132, 0o0, 0o0, 0o0];
     pub(crate) const LZMA_FILE: &'static [u8] = &[0o375, 0o067, 0o172, 0o130, 0o132];
+    pub(crate) const LZMA_ALONE_FILE: &[u8] = &[
+        0x5d, 0x00, 0x00, 0x80, 0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00,
+    ];
     pub(crate) const LOREM_IPSUM: &'static [u8] = b"Lorem ipsum dolor sit amet, consectetur adipiscing elit. Ut ultricies scelerisque diam, a scelerisque enim sagittis at.";
     pub(crate) const ZSTD_FILE: &'static [u8] = &[0x28, 0xb5, 0x2f, 0xfd, 0];
+    pub(crate) const LZ4_FILE: &[u8] = &[0x04, 0x22, 0x4d, 0x18, 0];
+    pub(crate) const COMPRESS_FILE: &[u8] = &[0x1f, 0x9d, 0x90, 0x61, 0x00];
+    pub(crate) const ZLIB_FILE: &[u8] = &[0x78, 0x9c, 0xf3, 0x54, 0xcf, 0x55, 0x48];
+    pub(crate) const ZIP_FILE: &[u8] = &[0x50, 0x4b, 0x03, 0x04, 0x14, 0x00];
+    pub(crate) const ZSTD_SKIPPABLE_FILE: &[u8] = &[
+        0x50, 0x2a, 0x4d, 0x18, 0x04, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x28, 0xb5, 0x2f,
+        0xfd,
+    ];
+    pub(crate) const LZ4_SKIPPABLE_FILE: &[u8] = &[
+        0x5f, 0x2a, 0x4d, 0x18, 0x00, 0x00, 0x00, 0x00, 0x04, 0x22, 0x4d, 0x18, 0x00,
+    ];
+    pub(crate) const ZSTD_LEGACY_FILE: &[u8] = &[
+        0x27, 0xb5, 0x2f, 0xfd, 0x00, 0x00, 0x40, 0x00, 0x1a, 0x49, 0x27, 0x6d, 0x20, 0x63, 0x6f,
+        0x6d, 0x70, 0x72, 0x65, 0x73, 0x73, 0x20, 0x69, 0x6e, 0x20, 0x7a, 0x73, 0x74, 0x64, 0x20,
+        0x76, 0x30, 0x2e, 0x37, 0x0a, 0xc0, 0x00, 0x00,
+    ];
+    pub(crate) const LZO_FILE: &[u8] = &[
+        0x89, 0x4c, 0x5a, 0x4f, 0x00, 0x0d, 0x0a, 0x1a, 0x0a, 0x10, 0x30, 0x20, 0x80, 0x09, 0x40,
+        0x01, 0x03, 0x03, 0x00, 0x00, 0x03, 0x00, 0x00, 0x81, 0xa4, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x26, 0x5f, 0x02, 0x59, 0x00, 0x00, 0x00, 0x25, 0x00, 0x00, 0x00,
+        0x1c, 0xf7, 0x2b, 0x0d, 0x04, 0x80, 0x7f, 0x07, 0xb1, 0x25, 0x49, 0x27, 0x6d, 0x20, 0x63,
+        0x6f, 0x6d, 0x70, 0x72, 0x65, 0x73, 0x73, 0x20, 0x69, 0x6e, 0x20, 0x6c, 0x7a, 0x6f, 0x2c,
+        0x2e, 0x41, 0x00, 0x0a, 0x11, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+    pub(crate) const LZIP_FILE: &[u8] = &[0x4c, 0x5a, 0x49, 0x50, 0x01, 0x0c];
+    pub(crate) const SNAPPY_FILE: &[u8] =
+        &[0xff, 0x06, 0x00, 0x00, 0x73, 0x4e, 0x61, 0x50, 0x70, 0x59];
+    pub(crate) const LZ4_LEGACY_FILE: &[u8] = &[
+        0x02, 0x21, 0x4c, 0x18, 0x1d, 0x00, 0x00, 0x00, 0xf0, 0x0c, 0x49, 0x27, 0x6d, 0x20, 0x63,
+        0x6f, 0x6d, 0x70, 0x72, 0x65, 0x73, 0x73, 0x20, 0x69, 0x6e, 0x20, 0x6c, 0x7a, 0x34, 0x20,
+        0x6c, 0x65, 0x67, 0x61, 0x63, 0x79, 0x0a,
+    ];
 
     mod compress_uncompress {
         use super::*;
@@ -196,6 +436,12 @@ mod test {
                 get_reader(Box::new(&LZMA_FILE[..])).is_err(),
                 "lzma disabled, this assertion should fail"
             );
+
+            assert!(
+                get_reader_with_format(Box::new(LZMA_ALONE_FILE), compression::Format::LzmaAlone)
+                    .is_err(),
+                "lzma disabled, this assertion should fail"
+            );
         }
 
         #[cfg(feature = "lzma")]
@@ -225,6 +471,39 @@ mod test {
             assert_eq!(LOREM_IPSUM, buffer.as_slice());
         }
 
+        #[cfg(feature = "lzma")]
+        #[test]
+        fn lzma_alone() {
+            let ofile = NamedTempFile::new().expect("Can't create tmpfile");
+
+            {
+                let wfile = ofile.reopen().expect("Can't create tmpfile");
+                let mut writer =
+                    get_writer(Box::new(wfile), compression::Format::LzmaAlone, Level::Six)
+                        .unwrap();
+                writer
+                    .write_all(LOREM_IPSUM)
+                    .expect("Error during write of data");
+            }
+
+            let rfile = ofile.reopen().expect("Can't create tmpfile");
+            let (_, compression) = sniff(Box::new(rfile)).expect("Error reading from tmpfile");
+            assert_eq!(compression, compression::Format::No);
+
+            let rfile = ofile.reopen().expect("Can't create tmpfile");
+            let (mut reader, compression) =
+                get_reader_with_format(Box::new(rfile), compression::Format::LzmaAlone)
+                    .expect("Error reading from tmpfile");
+
+            assert_eq!(compression, compression::Format::LzmaAlone);
+
+            let mut buffer = Vec::new();
+            reader
+                .read_to_end(&mut buffer)
+                .expect("Error during reading");
+            assert_eq!(LOREM_IPSUM, buffer.as_slice());
+        }
+
         #[test]
         #[cfg(not(feature = "zstd"))]
         fn no_zstd_feature() {
@@ -265,45 +544,1307 @@ mod test {
                 .expect("Error during reading");
             assert_eq!(LOREM_IPSUM, buffer.as_slice());
         }
-    }
 
-    mod compression_format_detection {
-        use super::*;
+        #[test]
+        #[cfg(not(feature = "lz4"))]
+        fn no_lz4_feature() {
+            assert!(
+                get_writer(Box::new(vec![]), compression::Format::Lz4, Level::Six).is_err(),
+                "lz4 disabled, this assertion should fail"
+            );
 
+            assert!(
+                get_reader(Box::new(LZ4_FILE)).is_err(),
+                "lz4 disabled, this assertion should fail"
+            );
+        }
+
+        #[cfg(feature = "lz4")]
         #[test]
-        fn gzip() {
-            let (_, compression) = sniff(Box::new(GZIP_FILE)).expect("Error in read file");
-            assert_eq!(compression, compression::Format::Gzip);
+        fn lz4() {
+            let ofile = NamedTempFile::new().expect("Can't create tmpfile");
+
+            {
+                let wfile = ofile.reopen().expect("Can't create tmpfile");
+                let mut writer =
+                    get_writer(Box::new(wfile), compression::Format::Lz4, Level::Six).unwrap();
+                writer
+                    .write_all(LOREM_IPSUM)
+                    .expect("Error during write of data");
+            }
+
+            let rfile = ofile.reopen().expect("Can't create tmpfile");
+            let (mut reader, compression) =
+                get_reader(Box::new(rfile)).expect("Error reading from tmpfile");
+
+            assert_eq!(compression, compression::Format::Lz4);
+
+            let mut buffer = Vec::new();
+            reader
+                .read_to_end(&mut buffer)
+                .expect("Error during reading");
+            assert_eq!(LOREM_IPSUM, buffer.as_slice());
         }
 
         #[test]
-        fn bzip() {
-            let (_, compression) = sniff(Box::new(BZIP_FILE)).expect("Error in read file");
-            assert_eq!(compression, compression::Format::Bzip);
+        #[cfg(not(feature = "brotli"))]
+        fn no_brotli_feature() {
+            assert!(
+                get_writer(Box::new(vec![]), compression::Format::Brotli, Level::Six).is_err(),
+                "brotli disabled, this assertion should fail"
+            );
+
+            assert!(
+                get_reader_with_format(Box::new(LOREM_IPSUM), compression::Format::Brotli).is_err(),
+                "brotli disabled, this assertion should fail"
+            );
         }
 
+        #[cfg(feature = "brotli")]
         #[test]
-        fn lzma() {
-            let (_, compression) = sniff(Box::new(LZMA_FILE)).expect("Error in read file");
-            assert_eq!(compression, compression::Format::Lzma);
+        fn brotli() {
+            let ofile = NamedTempFile::new().expect("Can't create tmpfile");
+
+            {
+                let wfile = ofile.reopen().expect("Can't create tmpfile");
+                let mut writer =
+                    get_writer(Box::new(wfile), compression::Format::Brotli, Level::Six).unwrap();
+                writer
+                    .write_all(LOREM_IPSUM)
+                    .expect("Error during write of data");
+            }
+
+            let rfile = ofile.reopen().expect("Can't create tmpfile");
+            let (mut reader, compression) =
+                get_reader_with_format(Box::new(rfile), compression::Format::Brotli)
+                    .expect("Error reading from tmpfile");
+
+            assert_eq!(compression, compression::Format::Brotli);
+
+            let mut buffer = Vec::new();
+            reader
+                .read_to_end(&mut buffer)
+                .expect("Error during reading");
+            assert_eq!(LOREM_IPSUM, buffer.as_slice());
         }
 
         #[test]
-        fn zstd() {
-            let (_, compression) = sniff(Box::new(ZSTD_FILE)).expect("Error in read file");
-            assert_eq!(compression, compression::Format::Zstd);
+        #[cfg(not(feature = "snappy"))]
+        fn no_snappy_feature() {
+            assert!(
+                get_writer(Box::new(vec![]), compression::Format::Snappy, Level::Six).is_err(),
+                "snappy disabled, this assertion should fail"
+            );
+
+            assert!(
+                get_reader(Box::new(SNAPPY_FILE)).is_err(),
+                "snappy disabled, this assertion should fail"
+            );
         }
 
+        #[cfg(feature = "snappy")]
         #[test]
-        fn too_short() {
-            let result = sniff(Box::new(SHORT_FILE));
-            assert!(result.is_err());
+        fn snappy() {
+            let ofile = NamedTempFile::new().expect("Can't create tmpfile");
+
+            {
+                let wfile = ofile.reopen().expect("Can't create tmpfile");
+                let mut writer =
+                    get_writer(Box::new(wfile), compression::Format::Snappy, Level::Six).unwrap();
+                writer
+                    .write_all(LOREM_IPSUM)
+                    .expect("Error during write of data");
+            }
+
+            let rfile = ofile.reopen().expect("Can't create tmpfile");
+            let (mut reader, compression) =
+                get_reader(Box::new(rfile)).expect("Error reading from tmpfile");
+
+            assert_eq!(compression, compression::Format::Snappy);
+
+            let mut buffer = Vec::new();
+            reader
+                .read_to_end(&mut buffer)
+                .expect("Error during reading");
+            assert_eq!(LOREM_IPSUM, buffer.as_slice());
         }
 
         #[test]
-        fn no_compression() {
-            let (_, compression) = sniff(Box::new(LOREM_IPSUM)).expect("Error in read file");
-            assert_eq!(compression, compression::Format::No);
+        #[cfg(not(feature = "lzip"))]
+        fn no_lzip_feature() {
+            assert!(
+                get_writer(Box::new(vec![]), compression::Format::Lzip, Level::Six).is_err(),
+                "lzip disabled, this assertion should fail"
+            );
+
+            assert!(
+                get_reader(Box::new(LZIP_FILE)).is_err(),
+                "lzip disabled, this assertion should fail"
+            );
+        }
+
+        #[cfg(feature = "lzip")]
+        #[test]
+        fn lzip() {
+            let ofile = NamedTempFile::new().expect("Can't create tmpfile");
+
+            {
+                let wfile = ofile.reopen().expect("Can't create tmpfile");
+                let mut writer =
+                    get_writer(Box::new(wfile), compression::Format::Lzip, Level::Six).unwrap();
+                writer
+                    .write_all(LOREM_IPSUM)
+                    .expect("Error during write of data");
+            }
+
+            let rfile = ofile.reopen().expect("Can't create tmpfile");
+            let (mut reader, compression) =
+                get_reader(Box::new(rfile)).expect("Error reading from tmpfile");
+
+            assert_eq!(compression, compression::Format::Lzip);
+
+            let mut buffer = Vec::new();
+            reader
+                .read_to_end(&mut buffer)
+                .expect("Error during reading");
+            assert_eq!(LOREM_IPSUM, buffer.as_slice());
+        }
+
+        #[test]
+        #[cfg(not(feature = "compress"))]
+        fn no_compress_feature() {
+            assert!(
+                get_writer(Box::new(vec![]), compression::Format::Compress, Level::Six).is_err(),
+                "compress disabled, this assertion should fail"
+            );
+
+            assert!(
+                get_reader(Box::new(COMPRESS_FILE)).is_err(),
+                "compress disabled, this assertion should fail"
+            );
+        }
+
+        #[cfg(feature = "compress")]
+        #[test]
+        fn compress() {
+            let ofile = NamedTempFile::new().expect("Can't create tmpfile");
+
+            {
+                let wfile = ofile.reopen().expect("Can't create tmpfile");
+                let mut writer =
+                    get_writer(Box::new(wfile), compression::Format::Compress, Level::Six).unwrap();
+                writer
+                    .write_all(LOREM_IPSUM)
+                    .expect("Error during write of data");
+            }
+
+            let rfile = ofile.reopen().expect("Can't create tmpfile");
+            let (mut reader, compression) =
+                get_reader(Box::new(rfile)).expect("Error reading from tmpfile");
+
+            assert_eq!(compression, compression::Format::Compress);
+
+            let mut buffer = Vec::new();
+            reader
+                .read_to_end(&mut buffer)
+                .expect("Error during reading");
+            assert_eq!(LOREM_IPSUM, buffer.as_slice());
+        }
+
+        #[test]
+        #[cfg(not(feature = "lzo"))]
+        fn no_lzo_feature() {
+            assert!(
+                get_writer(Box::new(vec![]), compression::Format::Lzo, Level::Six).is_err(),
+                "lzo disabled, this assertion should fail"
+            );
+
+            assert!(
+                get_reader(Box::new(LZO_FILE)).is_err(),
+                "lzo disabled, this assertion should fail"
+            );
+        }
+
+        #[cfg(feature = "lzo")]
+        #[test]
+        fn lzo() {
+            let ofile = NamedTempFile::new().expect("Can't create tmpfile");
+
+            {
+                let wfile = ofile.reopen().expect("Can't create tmpfile");
+                let mut writer =
+                    get_writer(Box::new(wfile), compression::Format::Lzo, Level::Six).unwrap();
+                writer
+                    .write_all(LOREM_IPSUM)
+                    .expect("Error during write of data");
+            }
+
+            let rfile = ofile.reopen().expect("Can't create tmpfile");
+            let (mut reader, compression) =
+                get_reader(Box::new(rfile)).expect("Error reading from tmpfile");
+
+            assert_eq!(compression, compression::Format::Lzo);
+
+            let mut buffer = Vec::new();
+            reader
+                .read_to_end(&mut buffer)
+                .expect("Error during reading");
+            assert_eq!(LOREM_IPSUM, buffer.as_slice());
+        }
+
+        #[test]
+        #[cfg(not(feature = "gz"))]
+        fn no_zlib_feature() {
+            assert!(
+                get_writer(Box::new(vec![]), compression::Format::Zlib, Level::Six).is_err(),
+                "gz disabled, this assertion should fail"
+            );
+
+            assert!(
+                get_reader_with_format(Box::new(ZLIB_FILE), compression::Format::Zlib).is_err(),
+                "gz disabled, this assertion should fail"
+            );
+
+            assert!(
+                get_writer(Box::new(vec![]), compression::Format::Deflate, Level::Six).is_err(),
+                "gz disabled, this assertion should fail"
+            );
+        }
+
+        #[cfg(feature = "gz")]
+        #[test]
+        fn zlib() {
+            let ofile = NamedTempFile::new().expect("Can't create tmpfile");
+
+            {
+                let wfile = ofile.reopen().expect("Can't create tmpfile");
+                let mut writer =
+                    get_writer(Box::new(wfile), compression::Format::Zlib, Level::Six).unwrap();
+                writer
+                    .write_all(LOREM_IPSUM)
+                    .expect("Error during write of data");
+            }
+
+            let rfile = ofile.reopen().expect("Can't create tmpfile");
+            let (_, compression) = sniff(Box::new(rfile)).expect("Error reading from tmpfile");
+            assert_eq!(compression, compression::Format::No);
+
+            let rfile = ofile.reopen().expect("Can't create tmpfile");
+            let (mut reader, compression) =
+                get_reader_with_format(Box::new(rfile), compression::Format::Zlib)
+                    .expect("Error reading from tmpfile");
+
+            assert_eq!(compression, compression::Format::Zlib);
+
+            let mut buffer = Vec::new();
+            reader
+                .read_to_end(&mut buffer)
+                .expect("Error during reading");
+            assert_eq!(LOREM_IPSUM, buffer.as_slice());
+        }
+
+        #[cfg(feature = "gz")]
+        #[test]
+        fn deflate() {
+            let ofile = NamedTempFile::new().expect("Can't create tmpfile");
+
+            {
+                let wfile = ofile.reopen().expect("Can't create tmpfile");
+                let mut writer =
+                    get_writer(Box::new(wfile), compression::Format::Deflate, Level::Six).unwrap();
+                writer
+                    .write_all(LOREM_IPSUM)
+                    .expect("Error during write of data");
+            }
+
+            let rfile = ofile.reopen().expect("Can't create tmpfile");
+            let (mut reader, compression) =
+                get_reader_with_format(Box::new(rfile), compression::Format::Deflate)
+                    .expect("Error reading from tmpfile");
+
+            assert_eq!(compression, compression::Format::Deflate);
+
+            let mut buffer = Vec::new();
+            reader
+                .read_to_end(&mut buffer)
+                .expect("Error during reading");
+            assert_eq!(LOREM_IPSUM, buffer.as_slice());
+        }
+
+        #[test]
+        #[cfg(not(feature = "zip"))]
+        fn no_zip_feature() {
+            assert!(
+                get_writer(Box::new(vec![]), compression::Format::Zip, Level::Six).is_err(),
+                "zip disabled, this assertion should fail"
+            );
+
+            assert!(
+                get_reader(Box::new(ZIP_FILE)).is_err(),
+                "zip disabled, this assertion should fail"
+            );
+        }
+
+        #[cfg(feature = "zip")]
+        #[test]
+        fn zip() {
+            let ofile = NamedTempFile::new().expect("Can't create tmpfile");
+
+            {
+                let wfile = ofile.reopen().expect("Can't create tmpfile");
+                let mut writer =
+                    get_writer(Box::new(wfile), compression::Format::Zip, Level::Six).unwrap();
+                writer
+                    .write_all(LOREM_IPSUM)
+                    .expect("Error during write of data");
+            }
+
+            let rfile = ofile.reopen().expect("Can't create tmpfile");
+            let (mut reader, compression) =
+                get_reader(Box::new(rfile)).expect("Error reading from tmpfile");
+
+            assert_eq!(compression, compression::Format::Zip);
+
+            let mut buffer = Vec::new();
+            reader
+                .read_to_end(&mut buffer)
+                .expect("Error during reading");
+            assert_eq!(LOREM_IPSUM, buffer.as_slice());
+        }
+
+        #[cfg(all(feature = "gz", feature = "bz2"))]
+        #[test]
+        fn recursive() {
+            let mut buffer = vec![];
+            {
+                let inner =
+                    get_writer(Box::new(&mut buffer), compression::Format::Bzip, Level::One)
+                        .unwrap();
+                let mut writer = get_writer(inner, compression::Format::Gzip, Level::One).unwrap();
+                writer
+                    .write_all(LOREM_IPSUM)
+                    .expect("Error during write of data");
+            }
+
+            let (mut reader, formats) = get_reader_recursive(Box::new(io::Cursor::new(buffer)), 8)
+                .expect("Error reading from buffer");
+            assert_eq!(
+                formats,
+                [compression::Format::Bzip, compression::Format::Gzip]
+            );
+
+            let mut contents = Vec::new();
+            reader
+                .read_to_end(&mut contents)
+                .expect("Error during reading");
+            assert_eq!(LOREM_IPSUM, contents.as_slice());
+        }
+
+        #[test]
+        fn skippable_frames() {
+            let (frames, mut stream) = read_skippable_frames(Box::new(ZSTD_SKIPPABLE_FILE))
+                .expect("Error reading from buffer");
+            assert_eq!(frames.len(), 1);
+            assert_eq!(frames[0].data, [0x10, 0x00, 0x00, 0x00]);
+
+            let mut rest = vec![];
+            stream.read_to_end(&mut rest).expect("Error during reading");
+            assert_eq!(rest, [0x28, 0xb5, 0x2f, 0xfd]);
+        }
+
+        #[cfg(feature = "zstd-legacy")]
+        #[test]
+        fn zstd_legacy() {
+            let (mut reader, compression) =
+                get_reader(Box::new(ZSTD_LEGACY_FILE)).expect("Error reading from buffer");
+
+            assert_eq!(compression, compression::Format::Zstd);
+
+            let mut buffer = Vec::new();
+            reader
+                .read_to_end(&mut buffer)
+                .expect("Error during reading");
+            assert_eq!(b"I'm compress in zstd v0.7\n", buffer.as_slice());
+        }
+    }
+
+    mod compression_format_detection {
+        use super::*;
+
+        #[test]
+        fn gzip() {
+            let (_, compression) = sniff(Box::new(GZIP_FILE)).expect("Error in read file");
+            assert_eq!(compression, compression::Format::Gzip);
+        }
+
+        #[test]
+        fn bzip() {
+            let (_, compression) = sniff(Box::new(BZIP_FILE)).expect("Error in read file");
+            assert_eq!(compression, compression::Format::Bzip);
+        }
+
+        #[test]
+        fn lzma() {
+            let (_, compression) = sniff(Box::new(LZMA_FILE)).expect("Error in read file");
+            assert_eq!(compression, compression::Format::Lzma);
+        }
+
+        #[test]
+        fn lzma_alone() {
+            // no magic number, only read on request or for `.lzma` files
+            let (_, compression) = sniff(Box::new(LZMA_ALONE_FILE)).expect("Error in read file");
+            assert_eq!(compression, compression::Format::No);
+        }
+
+        #[test]
+        fn zstd() {
+            let (_, compression) = sniff(Box::new(ZSTD_FILE)).expect("Error in read file");
+            assert_eq!(compression, compression::Format::Zstd);
+        }
+
+        #[test]
+        fn lz4() {
+            let (_, compression) = sniff(Box::new(LZ4_FILE)).expect("Error in read file");
+            assert_eq!(compression, compression::Format::Lz4);
+
+            let (_, compression) = sniff(Box::new(LZ4_LEGACY_FILE)).expect("Error in read file");
+            assert_eq!(compression, compression::Format::Lz4);
+        }
+
+        #[test]
+        fn snappy() {
+            let (_, compression) = sniff(Box::new(SNAPPY_FILE)).expect("Error in read file");
+            assert_eq!(compression, compression::Format::Snappy);
+        }
+
+        #[test]
+        fn lzip() {
+            let (_, compression) = sniff(Box::new(LZIP_FILE)).expect("Error in read file");
+            assert_eq!(compression, compression::Format::Lzip);
+        }
+
+        #[test]
+        fn compress() {
+            let (_, compression) = sniff(Box::new(COMPRESS_FILE)).expect("Error in read file");
+            assert_eq!(compression, compression::Format::Compress);
+        }
+
+        #[test]
+        fn lzo() {
+            let (_, compression) = sniff(Box::new(LZO_FILE)).expect("Error in read file");
+            assert_eq!(compression, compression::Format::Lzo);
+        }
+
+        #[test]
+        fn zlib() {
+            // the header matches some text, only read on request or for `.zz` files
+            let (_, compression) = sniff(Box::new(ZLIB_FILE)).expect("Error in read file");
+            assert_eq!(compression, compression::Format::No);
+        }
+
+        #[test]
+        fn zlib_like_text() {
+            let text = b"x^2 + y^2 = z^2 is the equation\n";
+            let (mut reader, compression) =
+                get_reader(Box::new(&text[..])).expect("Error in read file");
+            assert_eq!(compression, compression::Format::No);
+
+            let mut contents = Vec::new();
+            reader.read_to_end(&mut contents).unwrap();
+            assert_eq!(contents, text);
+        }
+
+        #[test]
+        fn zip() {
+            let (_, compression) = sniff(Box::new(ZIP_FILE)).expect("Error in read file");
+            assert_eq!(compression, compression::Format::Zip);
+        }
+
+        #[test]
+        fn zstd_skippable() {
+            let (_, compression) =
+                sniff(Box::new(ZSTD_SKIPPABLE_FILE)).expect("Error in read file");
+            assert_eq!(compression, compression::Format::Zstd);
+        }
+
+        #[test]
+        fn lz4_skippable() {
+            let (_, compression) = sniff(Box::new(LZ4_SKIPPABLE_FILE)).expect("Error in read file");
+            assert_eq!(compression, compression::Format::Lz4);
+        }
+
+        #[test]
+        fn zstd_legacy() {
+            let (_, compression) = sniff(Box::new(ZSTD_LEGACY_FILE)).expect("Error in read file");
+            assert_eq!(compression, compression::Format::Zstd);
+        }
+
+        #[test]
+        fn detect() {
+            assert_eq!(
+                compression::Format::detect(GZIP_FILE),
+                Some(compression::Format::Gzip)
+            );
+            assert_eq!(compression::Format::detect(&LZO_FILE[..8]), None);
+            assert_eq!(
+                compression::Format::detect(&LZO_FILE[..9]),
+                Some(compression::Format::Lzo)
+            );
+            assert_eq!(compression::Format::detect(&LOREM_IPSUM[..15]), None);
+            assert_eq!(
+                compression::Format::detect(&LOREM_IPSUM[..16]),
+                Some(compression::Format::No)
+            );
+
+            // the frame after skippable frames gives the format
+            assert_eq!(compression::Format::detect(&LZ4_SKIPPABLE_FILE[..11]), None);
+            assert_eq!(
+                compression::Format::detect(LZ4_SKIPPABLE_FILE),
+                Some(compression::Format::Lz4)
+            );
+        }
+
+        #[test]
+        fn bufread() {
+            let reader = io::BufReader::new(LZO_FILE);
+            let (mut reader, compression) = sniff_bufread(reader).expect("Error in read file");
+            assert_eq!(compression, compression::Format::Lzo);
+
+            // nothing was consumed
+            let mut contents = Vec::new();
+            reader
+                .read_to_end(&mut contents)
+                .expect("Error during reading");
+            assert_eq!(contents, LZO_FILE);
+
+            assert!(matches!(
+                sniff_bufread(SHORT_FILE),
+                Err(Error::FileTooShort)
+            ));
+        }
+
+        #[test]
+        fn bufread_chunked() {
+            /// Reads at most `size` bytes at once.
+            struct Chunked<'a> {
+                data: &'a [u8],
+                size: usize,
+            }
+
+            impl io::Read for Chunked<'_> {
+                fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                    let len = buf.len().min(self.size).min(self.data.len());
+                    buf[..len].copy_from_slice(&self.data[..len]);
+                    self.data = &self.data[len..];
+                    Ok(len)
+                }
+            }
+
+            for (data, format) in [
+                (LZO_FILE, compression::Format::Lzo),
+                (SNAPPY_FILE, compression::Format::Snappy),
+                (LZMA_FILE, compression::Format::Lzma),
+                (LOREM_IPSUM, compression::Format::No),
+            ] {
+                for size in [3, 8] {
+                    let reader = io::BufReader::new(Chunked { data, size });
+                    let (mut reader, compression) =
+                        sniff_bufread(reader).expect("Error in read file");
+                    assert_eq!(compression, format, "{size} bytes chunks");
+
+                    // the bytes read to find out the format are read again
+                    let mut contents = Vec::new();
+                    reader
+                        .read_to_end(&mut contents)
+                        .expect("Error during reading");
+                    assert_eq!(contents, data);
+                }
+            }
+        }
+
+        #[test]
+        fn too_short() {
+            let result = sniff(Box::new(SHORT_FILE));
+            assert!(result.is_err());
+        }
+
+        #[test]
+        fn no_compression() {
+            let (_, compression) = sniff(Box::new(LOREM_IPSUM)).expect("Error in read file");
+            assert_eq!(compression, compression::Format::No);
+        }
+
+        #[test]
+        fn binary() {
+            // xorshift generator, for the same bytes on each run
+            let mut state = 0x2545_f491_4f6c_dd1d_u64;
+            let random = (0..4096)
+                .map(|_| {
+                    state ^= state << 13;
+                    state ^= state >> 7;
+                    state ^= state << 17;
+                    state as u8
+                })
+                .collect::<Vec<u8>>();
+
+            for input in [&[0; 13][..], &[0; 64], &random] {
+                let (mut reader, compression) =
+                    get_reader(Box::new(input)).expect("Error in read file");
+                assert_eq!(compression, compression::Format::No);
+
+                let mut contents = Vec::new();
+                reader.read_to_end(&mut contents).unwrap();
+                assert_eq!(contents, input);
+            }
+        }
+    }
+
+    mod content_detection {
+        use super::*;
+
+        #[cfg(feature = "gz")]
+        #[test]
+        fn bam() {
+            let mut buffer = vec![];
+            {
+                let mut writer =
+                    get_writer(Box::new(&mut buffer), compression::Format::Gzip, Level::One)
+                        .unwrap();
+                writer
+                    .write_all(b"BAM\x01\x00\x00\x00\x00")
+                    .expect("Error during write of data");
+            }
+
+            let (reader, format) = get_reader(Box::new(&buffer[..])).expect("Error reading");
+            let (mut reader, content) = sniff_content(reader).expect("Error reading content");
+            assert_eq!(format, compression::Format::Gzip);
+            assert_eq!(content, Content::Bam);
+
+            let mut contents = Vec::new();
+            reader
+                .read_to_end(&mut contents)
+                .expect("Error during reading");
+            assert_eq!(contents, b"BAM\x01\x00\x00\x00\x00");
+        }
+
+        #[test]
+        fn long_stream() {
+            let data = b"@read\nACGT\n+\nIIII\n".repeat(1000);
+            let (mut reader, content) =
+                sniff_content(Box::new(&data[..])).expect("Error reading content");
+            assert_eq!(content, Content::Fastq);
+
+            let mut contents = Vec::new();
+            reader
+                .read_to_end(&mut contents)
+                .expect("Error during reading");
+            assert_eq!(contents, data);
+        }
+
+        #[test]
+        fn open_path() {
+            let file = NamedTempFile::new().expect("Can't create tmpfile");
+            {
+                let mut writer = to_path(file.path(), compression::Format::No, Level::One)
+                    .expect("Error creating file");
+                writer
+                    .write_all(b"chr1\t100\t200\n")
+                    .expect("Error during write of data");
+            }
+
+            let (mut reader, format, content) = open_any(file.path()).expect("Error opening file");
+            assert_eq!(format, compression::Format::No);
+            assert_eq!(content, Content::Bed);
+
+            let mut contents = Vec::new();
+            reader
+                .read_to_end(&mut contents)
+                .expect("Error during reading");
+            assert_eq!(contents, b"chr1\t100\t200\n");
+        }
+    }
+
+    mod extension {
+        use super::*;
+
+        #[test]
+        fn from_extension() {
+            for (extension, format) in [
+                ("gz", Some(compression::Format::Gzip)),
+                ("bgz", Some(compression::Format::Gzip)),
+                ("tar.gz", Some(compression::Format::Gzip)),
+                (".fq.gz", Some(compression::Format::Gzip)),
+                ("bz2", Some(compression::Format::Bzip)),
+                ("xz", Some(compression::Format::Lzma)),
+                ("ZST", Some(compression::Format::Zstd)),
+                ("Z", Some(compression::Format::Compress)),
+                ("z", None),
+                ("fastq", None),
+                ("", None),
+            ] {
+                assert_eq!(compression::Format::from_extension(extension), format);
+            }
+
+            assert_eq!(
+                compression::Format::from_path("data/archive.tar.zst"),
+                Some(compression::Format::Zstd)
+            );
+            assert_eq!(compression::Format::from_path("data/reads"), None);
+        }
+
+        #[cfg(feature = "gz")]
+        #[test]
+        fn auto_format() {
+            let dir = tempfile::tempdir().expect("Can't create tmpdir");
+            let path = dir.path().join("reads.fq.gz");
+            {
+                let mut writer = to_path_auto(&path, Level::One).expect("Error creating file");
+                writer
+                    .write_all(LOREM_IPSUM)
+                    .expect("Error during write of data");
+            }
+
+            let (mut reader, format, mismatch) =
+                from_path_checked(&path, ExtensionCheck::Reject).expect("Error reading file");
+            assert_eq!(format, compression::Format::Gzip);
+            assert_eq!(mismatch, None);
+
+            let mut contents = Vec::new();
+            reader
+                .read_to_end(&mut contents)
+                .expect("Error during reading");
+            assert_eq!(contents, LOREM_IPSUM);
+        }
+
+        #[test]
+        fn mismatch() {
+            // an uncompressed file with a compressed file extension
+            let dir = tempfile::tempdir().expect("Can't create tmpdir");
+            let path = dir.path().join("reads.fq.gz");
+            std::fs::write(&path, LOREM_IPSUM).expect("Error during write of data");
+
+            let (_, format, mismatch) =
+                from_path_checked(&path, ExtensionCheck::Report).expect("Error reading file");
+            assert_eq!(format, compression::Format::No);
+            assert_eq!(
+                mismatch,
+                Some(compression::ExtensionMismatch {
+                    expected: compression::Format::Gzip,
+                    detected: compression::Format::No,
+                })
+            );
+
+            assert!(matches!(
+                from_path_checked(&path, ExtensionCheck::Reject),
+                Err(Error::ExtensionMismatch { .. })
+            ));
+        }
+    }
+
+    mod buffered {
+        use super::*;
+        use std::io::BufRead;
+
+        #[cfg(feature = "gz")]
+        #[test]
+        fn bufread() {
+            let mut buffer = vec![];
+            {
+                let mut writer =
+                    get_writer(Box::new(&mut buffer), compression::Format::Gzip, Level::One)
+                        .unwrap();
+                writer
+                    .write_all(b"@read1\nACGT\n+\nIIII\n")
+                    .expect("Error during write of data");
+            }
+
+            let (reader, format) = get_bufread(Box::new(&buffer[..]), 4).expect("Error reading");
+            assert_eq!(format, compression::Format::Gzip);
+
+            let lines = reader
+                .lines()
+                .collect::<Result<Vec<_>, _>>()
+                .expect("Error during reading");
+            assert_eq!(lines, ["@read1", "ACGT", "+", "IIII"]);
+        }
+
+        #[test]
+        fn path_uncompressed() {
+            let file = NamedTempFile::new().expect("Can't create tmpfile");
+            std::fs::write(file.path(), LOREM_IPSUM).expect("Error during write of data");
+
+            let (reader, format) = from_path_bufread(file.path(), 1).expect("Error reading");
+            assert_eq!(format, compression::Format::No);
+
+            let lines = reader
+                .lines()
+                .collect::<Result<Vec<_>, _>>()
+                .expect("Error during reading");
+            assert_eq!(lines, [String::from_utf8_lossy(LOREM_IPSUM)]);
+        }
+    }
+
+    mod custom_codec {
+        use super::*;
+
+        /// A magic number in front of uncompressed data, the registry is shared with the tests of
+        /// [basic](crate::basic) so its format has another name.
+        struct Tagged;
+
+        impl codec::Codec for Tagged {
+            fn format(&self) -> crate::Format {
+                crate::Format::Custom("sendable")
+            }
+
+            fn matches(&self, first_bytes: &[u8]) -> bool {
+                first_bytes.starts_with(b"SENDABLE")
+            }
+
+            fn decoder<'a>(
+                &self,
+                mut in_stream: Box<dyn io::Read + 'a>,
+            ) -> Result<Box<dyn io::Read + 'a>, Error> {
+                in_stream.read_exact(&mut [0; 8])?;
+                Ok(in_stream)
+            }
+
+            fn encoder<'a>(
+                &self,
+                mut out_stream: Box<dyn io::Write + 'a>,
+                _: Level,
+            ) -> Result<Box<dyn io::Write + 'a>, Error> {
+                out_stream.write_all(b"SENDABLE")?;
+                Ok(out_stream)
+            }
+
+            fn send_decoder<'a>(
+                &self,
+                mut in_stream: Box<dyn io::Read + Send + 'a>,
+            ) -> Result<Box<dyn io::Read + Send + 'a>, Error> {
+                in_stream.read_exact(&mut [0; 8])?;
+                Ok(in_stream)
+            }
+
+            fn send_encoder<'a>(
+                &self,
+                mut out_stream: Box<dyn io::Write + Send + 'a>,
+                _: Level,
+            ) -> Result<Box<dyn io::Write + Send + 'a>, Error> {
+                out_stream.write_all(b"SENDABLE")?;
+                Ok(out_stream)
+            }
+        }
+
+        #[test]
+        fn custom() {
+            codec::register(Tagged);
+
+            let mut buffer = vec![];
+            {
+                let mut writer = get_writer(
+                    Box::new(&mut buffer),
+                    compression::Format::Custom("sendable"),
+                    Level::One,
+                )
+                .expect("Error creating writer");
+                writer
+                    .write_all(LOREM_IPSUM)
+                    .expect("Error during write of data");
+            }
+            assert!(buffer.starts_with(b"SENDABLE"));
+
+            let (mut reader, compression) =
+                get_reader(Box::new(&buffer[..])).expect("Error reading from buffer");
+            assert_eq!(compression, compression::Format::Custom("sendable"));
+
+            let mut contents = Vec::new();
+            reader
+                .read_to_end(&mut contents)
+                .expect("Error during reading");
+            assert_eq!(contents, LOREM_IPSUM);
+
+            // the codec is also used by the functions of basic
+            let (mut reader, compression) =
+                crate::get_reader(Box::new(&buffer[..])).expect("Error reading from buffer");
+            assert_eq!(compression, crate::Format::Custom("sendable"));
+
+            let mut contents = Vec::new();
+            reader
+                .read_to_end(&mut contents)
+                .expect("Error during reading");
+            assert_eq!(contents, LOREM_IPSUM);
+        }
+
+        /// Without sendable streams.
+        struct Unsendable;
+
+        impl codec::Codec for Unsendable {
+            fn format(&self) -> crate::Format {
+                crate::Format::Custom("unsendable")
+            }
+
+            fn matches(&self, first_bytes: &[u8]) -> bool {
+                first_bytes.starts_with(b"UNSENDABLE")
+            }
+
+            fn decoder<'a>(
+                &self,
+                in_stream: Box<dyn io::Read + 'a>,
+            ) -> Result<Box<dyn io::Read + 'a>, Error> {
+                Ok(in_stream)
+            }
+
+            fn encoder<'a>(
+                &self,
+                out_stream: Box<dyn io::Write + 'a>,
+                _: Level,
+            ) -> Result<Box<dyn io::Write + 'a>, Error> {
+                Ok(out_stream)
+            }
+        }
+
+        #[test]
+        fn unsendable() {
+            codec::register(Unsendable);
+
+            let (_, compression) =
+                sniff(Box::new(&b"UNSENDABLE data"[..])).expect("Error in read file");
+            assert_eq!(compression, compression::Format::Custom("unsendable"));
+
+            assert!(matches!(
+                get_reader(Box::new(&b"UNSENDABLE data"[..])),
+                Err(Error::UnsupportedFormat { format, feature: None }) if format == crate::Format::Custom("unsendable")
+            ));
+        }
+
+        #[test]
+        fn unknown() {
+            assert!(matches!(
+                get_writer(
+                    Box::new(vec![]),
+                    compression::Format::Custom("unknown"),
+                    Level::One
+                ),
+                Err(Error::UnknownFormat(_))
+            ));
+            assert!(matches!(
+                get_reader_with_format(
+                    Box::new(LOREM_IPSUM),
+                    compression::Format::Custom("unknown")
+                ),
+                Err(Error::UnknownFormat(_))
+            ));
+        }
+    }
+
+    mod errors {
+        use super::*;
+
+        /// Inner error of the error of a reader.
+        #[cfg(any(feature = "gz", feature = "lzma"))]
+        fn read_error(mut reader: Box<dyn io::Read + Send + '_>) -> Error {
+            let mut contents = Vec::new();
+            let error = reader
+                .read_to_end(&mut contents)
+                .expect_err("Corrupted stream is read");
+
+            *error
+                .into_inner()
+                .expect("Error without inner error")
+                .downcast::<Error>()
+                .expect("Inner error isn't a niffler error")
+        }
+
+        #[cfg(feature = "gz")]
+        fn gzip() -> Vec<u8> {
+            let mut buffer = vec![];
+            {
+                let mut writer =
+                    get_writer(Box::new(&mut buffer), compression::Format::Gzip, Level::One)
+                        .unwrap();
+                writer
+                    .write_all(LOREM_IPSUM)
+                    .expect("Error during write of data");
+            }
+
+            buffer
+        }
+
+        #[cfg(not(feature = "gz"))]
+        #[test]
+        fn unsupported_format() {
+            assert!(matches!(
+                get_writer(Box::new(vec![]), compression::Format::Gzip, Level::One),
+                Err(Error::UnsupportedFormat { format, feature: Some("gz") }) if format == crate::Format::Gzip
+            ));
+        }
+
+        #[cfg(feature = "gz")]
+        #[test]
+        fn corrupt_data() {
+            let mut buffer = gzip();
+            // the first deflate block has the reserved block type
+            buffer[10] = 0x07;
+
+            let (reader, _) = get_reader(Box::new(io::Cursor::new(buffer))).expect("Error reading");
+            assert!(matches!(
+                read_error(reader),
+                Error::CorruptData { format, compressed_offset } if format == crate::Format::Gzip && compressed_offset > 10
+            ));
+        }
+
+        #[cfg(feature = "gz")]
+        #[test]
+        fn checksum_mismatch() {
+            let mut buffer = gzip();
+            let crc = buffer.len() - 8;
+            buffer[crc] ^= 0xff;
+
+            let (reader, _) = get_reader(Box::new(io::Cursor::new(buffer))).expect("Error reading");
+            assert!(matches!(
+                read_error(reader),
+                Error::ChecksumMismatch { format } if format == crate::Format::Gzip
+            ));
+        }
+
+        #[cfg(feature = "lzma")]
+        #[test]
+        fn truncated() {
+            let mut buffer = vec![];
+            {
+                let mut writer =
+                    get_writer(Box::new(&mut buffer), compression::Format::Lzma, Level::One)
+                        .unwrap();
+                writer
+                    .write_all(LOREM_IPSUM)
+                    .expect("Error during write of data");
+            }
+            buffer.truncate(buffer.len() / 2);
+
+            let (reader, _) = get_reader(Box::new(io::Cursor::new(buffer))).expect("Error reading");
+            assert!(matches!(
+                read_error(reader),
+                Error::Truncated { format, .. } if format == crate::Format::Lzma
+            ));
+        }
+
+        #[cfg(feature = "zstd")]
+        #[test]
+        fn input_error() {
+            struct Failing;
+
+            impl io::Read for Failing {
+                fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                    Err(io::Error::other("connection reset"))
+                }
+            }
+
+            // errors of the compressed stream aren't decoding errors
+            let input = io::Cursor::new(ZSTD_FILE).chain(Failing);
+            let (mut reader, _) =
+                get_reader_with_format(Box::new(input), compression::Format::Zstd)
+                    .expect("Error reading");
+            let error = reader.read_to_end(&mut vec![]).unwrap_err();
+            assert_eq!(error.to_string(), "connection reset");
+        }
+
+        #[test]
+        fn path() {
+            let dir = tempfile::tempdir().expect("Can't create tmpdir");
+            let path = dir.path().join("missing.fq.gz");
+
+            assert!(matches!(
+                from_path(&path),
+                Err(Error::PathIOError { path: error_path, source })
+                    if error_path == path && source.kind() == io::ErrorKind::NotFound
+            ));
+            assert!(matches!(
+                to_path(
+                    dir.path().join("missing/reads.fq"),
+                    compression::Format::No,
+                    Level::One
+                ),
+                Err(Error::PathIOError { .. })
+            ));
+        }
+    }
+
+    #[cfg(any(
+        feature = "gz",
+        feature = "bz2",
+        feature = "lzma",
+        feature = "zstd",
+        feature = "lz4",
+        feature = "brotli",
+        feature = "snappy",
+        feature = "lzip",
+        feature = "compress",
+        feature = "lzo",
+        feature = "zip"
+    ))]
+    mod truncated {
+        use super::*;
+
+        fn compressed(format: compression::Format) -> Vec<u8> {
+            let mut buffer = vec![];
+            {
+                let mut writer = get_writer(Box::new(&mut buffer), format, Level::One).unwrap();
+                writer
+                    .write_all(LOREM_IPSUM)
+                    .expect("Error during write of data");
+            }
+
+            buffer
+        }
+
+        /// Check the error of reading `format` cut after `cut` bytes.
+        fn assert_truncated_at(format: compression::Format, buffer: &[u8], cut: usize) {
+            let input = Box::new(&buffer[..cut]);
+            let reader = if format.to_basic().is_undetectable() {
+                get_reader_with_format(input, format)
+            } else {
+                get_reader(input)
+            };
+            let error = match reader {
+                Ok((mut reader, _)) => {
+                    let mut contents = Vec::new();
+                    let error = reader
+                        .read_to_end(&mut contents)
+                        .expect_err("Truncated stream is read");
+                    *error
+                        .into_inner()
+                        .expect("Error without inner error")
+                        .downcast::<Error>()
+                        .expect("Inner error isn't a niffler error")
+                }
+                Err(error) => error,
+            };
+
+            assert!(
+                matches!(&error, Error::Truncated { format: name, .. } if *name == format.to_basic()),
+                "{format:?} cut at {cut}: {error}"
+            );
+        }
+
+        /// Check the errors of reading `format` cut at the middle and before its last byte.
+        #[cfg(any(
+            feature = "gz",
+            feature = "bz2",
+            feature = "lzma",
+            feature = "zstd",
+            feature = "lz4",
+            feature = "brotli",
+            feature = "snappy",
+            feature = "lzip",
+            feature = "lzo",
+            feature = "zip"
+        ))]
+        fn assert_truncated(format: compression::Format) {
+            let buffer = compressed(format);
+            assert_truncated_at(format, &buffer, buffer.len() / 2);
+            assert_truncated_at(format, &buffer, buffer.len() - 1);
+        }
+
+        #[cfg(feature = "gz")]
+        #[test]
+        fn gzip() {
+            assert_truncated(compression::Format::Gzip);
+        }
+
+        #[cfg(feature = "gz")]
+        #[test]
+        fn zlib() {
+            assert_truncated(compression::Format::Zlib);
+        }
+
+        #[cfg(feature = "gz")]
+        #[test]
+        fn deflate() {
+            assert_truncated(compression::Format::Deflate);
+        }
+
+        #[cfg(feature = "bz2")]
+        #[test]
+        fn bzip() {
+            assert_truncated(compression::Format::Bzip);
+        }
+
+        #[cfg(feature = "lzma")]
+        #[test]
+        fn lzma() {
+            assert_truncated(compression::Format::Lzma);
+            assert_truncated(compression::Format::LzmaAlone);
+        }
+
+        #[cfg(feature = "zstd")]
+        #[test]
+        fn zstd() {
+            assert_truncated(compression::Format::Zstd);
+        }
+
+        #[cfg(feature = "lz4")]
+        #[test]
+        fn lz4() {
+            assert_truncated(compression::Format::Lz4);
+        }
+
+        #[cfg(feature = "brotli")]
+        #[test]
+        fn brotli() {
+            assert_truncated(compression::Format::Brotli);
+        }
+
+        #[cfg(feature = "snappy")]
+        #[test]
+        fn snappy() {
+            assert_truncated(compression::Format::Snappy);
+        }
+
+        #[cfg(feature = "lzip")]
+        #[test]
+        fn lzip() {
+            assert_truncated(compression::Format::Lzip);
+        }
+
+        #[cfg(feature = "compress")]
+        #[test]
+        fn compress() {
+            // without end marker, only streams cut inside a code are truncated, the
+            // 9 bits codes after the 3 bytes header are cut after 1 + 9n bytes
+            let buffer = compressed(compression::Format::Compress);
+            for cut in [13, 49, 103] {
+                assert_truncated_at(compression::Format::Compress, &buffer, cut);
+            }
+        }
+
+        #[cfg(feature = "lzo")]
+        #[test]
+        fn lzo() {
+            assert_truncated(compression::Format::Lzo);
+        }
+
+        #[cfg(feature = "zip")]
+        #[test]
+        fn zip() {
+            assert_truncated(compression::Format::Zip);
+        }
+
+        #[cfg(feature = "gz")]
+        #[test]
+        fn decompressed() {
+            let mut buffer = compressed(compression::Format::Gzip);
+            // only the size of the data is missing
+            buffer.pop();
+
+            let (mut reader, _) = get_reader(Box::new(&buffer[..])).expect("Error reading");
+            let error = reader.read_to_end(&mut vec![]).unwrap_err();
+            assert_eq!(
+                error.to_string(),
+                format!(
+                    "Gzip stream is truncated, after {} decompressed bytes",
+                    LOREM_IPSUM.len()
+                )
+            );
         }
     }
 }
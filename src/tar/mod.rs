@@ -0,0 +1,263 @@
+//! Read and write tar archives compressed with any format supported by niffler.
+//!
+//! Archives and builders come from the [tar](https://lib.rs/crates/tar) crate,
+//! niffler handles the compression layer and checks that the decompressed stream
+//! is a tar archive.
+
+#[deprecated(
+    note = "build a `tar::Archive` or `tar::Builder` over the readers and writers of `generic`, they are `Send` when their stream is"
+)]
+pub mod send;
+
+/* standard use */
+use std::io;
+use std::io::Read;
+use std::path::Path;
+
+/* crates use */
+pub use ::tar::{Archive, Builder, Entries, Entry, EntryType, Header};
+
+/* project use */
+use crate::basic::compression::Format;
+use crate::error::Error;
+use crate::level::Level;
+
+const BLOCK_SIZE: usize = 512;
+
+/// Check if a decompressed stream starts with a tar header, from its first 512 bytes.
+///
+/// POSIX ustar and GNU tar archives have a `ustar` magic at offset 257, archives
+/// written by the original Unix tar have no magic and can't be detected.
+pub fn is_tar(block: &[u8]) -> bool {
+    block.get(..BLOCK_SIZE).is_some_and(|block| {
+        let header = Header::from_byte_slice(block);
+        header.as_ustar().is_some() || header.as_gnu().is_some()
+    })
+}
+
+/// Decompress a stream and read it as a tar archive.
+///
+/// Return the archive and the compression format of the stream, this fails with
+/// [Error::NotAnArchive] if the decompressed stream isn't a tar archive.
+///
+/// # Example
+/// ```
+/// use std::io::Read;
+/// use niffler::{Error, compression};
+/// # fn main() -> Result<(), Error> {
+///
+/// # #[cfg(feature = "gz")] {
+/// let mut buffer = vec![];
+/// {
+///   let mut builder = niffler::tar::get_builder(Box::new(&mut buffer), compression::Format::Gzip, niffler::Level::One)?;
+///   let mut header = niffler::tar::Header::new_gnu();
+///   header.set_size(5);
+///   header.set_cksum();
+///   builder.append_data(&mut header, "hello.txt", &b"hello"[..])?;
+///   builder.finish()?;
+/// }
+///
+/// let (mut archive, format) = niffler::tar::get_archive(Box::new(&buffer[..]))?;
+/// assert_eq!(format, compression::Format::Gzip);
+///
+/// for entry in archive.entries()? {
+///   let mut entry = entry?;
+///   let mut contents = String::new();
+///   entry.read_to_string(&mut contents)?;
+///
+///   assert_eq!(entry.path()?.to_str(), Some("hello.txt"));
+///   assert_eq!(contents, "hello");
+/// }
+/// # }
+/// # Ok(())
+/// # }
+/// ```
+pub fn get_archive<'a>(
+    in_stream: Box<dyn io::Read + 'a>,
+) -> Result<(Archive<Box<dyn io::Read + 'a>>, Format), Error> {
+    let (reader, format) = crate::get_reader(in_stream)?;
+
+    Ok((Archive::new(Box::new(check_tar(reader)?)), format))
+}
+
+/// Open a possibly compressed tar archive.
+pub fn from_path<'a, P: AsRef<Path>>(
+    path: P,
+) -> Result<(Archive<Box<dyn io::Read + 'a>>, Format), Error> {
+    let (reader, format) = crate::from_path(path)?;
+
+    Ok((Archive::new(Box::new(check_tar(reader)?)), format))
+}
+
+/// Create a tar archive builder, writing to a stream compressed with the given format and level.
+///
+/// The end of the archive is written by [Builder::finish] or when the builder is dropped,
+/// the compressed stream is finished when it's dropped.
+pub fn get_builder<'a>(
+    out_stream: Box<dyn io::Write + 'a>,
+    format: Format,
+    level: Level,
+) -> Result<Builder<Box<dyn io::Write + 'a>>, Error> {
+    Ok(Builder::new(crate::get_writer(out_stream, format, level)?))
+}
+
+/// Create a tar archive file compressed with the given format and level.
+pub fn to_path<'a, P: AsRef<Path>>(
+    path: P,
+    format: Format,
+    level: Level,
+) -> Result<Builder<Box<dyn io::Write + 'a>>, Error> {
+    Ok(Builder::new(crate::to_path(path, format, level)?))
+}
+
+/// Check that a decompressed stream is a tar archive, from its first block.
+pub(crate) fn check_tar<R: Read>(
+    mut reader: R,
+) -> Result<io::Chain<io::Cursor<Vec<u8>>, R>, Error> {
+    let mut block = Vec::with_capacity(BLOCK_SIZE);
+    (&mut reader)
+        .take(BLOCK_SIZE as u64)
+        .read_to_end(&mut block)?;
+    if !is_tar(&block) {
+        return Err(Error::NotAnArchive);
+    }
+
+    Ok(io::Cursor::new(block).chain(reader))
+}
+
+/// Skip the entries of a decompressed tar archive until the file called `name`, and return
+/// a reader of its content.
+pub(crate) fn find_entry<R: Read>(reader: R, name: &str) -> Result<io::Take<R>, Error> {
+    let mut archive = Archive::new(reader);
+
+    let mut size = None;
+    for entry in archive.entries()? {
+        let entry = entry?;
+        let is_file = matches!(
+            entry.header().entry_type(),
+            EntryType::Regular | EntryType::Continuous
+        );
+        if is_file && trim_current_dir(&entry.path_bytes()) == trim_current_dir(name.as_bytes()) {
+            size = Some(entry.size());
+            break;
+        }
+    }
+    let size = size.ok_or_else(|| Error::EntryNotFound(name.to_string()))?;
+
+    // entries are read in order from the stream, it's at the start of the content of this one
+    Ok(archive.into_inner().take(size))
+}
+
+/// Names of entries can start with `./`, `tar -C dir .` writes them this way.
+fn trim_current_dir(mut name: &[u8]) -> &[u8] {
+    while let Some(rest) = name.strip_prefix(b"./") {
+        name = rest;
+    }
+
+    name
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[cfg(any(feature = "gz", feature = "zstd"))]
+    fn tarball(format: Format) -> Vec<u8> {
+        let mut buffer = vec![];
+        {
+            let mut builder = get_builder(Box::new(&mut buffer), format, Level::One)
+                .expect("Can't create builder");
+            for (name, data) in [
+                ("reads/a.fastq", &b"@a\nACGT\n+\nIIII\n"[..]),
+                ("reads/b.fastq", b"@b\nTTTT\n+\nIIII\n"),
+            ] {
+                let mut header = Header::new_gnu();
+                header.set_size(data.len() as u64);
+                header.set_mode(0o644);
+                header.set_cksum();
+                builder
+                    .append_data(&mut header, name, data)
+                    .expect("Error during write of data");
+            }
+            builder.finish().expect("Error during write of data");
+        }
+
+        buffer
+    }
+
+    #[cfg(feature = "gz")]
+    #[test]
+    fn entries() {
+        let buffer = tarball(Format::Gzip);
+        let (mut archive, format) =
+            get_archive(Box::new(&buffer[..])).expect("Error reading archive");
+
+        assert_eq!(format, Format::Gzip);
+
+        let mut names = vec![];
+        for entry in archive.entries().expect("Error reading archive") {
+            let mut entry = entry.expect("Error reading entry");
+            let mut contents = vec![];
+            entry
+                .read_to_end(&mut contents)
+                .expect("Error during reading");
+
+            names.push(entry.path().unwrap().to_string_lossy().into_owned());
+            assert_eq!(contents.len(), 15);
+        }
+        assert_eq!(names, ["reads/a.fastq", "reads/b.fastq"]);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn entry() {
+        let buffer = tarball(Format::Zstd);
+        let (mut reader, format) = crate::get_entry_reader(Box::new(&buffer[..]), "reads/b.fastq")
+            .expect("Error reading entry");
+
+        assert_eq!(format, Format::Zstd);
+
+        let mut contents = vec![];
+        reader
+            .read_to_end(&mut contents)
+            .expect("Error during reading");
+        assert_eq!(contents, b"@b\nTTTT\n+\nIIII\n");
+    }
+
+    #[test]
+    fn long_names() {
+        let name = format!("{}/reads.fastq", "a".repeat(150));
+        let mut buffer = vec![];
+        {
+            let mut builder = get_builder(Box::new(&mut buffer), Format::No, Level::One).unwrap();
+            let mut header = Header::new_gnu();
+            header.set_size(5);
+            builder
+                .append_data(&mut header, &name, &b"hello"[..])
+                .unwrap();
+        }
+
+        let (mut reader, _) =
+            crate::get_entry_reader(Box::new(&buffer[..]), &name).expect("Error reading entry");
+        let mut contents = vec![];
+        reader
+            .read_to_end(&mut contents)
+            .expect("Error during reading");
+        assert_eq!(contents, b"hello");
+
+        assert!(matches!(
+            crate::get_entry_reader(Box::new(&buffer[..]), "reads.fastq"),
+            Err(Error::EntryNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn not_tar() {
+        assert!(matches!(
+            get_archive(Box::new(
+                &b"I'm not a tar archive, even if I'm a bit longer"[..]
+            )),
+            Err(Error::NotAnArchive)
+        ));
+    }
+}
@@ -0,0 +1,84 @@
+// sendable archives use the formats and functions of the deprecated send module
+#![allow(deprecated)]
+
+/* standard use */
+use std::io;
+use std::path::Path;
+
+/* project use */
+use crate::error::Error;
+use crate::level::Level;
+use crate::send::compression::Format;
+use crate::tar::{check_tar, Archive, Builder};
+
+/// Similar to [get_archive](crate::tar::get_archive) but readable stream is now sendable
+pub fn get_archive<'a>(
+    in_stream: Box<dyn io::Read + Send + 'a>,
+) -> Result<(Archive<Box<dyn io::Read + Send + 'a>>, Format), Error> {
+    let (reader, format) = crate::send::get_reader(in_stream)?;
+
+    Ok((Archive::new(Box::new(check_tar(reader)?)), format))
+}
+
+/// Similar to [from_path](crate::tar::from_path) but readable stream is now sendable
+pub fn from_path<'a, P: AsRef<Path>>(
+    path: P,
+) -> Result<(Archive<Box<dyn io::Read + Send + 'a>>, Format), Error> {
+    let (reader, format) = crate::send::from_path(path)?;
+
+    Ok((Archive::new(Box::new(check_tar(reader)?)), format))
+}
+
+/// Similar to [get_builder](crate::tar::get_builder) but writable stream is now sendable
+pub fn get_builder<'a>(
+    out_stream: Box<dyn io::Write + Send + 'a>,
+    format: Format,
+    level: Level,
+) -> Result<Builder<Box<dyn io::Write + Send + 'a>>, Error> {
+    Ok(Builder::new(crate::send::get_writer(
+        out_stream, format, level,
+    )?))
+}
+
+/// Similar to [to_path](crate::tar::to_path) but writable stream is now sendable
+pub fn to_path<'a, P: AsRef<Path>>(
+    path: P,
+    format: Format,
+    level: Level,
+) -> Result<Builder<Box<dyn io::Write + Send + 'a>>, Error> {
+    Ok(Builder::new(crate::send::to_path(path, format, level)?))
+}
+
+#[cfg(all(test, feature = "bz2"))]
+mod test {
+    use super::*;
+    use crate::tar::Header;
+    use std::io::Read;
+
+    #[test]
+    fn bzip_tarball() {
+        let mut buffer = vec![];
+        {
+            let mut builder = get_builder(Box::new(&mut buffer), Format::Bzip, Level::One)
+                .expect("Can't create builder");
+            let mut header = Header::new_ustar();
+            header.set_size(5);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, "hello.txt", &b"hello"[..])
+                .expect("Error during write of data");
+        }
+
+        let (mut archive, format) =
+            get_archive(Box::new(io::Cursor::new(buffer))).expect("Error reading archive");
+        assert_eq!(format, Format::Bzip);
+
+        let mut entries = archive.entries().expect("Error reading archive");
+        let mut entry = entries.next().unwrap().expect("Error reading entry");
+        let mut contents = String::new();
+        entry
+            .read_to_string(&mut contents)
+            .expect("Error during reading");
+        assert_eq!(contents, "hello");
+    }
+}
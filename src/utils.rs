@@ -1,19 +1,59 @@
 /* standard use */
 use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
 
 /* project use */
+use crate::basic::compression::Format;
 use crate::error::Error;
 use crate::seek::compression::ReadSeek;
 
-pub fn get_first_five<'a, T>(mut in_stream: T) -> Result<([u8; 5], T), Error>
+/// Number of bytes read from the start of a stream to find out its format.
+pub const MAGIC_LEN: usize = 16;
+
+/// Read up to [MAGIC_LEN] bytes from the stream, stopping early only at end of stream.
+///
+/// Streams shorter than five bytes can't be recognized and are rejected.
+pub fn get_first_bytes_read<'a, T>(in_stream: T) -> Result<(Vec<u8>, T), Error>
 where
     T: io::Read + 'a,
 {
-    let mut buf = [0u8; 5];
-    match in_stream.read_exact(&mut buf) {
-        Ok(()) => Ok((buf, in_stream)),
-        Err(_) => Err(Error::FileTooShort),
+    let (buf, in_stream) = read_first_bytes(in_stream)?;
+
+    if buf.len() < 5 {
+        return Err(Error::FileTooShort);
     }
+
+    Ok((buf, in_stream))
+}
+
+/// Read up to [MAGIC_LEN] bytes from the stream, the result is shorter only if the stream is.
+pub fn read_first_bytes<T>(in_stream: T) -> io::Result<(Vec<u8>, T)>
+where
+    T: io::Read,
+{
+    read_up_to(in_stream, MAGIC_LEN)
+}
+
+/// Read up to `len` bytes from the stream, the result is shorter only if the stream is.
+pub fn read_up_to<T>(mut in_stream: T, len: usize) -> io::Result<(Vec<u8>, T)>
+where
+    T: io::Read,
+{
+    let mut buf = vec![0u8; len];
+    let mut filled = 0;
+    while filled < len {
+        match in_stream.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    buf.truncate(filled);
+    Ok((buf, in_stream))
 }
 
 pub fn get_first_bytes<'a, T>(in_stream: &mut T) -> Result<[u8; 17], Error>
@@ -30,3 +70,127 @@ where
         Err(_) => Err(Error::FileTooShort),
     }
 }
+
+/// Open a file, errors give its path.
+pub(crate) fn open_file(path: &Path) -> Result<std::fs::File, Error> {
+    std::fs::File::open(path).map_err(|source| Error::PathIOError {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Create a file, errors give its path.
+pub(crate) fn create_file(path: &Path) -> Result<std::fs::File, Error> {
+    std::fs::File::create(path).map_err(|source| Error::PathIOError {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Bytes read from a compressed stream by a [Counter].
+#[derive(Debug, Default)]
+pub(crate) struct Consumed {
+    bytes: AtomicU64,
+    failed: AtomicBool,
+}
+
+impl Consumed {
+    pub(crate) fn bytes(&self) -> u64 {
+        self.bytes.load(Ordering::Relaxed)
+    }
+
+    /// The compressed stream returned an error, errors of its decoder aren't decoding errors.
+    pub(crate) fn failed(&self) -> bool {
+        self.failed.load(Ordering::Relaxed)
+    }
+}
+
+/// Count the bytes read from a compressed stream.
+pub(crate) struct Counter<R> {
+    inner: R,
+    consumed: Arc<Consumed>,
+}
+
+impl<R: io::Read> Counter<R> {
+    pub(crate) fn new(inner: R, consumed: Arc<Consumed>) -> Self {
+        Counter { inner, consumed }
+    }
+}
+
+impl<R: io::Read> io::Read for Counter<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.inner.read(buf) {
+            Ok(len) => {
+                self.consumed.bytes.fetch_add(len as u64, Ordering::Relaxed);
+                Ok(len)
+            }
+            Err(e) => {
+                if e.kind() != io::ErrorKind::Interrupted {
+                    self.consumed.failed.store(true, Ordering::Relaxed);
+                }
+                Err(e)
+            }
+        }
+    }
+}
+
+/// The error of the decoder of `format` as [Error::Truncated], [Error::ChecksumMismatch] or
+/// [Error::CorruptData], `None` for other errors. The decoder returned `decompressed` bytes.
+pub(crate) fn decoding_error(
+    error: &io::Error,
+    format: Format,
+    consumed: &Consumed,
+    decompressed: u64,
+) -> Option<Error> {
+    if consumed.failed() || error.get_ref().is_some_and(|inner| inner.is::<Error>()) {
+        return None;
+    }
+
+    if error.kind() == io::ErrorKind::UnexpectedEof {
+        return Some(Error::Truncated {
+            format,
+            decompressed,
+        });
+    }
+
+    #[allow(unreachable_patterns)]
+    let checksum = match format {
+        #[cfg(feature = "gz")]
+        Format::Gzip => crate::formats::checksum::is_gzip_mismatch(error),
+        // the decoders of niffler fail with ChecksumMismatch, returned above
+        Format::Lzo | Format::Zip => false,
+        // the decoders of flate2, bzip2 and liblzma fail with the same error on corrupted data
+        Format::Zlib | Format::Bzip | Format::Lzma | Format::LzmaAlone | Format::Lzip => false,
+        #[cfg(feature = "zstd")]
+        Format::Zstd => crate::formats::checksum::is_zstd_mismatch(error),
+        #[cfg(feature = "lz4")]
+        Format::Lz4 => crate::formats::checksum::is_lz4_mismatch(error),
+        #[cfg(feature = "snappy")]
+        Format::Snappy => crate::formats::checksum::is_snappy_mismatch(error),
+        // without checksum, or from codecs that can fail with ChecksumMismatch themselves
+        Format::Deflate | Format::Brotli | Format::Compress | Format::No | Format::Custom(_) => {
+            false
+        }
+        // decoders of disabled features don't exist
+        _ => false,
+    };
+
+    match error.kind() {
+        _ if checksum => Some(Error::ChecksumMismatch { format }),
+        io::ErrorKind::InvalidData | io::ErrorKind::InvalidInput | io::ErrorKind::Other => {
+            Some(Error::CorruptData {
+                format,
+                compressed_offset: consumed.bytes(),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// [decoding_error] of the errors returned when a decoder is created.
+pub(crate) fn decoder_error(error: Error, format: Format, consumed: &Consumed) -> Error {
+    match error {
+        Error::IOError(e) => decoding_error(&e, format, consumed, 0).unwrap_or(Error::IOError(e)),
+        e => e,
+    }
+}